@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use prost::Message;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -189,14 +191,10 @@ impl Client {
 
     pub async fn attach_terminal(
         mut self,
-        server_dir: impl Into<String>,
+        request: AttachTerminalRequest,
     ) -> Result<(TerminalReader, TerminalWriter), Error> {
         let response_payload = self
-            .send_request(RequestPayload::AttachTerminalRequest(
-                AttachTerminalRequest {
-                    server_dir: server_dir.into(),
-                },
-            ))
+            .send_request(RequestPayload::AttachTerminalRequest(request))
             .await?;
 
         let (stream_reader, stream_writer) = self.stream.into_split();
@@ -231,6 +229,176 @@ impl Client {
         }
     }
 
+    pub async fn register_server(&mut self, server_dir: impl Into<String>) -> Result<(), Error> {
+        let response_payload = self
+            .send_request(RequestPayload::RegisterServerRequest(
+                RegisterServerRequest {
+                    server_dir: server_dir.into(),
+                },
+            ))
+            .await?;
+
+        match response_payload {
+            None => Ok(()),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "RegisterServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn list_servers(&mut self) -> Result<Vec<ServerInfo>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ListServersRequest(ListServersRequest {}))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ListServersResponse(ListServersResponse { servers })) => {
+                Ok(servers)
+            }
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ListServersResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn delete_server(
+        &mut self,
+        server_dir: impl Into<String>,
+        keep_world: bool,
+    ) -> Result<(), Error> {
+        let response_payload = self
+            .send_request(RequestPayload::DeleteServerRequest(DeleteServerRequest {
+                server_dir: server_dir.into(),
+                keep_world,
+            }))
+            .await?;
+
+        match response_payload {
+            None => Ok(()),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "DeleteServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn clone_server(
+        &mut self,
+        server_dir: impl Into<String>,
+        destination_dir: impl Into<String>,
+        name: impl Into<String>,
+        hostname: Option<String>,
+    ) -> Result<(), Error> {
+        let response_payload = self
+            .send_request(RequestPayload::CloneServerRequest(CloneServerRequest {
+                server_dir: server_dir.into(),
+                destination_dir: destination_dir.into(),
+                name: name.into(),
+                hostname,
+            }))
+            .await?;
+
+        match response_payload {
+            None => Ok(()),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "CloneServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn move_server(
+        &mut self,
+        server_dir: impl Into<String>,
+        destination_dir: impl Into<String>,
+    ) -> Result<(), Error> {
+        let response_payload = self
+            .send_request(RequestPayload::MoveServerRequest(MoveServerRequest {
+                server_dir: server_dir.into(),
+                destination_dir: destination_dir.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            None => Ok(()),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "MoveServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn import_server(
+        &mut self,
+        server_dir: impl Into<String>,
+        name: impl Into<String>,
+        connection: ConnectionType,
+        hostname: Option<String>,
+        jar: Option<String>,
+    ) -> Result<ImportServerResponse, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ImportServerRequest(ImportServerRequest {
+                server_dir: server_dir.into(),
+                name: name.into(),
+                connection: connection as i32,
+                hostname,
+                jar,
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ImportServerResponse(result)) => Ok(result),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ImportServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn adopt_extensions(
+        &mut self,
+        server_dir: impl Into<String>,
+        auto_update: bool,
+    ) -> Result<AdoptExtensionsResponse, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::AdoptExtensionsRequest(
+                AdoptExtensionsRequest {
+                    server_dir: server_dir.into(),
+                    auto_update,
+                },
+            ))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::AdoptExtensionsResponse(result)) => Ok(result),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "AdoptExtensionsResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    /// Returns the directory of the server with the name, ID or unique ID prefix.
+    pub async fn resolve_server(&mut self, server: impl Into<String>) -> Result<String, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ResolveServerRequest(ResolveServerRequest {
+                server: server.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ResolveServerResponse(ResolveServerResponse { server_dir })) => {
+                Ok(server_dir)
+            }
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ResolveServerResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
     pub async fn wait_server_ready(&mut self, server_dir: impl Into<String>) -> Result<(), Error> {
         let response_payload = self
             .send_request(RequestPayload::WaitServerReadyRequest(
@@ -249,10 +417,15 @@ impl Client {
         }
     }
 
-    pub async fn restart_server(&mut self, server_dir: impl Into<String>) -> Result<(), Error> {
+    pub async fn restart_server(
+        &mut self,
+        server_dir: impl Into<String>,
+        countdown: Option<Duration>,
+    ) -> Result<(), Error> {
         let response_payload = self
             .send_request(RequestPayload::RestartServerRequest(RestartServerRequest {
                 server_dir: server_dir.into(),
+                countdown_secs: countdown.map(|countdown| countdown.as_secs()),
             }))
             .await?;
 
@@ -433,6 +606,280 @@ impl Client {
             }),
         }
     }
+
+    pub async fn list_backups(
+        &mut self,
+        server_dir: impl Into<String>,
+    ) -> Result<Vec<BackupInfo>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ListBackupsRequest(ListBackupsRequest {
+                server_dir: server_dir.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ListBackupsResponse(result)) => Ok(result.backups),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ListBackupsResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn create_backup(
+        &mut self,
+        server_dir: impl Into<String>,
+    ) -> Result<BackupInfo, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::CreateBackupRequest(CreateBackupRequest {
+                server_dir: server_dir.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::CreateBackupResponse(CreateBackupResponse {
+                backup: Some(backup),
+            })) => Ok(backup),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "CreateBackupResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn restore_backup(
+        &mut self,
+        server_dir: impl Into<String>,
+        backup_id: impl Into<String>,
+    ) -> Result<Option<BackupInfo>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::RestoreBackupRequest(RestoreBackupRequest {
+                server_dir: server_dir.into(),
+                backup_id: backup_id.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::RestoreBackupResponse(result)) => Ok(result.safety_backup),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "RestoreBackupResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn delete_backup(
+        &mut self,
+        server_dir: impl Into<String>,
+        backup_id: impl Into<String>,
+    ) -> Result<(), Error> {
+        let response_payload = self
+            .send_request(RequestPayload::DeleteBackupRequest(DeleteBackupRequest {
+                server_dir: server_dir.into(),
+                backup_id: backup_id.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            None => Ok(()),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "DeleteBackupResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn verify_backups(
+        &mut self,
+        server_dir: impl Into<String>,
+        backup_id: Option<String>,
+    ) -> Result<Vec<BackupVerificationResult>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::VerifyBackupsRequest(VerifyBackupsRequest {
+                server_dir: server_dir.into(),
+                backup_id,
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::VerifyBackupsResponse(result)) => Ok(result.results),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "VerifyBackupsResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn list_schedules(
+        &mut self,
+        server_dir: impl Into<String>,
+    ) -> Result<ListSchedulesResponse, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ListSchedulesRequest(ListSchedulesRequest {
+                server_dir: server_dir.into(),
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ListSchedulesResponse(result)) => Ok(result),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ListSchedulesResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    /// Returns the response text of the command.
+    pub async fn execute_command(
+        &mut self,
+        server_dir: impl Into<String>,
+        command: impl Into<String>,
+    ) -> Result<String, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ExecuteCommandRequest(
+                ExecuteCommandRequest {
+                    server_dir: server_dir.into(),
+                    command: command.into(),
+                },
+            ))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ExecuteCommandResponse(ExecuteCommandResponse { output })) => {
+                Ok(output)
+            }
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ExecuteCommandResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn get_player_sessions(
+        &mut self,
+        request: GetPlayerSessionsRequest,
+    ) -> Result<Vec<PlayerSession>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::GetPlayerSessionsRequest(request))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::GetPlayerSessionsResponse(GetPlayerSessionsResponse {
+                sessions,
+            })) => Ok(sessions),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "GetPlayerSessionsResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn get_player_list(
+        &mut self,
+        server_dir: impl Into<String>,
+        list_type: PlayerListType,
+    ) -> Result<Vec<PlayerListEntry>, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::GetPlayerListRequest(GetPlayerListRequest {
+                server_dir: server_dir.into(),
+                list_type: list_type as i32,
+            }))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::GetPlayerListResponse(GetPlayerListResponse { entries })) => {
+                Ok(entries)
+            }
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "GetPlayerListResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    /// Returns a message describing the result.
+    pub async fn add_player_list_entry(
+        &mut self,
+        request: AddPlayerListEntryRequest,
+    ) -> Result<String, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::AddPlayerListEntryRequest(request))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ModifyPlayerListResponse(ModifyPlayerListResponse {
+                message,
+            })) => Ok(message),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ModifyPlayerListResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    /// Returns a message describing the result.
+    pub async fn remove_player_list_entry(
+        &mut self,
+        server_dir: impl Into<String>,
+        list_type: PlayerListType,
+        player: impl Into<String>,
+    ) -> Result<String, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::RemovePlayerListEntryRequest(
+                RemovePlayerListEntryRequest {
+                    server_dir: server_dir.into(),
+                    list_type: list_type as i32,
+                    player: player.into(),
+                },
+            ))
+            .await?;
+
+        match response_payload {
+            Some(ResponsePayload::ModifyPlayerListResponse(ModifyPlayerListResponse {
+                message,
+            })) => Ok(message),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ModifyPlayerListResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+
+    pub async fn read_logs(mut self, request: ReadLogsRequest) -> Result<LogReader, Error> {
+        let response_payload = self
+            .send_request(RequestPayload::ReadLogsRequest(request))
+            .await?;
+
+        match response_payload {
+            None => Ok(LogReader {
+                stream: self.stream,
+            }),
+            _ => Err(Error::UnexpectedResponseType {
+                expected: "ReadLogsResponse",
+                actual: format!("{response_payload:?}"),
+            }),
+        }
+    }
+}
+
+pub struct LogReader {
+    stream: UnixStream,
+}
+
+impl LogReader {
+    /// Returns `None` once the daemon has sent all lines.
+    pub async fn read(&mut self) -> Result<Option<LogLine>, Error> {
+        let length = match self.stream.read_u32().await {
+            Ok(len) => len,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut buffer = vec![0u8; length as usize];
+        self.stream.read_exact(&mut buffer).await?;
+
+        let line = LogLine::decode(&buffer[..])?;
+        Ok(Some(line))
+    }
 }
 
 pub struct TerminalReader {
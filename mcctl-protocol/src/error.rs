@@ -25,6 +25,8 @@ pub enum Error {
     InvalidUpdateType,
     #[error("Invalid extension type")]
     InvalidExtensionType,
+    #[error("Invalid player list type")]
+    InvalidPlayerListType,
     #[error("Invalid aggregation")]
     InvalidAggregation,
 }
@@ -1,9 +1,13 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use prost::Message;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::{UnixListener, UnixStream},
+    sync::mpsc,
 };
 
 use crate::{
@@ -37,10 +41,17 @@ where
     fn start_server(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
     fn stop_server(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
     fn kill_server(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
-    fn attach_terminal(server_dir: &Path) -> impl Future<Output = Result<(R, W), E>> + Send;
+    fn attach_terminal(
+        server_dir: &Path,
+        scrollback_lines: Option<u32>,
+        read_only: bool,
+    ) -> impl Future<Output = Result<(R, W), E>> + Send;
     fn get_running_servers() -> impl Future<Output = Result<Vec<RunningServer>, E>> + Send;
     fn wait_ready(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
-    fn restart_server(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
+    fn restart_server(
+        server_dir: &Path,
+        countdown: Option<Duration>,
+    ) -> impl Future<Output = Result<(), E>> + Send;
     fn update_server(
         server_dir: &Path,
         update_type: UpdateType,
@@ -83,6 +94,88 @@ where
         limit: Option<u32>,
         offset: Option<u32>,
     ) -> impl Future<Output = Result<GetMetricsResponse, E>> + Send;
+    fn list_backups(server_dir: &Path) -> impl Future<Output = Result<Vec<BackupInfo>, E>> + Send;
+    fn create_backup(server_dir: &Path) -> impl Future<Output = Result<BackupInfo, E>> + Send;
+    fn restore_backup(
+        server_dir: &Path,
+        backup_id: &str,
+    ) -> impl Future<Output = Result<Option<BackupInfo>, E>> + Send;
+    fn delete_backup(
+        server_dir: &Path,
+        backup_id: &str,
+    ) -> impl Future<Output = Result<(), E>> + Send;
+    fn verify_backups(
+        server_dir: &Path,
+        backup_id: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<BackupVerificationResult>, E>> + Send;
+    fn list_schedules(
+        server_dir: &Path,
+    ) -> impl Future<Output = Result<ListSchedulesResponse, E>> + Send;
+    /// The returned channel is streamed to the client until it is closed.
+    fn read_logs(
+        server_dir: &Path,
+        since_timestamp_millis: Option<i64>,
+        lines: Option<u32>,
+        grep: Option<&str>,
+        strip_ansi: bool,
+        follow: bool,
+    ) -> impl Future<Output = Result<mpsc::Receiver<LogLine>, E>> + Send;
+    fn execute_command(
+        server_dir: &Path,
+        command: &str,
+    ) -> impl Future<Output = Result<String, E>> + Send;
+    fn get_player_sessions(
+        server_dir: &Path,
+        online_only: bool,
+        since: Option<i64>,
+        player: Option<&str>,
+    ) -> impl Future<Output = Result<Vec<PlayerSession>, E>> + Send;
+    fn get_player_list(
+        server_dir: &Path,
+        list_type: PlayerListType,
+    ) -> impl Future<Output = Result<Vec<PlayerListEntry>, E>> + Send;
+    /// Returns a message describing the result.
+    fn add_player_list_entry(
+        server_dir: &Path,
+        list_type: PlayerListType,
+        player: &str,
+        reason: Option<&str>,
+    ) -> impl Future<Output = Result<String, E>> + Send;
+    /// Returns a message describing the result.
+    fn remove_player_list_entry(
+        server_dir: &Path,
+        list_type: PlayerListType,
+        player: &str,
+    ) -> impl Future<Output = Result<String, E>> + Send;
+    fn register_server(server_dir: &Path) -> impl Future<Output = Result<(), E>> + Send;
+    fn list_servers() -> impl Future<Output = Result<Vec<ServerInfo>, E>> + Send;
+    /// Returns the directory of the server.
+    fn resolve_server(server: &str) -> impl Future<Output = Result<PathBuf, E>> + Send;
+    fn delete_server(
+        server_dir: &Path,
+        keep_world: bool,
+    ) -> impl Future<Output = Result<(), E>> + Send;
+    fn clone_server(
+        server_dir: &Path,
+        destination_dir: &Path,
+        name: &str,
+        hostname: Option<&str>,
+    ) -> impl Future<Output = Result<(), E>> + Send;
+    fn move_server(
+        server_dir: &Path,
+        destination_dir: &Path,
+    ) -> impl Future<Output = Result<(), E>> + Send;
+    fn import_server(
+        server_dir: &Path,
+        name: &str,
+        connection: ConnectionType,
+        hostname: Option<&str>,
+        jar: Option<&str>,
+    ) -> impl Future<Output = Result<ImportServerResponse, E>> + Send;
+    fn adopt_extensions(
+        server_dir: &Path,
+        auto_update: bool,
+    ) -> impl Future<Output = Result<AdoptExtensionsResponse, E>> + Send;
 }
 
 pub trait TerminalReader<E>: Send + 'static
@@ -225,6 +318,18 @@ where
                 .await?;
                 return Ok(());
             }
+            HandleRequestResult::StreamLogs(log_rx) => {
+                let response = Response { payload: None };
+                let mut response_buf = Vec::new();
+                response.encode(&mut response_buf).unwrap();
+                let response_length = response_buf.len() as u32;
+
+                stream.write_u32(response_length).await?;
+                stream.write_all(&response_buf).await?;
+
+                handle_log_stream(stream, log_rx).await?;
+                return Ok(());
+            }
         };
     }
 
@@ -239,6 +344,7 @@ where
 {
     Response(Option<ResponsePayload>),
     AttachTerminal(R, W, std::marker::PhantomData<E>),
+    StreamLogs(mpsc::Receiver<LogLine>),
 }
 
 #[derive(Debug)]
@@ -325,8 +431,12 @@ where
             Ok(HandleRequestResult::Response(None))
         }
         RequestPayload::AttachTerminalRequest(req) => {
-            let (terminal_reader, terminal_writer) =
-                H::attach_terminal(Path::new(&req.server_dir)).await?;
+            let (terminal_reader, terminal_writer) = H::attach_terminal(
+                Path::new(&req.server_dir),
+                req.scrollback_lines,
+                req.read_only,
+            )
+            .await?;
 
             Ok(HandleRequestResult::AttachTerminal(
                 terminal_reader,
@@ -348,7 +458,11 @@ where
             Ok(HandleRequestResult::Response(None))
         }
         RequestPayload::RestartServerRequest(req) => {
-            H::restart_server(Path::new(&req.server_dir)).await?;
+            H::restart_server(
+                Path::new(&req.server_dir),
+                req.countdown_secs.map(Duration::from_secs),
+            )
+            .await?;
 
             Ok(HandleRequestResult::Response(None))
         }
@@ -447,7 +561,215 @@ where
                 ResponsePayload::GetMetricsResponse(result),
             )))
         }
+        RequestPayload::ListBackupsRequest(req) => {
+            let backups = H::list_backups(Path::new(&req.server_dir)).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ListBackupsResponse(ListBackupsResponse { backups }),
+            )))
+        }
+        RequestPayload::CreateBackupRequest(req) => {
+            let backup = H::create_backup(Path::new(&req.server_dir)).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::CreateBackupResponse(CreateBackupResponse {
+                    backup: Some(backup),
+                }),
+            )))
+        }
+        RequestPayload::RestoreBackupRequest(req) => {
+            let safety_backup =
+                H::restore_backup(Path::new(&req.server_dir), &req.backup_id).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::RestoreBackupResponse(RestoreBackupResponse { safety_backup }),
+            )))
+        }
+        RequestPayload::DeleteBackupRequest(req) => {
+            H::delete_backup(Path::new(&req.server_dir), &req.backup_id).await?;
+
+            Ok(HandleRequestResult::Response(None))
+        }
+        RequestPayload::VerifyBackupsRequest(req) => {
+            let results =
+                H::verify_backups(Path::new(&req.server_dir), req.backup_id.as_deref()).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::VerifyBackupsResponse(VerifyBackupsResponse { results }),
+            )))
+        }
+        RequestPayload::ListSchedulesRequest(req) => {
+            let result = H::list_schedules(Path::new(&req.server_dir)).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ListSchedulesResponse(result),
+            )))
+        }
+        RequestPayload::ReadLogsRequest(req) => {
+            let log_rx = H::read_logs(
+                Path::new(&req.server_dir),
+                req.since_timestamp_millis,
+                req.lines,
+                req.grep.as_deref(),
+                req.strip_ansi,
+                req.follow,
+            )
+            .await?;
+
+            Ok(HandleRequestResult::StreamLogs(log_rx))
+        }
+        RequestPayload::ExecuteCommandRequest(req) => {
+            let output = H::execute_command(Path::new(&req.server_dir), &req.command).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ExecuteCommandResponse(ExecuteCommandResponse { output }),
+            )))
+        }
+        RequestPayload::GetPlayerSessionsRequest(req) => {
+            let sessions = H::get_player_sessions(
+                Path::new(&req.server_dir),
+                req.online_only,
+                req.since,
+                req.player.as_deref(),
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::GetPlayerSessionsResponse(GetPlayerSessionsResponse { sessions }),
+            )))
+        }
+        RequestPayload::GetPlayerListRequest(req) => {
+            let entries = H::get_player_list(
+                Path::new(&req.server_dir),
+                PlayerListType::try_from(req.list_type)
+                    .map_err(|_| HandleRequestError::Error(Error::InvalidPlayerListType))?,
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::GetPlayerListResponse(GetPlayerListResponse { entries }),
+            )))
+        }
+        RequestPayload::AddPlayerListEntryRequest(req) => {
+            let message = H::add_player_list_entry(
+                Path::new(&req.server_dir),
+                PlayerListType::try_from(req.list_type)
+                    .map_err(|_| HandleRequestError::Error(Error::InvalidPlayerListType))?,
+                &req.player,
+                req.reason.as_deref(),
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ModifyPlayerListResponse(ModifyPlayerListResponse { message }),
+            )))
+        }
+        RequestPayload::RegisterServerRequest(req) => {
+            H::register_server(Path::new(&req.server_dir)).await?;
+
+            Ok(HandleRequestResult::Response(None))
+        }
+        RequestPayload::ListServersRequest(_) => {
+            let servers = H::list_servers().await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ListServersResponse(ListServersResponse { servers }),
+            )))
+        }
+        RequestPayload::DeleteServerRequest(req) => {
+            H::delete_server(Path::new(&req.server_dir), req.keep_world).await?;
+
+            Ok(HandleRequestResult::Response(None))
+        }
+        RequestPayload::CloneServerRequest(req) => {
+            H::clone_server(
+                Path::new(&req.server_dir),
+                Path::new(&req.destination_dir),
+                &req.name,
+                req.hostname.as_deref(),
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(None))
+        }
+        RequestPayload::MoveServerRequest(req) => {
+            H::move_server(Path::new(&req.server_dir), Path::new(&req.destination_dir)).await?;
+
+            Ok(HandleRequestResult::Response(None))
+        }
+        RequestPayload::ImportServerRequest(req) => {
+            let result = H::import_server(
+                Path::new(&req.server_dir),
+                &req.name,
+                ConnectionType::try_from(req.connection)
+                    .map_err(|_| HandleRequestError::Error(Error::InvalidConnectionType))?,
+                req.hostname.as_deref(),
+                req.jar.as_deref(),
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ImportServerResponse(result),
+            )))
+        }
+        RequestPayload::AdoptExtensionsRequest(req) => {
+            let result = H::adopt_extensions(Path::new(&req.server_dir), req.auto_update).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::AdoptExtensionsResponse(result),
+            )))
+        }
+        RequestPayload::ResolveServerRequest(req) => {
+            let server_dir = H::resolve_server(&req.server).await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ResolveServerResponse(ResolveServerResponse {
+                    server_dir: server_dir.to_string_lossy().to_string(),
+                }),
+            )))
+        }
+        RequestPayload::RemovePlayerListEntryRequest(req) => {
+            let message = H::remove_player_list_entry(
+                Path::new(&req.server_dir),
+                PlayerListType::try_from(req.list_type)
+                    .map_err(|_| HandleRequestError::Error(Error::InvalidPlayerListType))?,
+                &req.player,
+            )
+            .await?;
+
+            Ok(HandleRequestResult::Response(Some(
+                ResponsePayload::ModifyPlayerListResponse(ModifyPlayerListResponse { message }),
+            )))
+        }
+    }
+}
+
+async fn handle_log_stream(
+    stream: UnixStream,
+    mut log_rx: mpsc::Receiver<LogLine>,
+) -> Result<(), Error> {
+    let (mut stream_reader, mut stream_writer) = stream.into_split();
+
+    loop {
+        tokio::select! {
+            line = log_rx.recv() => {
+                let Some(line) = line else {
+                    break;
+                };
+
+                let line_buf = line.encode_to_vec();
+                stream_writer.write_u32(line_buf.len() as u32).await?;
+                stream_writer.write_all(&line_buf).await?;
+            }
+            // the client sends nothing, so this only returns once it disconnects
+            _ = stream_reader.read_u8() => {
+                debug!("Log client disconnected");
+                break;
+            }
+        }
     }
+
+    Ok(())
 }
 
 async fn handle_terminal_connection<E, R, W>(
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 #[derive(clap::Parser)]
 pub struct Cli {
@@ -20,10 +20,28 @@ pub enum Subcommand {
     Kill(KillArgs),
     /// Attach to a server's console
     Attach(AttachArgs),
+    /// Show a server's console output
+    Logs(LogsArgs),
+    /// Run a command on a server and print its response
+    Exec(ExecArgs),
+    /// Run commands on a server interactively
+    Rcon(RconArgs),
     /// Update a server to the latest version
     Update(UpdateArgs),
     /// List all running servers
     Ps,
+    /// List all known servers, including stopped ones
+    Ls,
+    /// Make an existing server known to minecraftd without starting it
+    Register(RegisterArgs),
+    /// Turn a directory with an existing Minecraft server into a minecraftd server
+    Import(ImportArgs),
+    /// Delete a stopped server
+    Rm(RmArgs),
+    /// Copy a stopped server as a new server
+    Clone(CloneArgs),
+    /// Move a stopped server to another directory
+    Mv(MvArgs),
     /// Manage server mods/plugins
     Extensions {
         #[command(subcommand)]
@@ -31,6 +49,36 @@ pub enum Subcommand {
     },
     /// Show server statistics
     Stats(StatsArgs),
+    /// Manage world backups
+    Backup {
+        #[command(subcommand)]
+        command: Backup,
+    },
+    /// Show scheduled tasks
+    Schedules {
+        #[command(subcommand)]
+        command: Schedules,
+    },
+    /// Show the players of a server
+    Players {
+        #[command(subcommand)]
+        command: Players,
+    },
+    /// Manage the whitelist of a server
+    Whitelist {
+        #[command(subcommand)]
+        command: PlayerList,
+    },
+    /// Manage the operators of a server
+    Op {
+        #[command(subcommand)]
+        command: PlayerList,
+    },
+    /// Manage the banned players of a server
+    Ban {
+        #[command(subcommand)]
+        command: BanList,
+    },
 }
 
 #[derive(clap::Args)]
@@ -58,46 +106,216 @@ pub struct CreateArgs {
     pub hostname: Option<String>,
 }
 
+#[derive(clap::Args)]
+pub struct RegisterArgs {
+    /// The directory of the server to register. If not specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct ImportArgs {
+    /// The directory of the server to import. If not specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name of the server.
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// The type of connection to use for the server. Possible values are "direct" and "proxy".
+    #[arg(short, long)]
+    pub connection: Option<String>,
+    /// The hostname to use for the server if connection type is "proxy".
+    #[arg(long)]
+    pub hostname: Option<String>,
+    /// The file name of the server jar. If not specified, the jars in the server directory are inspected.
+    #[arg(long)]
+    pub jar: Option<String>,
+    /// Convert the jars in mods/ and plugins/ into managed mods/plugins without asking.
+    #[arg(long)]
+    pub adopt_extensions: bool,
+}
+
+#[derive(clap::Args)]
+pub struct RmArgs {
+    /// The directory of the server to delete. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Keep the world directories and delete everything else.
+    #[arg(long)]
+    pub keep_world: bool,
+    /// Do not ask for confirmation.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+#[derive(clap::Args)]
+pub struct CloneArgs {
+    /// The name, ID or unique ID prefix of the server to copy, or its directory.
+    pub source: String,
+    /// The directory to copy the server to. It must not exist yet.
+    pub destination: PathBuf,
+    /// The name of the copy. If not specified, the name of the destination directory will be used.
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// The proxy hostname of the copy. If not specified, it is derived from the hostname of the original server and the name of the copy.
+    #[arg(long)]
+    pub hostname: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct MvArgs {
+    /// The name, ID or unique ID prefix of the server to move, or its directory.
+    pub source: String,
+    /// The directory to move the server to. It must not exist yet.
+    pub destination: PathBuf,
+}
+
 #[derive(clap::Args)]
 pub struct StartArgs {
-    /// The directory of the server to start. If not specified, current directory will be used.
+    /// The directory of the server to start. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Wait until the server is ready, failing if it does not start in time. This is the default.
+    #[arg(long, overrides_with = "no_wait")]
+    pub wait: bool,
+    /// Return as soon as the server process has been started.
+    #[arg(long, overrides_with = "wait")]
+    pub no_wait: bool,
 }
 
 #[derive(clap::Args)]
 pub struct StopArgs {
-    /// The directory of the server to stop. If not specified, current directory will be used.
+    /// The directory of the server to stop. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
 }
 
 #[derive(clap::Args)]
 pub struct RestartArgs {
-    /// The directory of the server to restart. If not specified, current directory will be used.
+    /// The directory of the server to restart. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Warn the players and wait this long before restarting (e.g. 10m).
+    #[arg(long, value_parser = parse_duration)]
+    pub countdown: Option<Duration>,
 }
 
 #[derive(clap::Args)]
 pub struct KillArgs {
-    /// The directory of the server to kill. If not specified, current directory will be used.
+    /// The directory of the server to kill. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
 }
 
 #[derive(clap::Args)]
 pub struct AttachArgs {
-    /// The directory of the server to attach to. If not specified, current directory will be used.
+    /// The directory of the server to attach to. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The number of lines of recent output to show before the live output.
+    #[arg(short = 'n', long, default_value_t = 100)]
+    pub scrollback: u32,
+    /// Only show the output, without sending any input to the server. Press Ctrl+C to detach.
+    #[arg(long)]
+    pub read_only: bool,
+    /// The key sequence to detach with, e.g. "ctrl-p,ctrl-q". All other keys, including Ctrl+C, are sent to the server.
+    #[arg(long, default_value = "ctrl-p,ctrl-q", value_parser = parse_detach_keys)]
+    pub detach_keys: DetachKeys,
+}
+
+#[derive(Clone)]
+pub struct DetachKeys {
+    pub keys: Vec<u8>,
+    pub description: String,
+}
+
+#[derive(clap::Args)]
+pub struct LogsArgs {
+    /// The directory of the server to show the console output of. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Keep printing new output until interrupted.
+    #[arg(short, long)]
+    pub follow: bool,
+    /// Only show output from this long ago onwards (e.g. 1h).
+    #[arg(long, value_parser = parse_duration)]
+    pub since: Option<Duration>,
+    /// Only show lines matching this regular expression.
+    #[arg(short, long)]
+    pub grep: Option<String>,
+    /// Only show the last N lines of the history.
+    #[arg(short = 'n', long)]
+    pub lines: Option<u32>,
+    /// Show the time each line was logged at.
+    #[arg(short, long)]
+    pub timestamps: bool,
+    /// Whether to keep colors and other ANSI escape sequences in the output.
+    #[clap(long, default_value = "auto")]
+    pub ansi: AnsiMode,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+pub enum AnsiMode {
+    /// Keep ANSI escape sequences if the output is a terminal.
+    Auto,
+    /// Always keep ANSI escape sequences.
+    Always,
+    /// Always strip ANSI escape sequences.
+    Never,
+}
+
+#[derive(clap::Args)]
+pub struct ExecArgs {
+    /// The directory of the server to run the command on. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The command to run, without the leading slash (e.g. "list").
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(clap::Args)]
+pub struct RconArgs {
+    /// The directory of the server to run commands on. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
 }
 
 #[derive(clap::Args)]
 pub struct UpdateArgs {
-    /// The directory of the server to update. If not specified, current directory will be used.
+    /// The directory of the server to update. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
     /// The type of update to perform.
     #[clap(short, long, default_value = "stable")]
     pub update_type: UpdateType,
@@ -115,13 +333,31 @@ pub enum UpdateType {
 pub enum Extensions {
     /// Add a mod/plugin to the server
     Add(ExtensionsAddArgs),
+    /// Turn the mods/plugins that were put into mods/ or plugins/ by hand into managed ones
+    Adopt(ExtensionsAdoptArgs),
+}
+
+#[derive(clap::Args)]
+pub struct ExtensionsAdoptArgs {
+    /// The directory of the server. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Enable auto-updates for the adopted mods/plugins.
+    #[arg(long)]
+    pub auto_update: bool,
 }
 
 #[derive(clap::Args)]
 pub struct ExtensionsAddArgs {
-    /// The directory of the server to add the mod/plugin to. If not specified, current directory will be used.
+    /// The directory of the server to add the mod/plugin to. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
     /// Allow adding mods that are incompatible with the server version.
     #[arg(long)]
     pub allow_incompatible_versions: bool,
@@ -136,7 +372,237 @@ pub struct ExtensionsAddArgs {
 
 #[derive(clap::Args)]
 pub struct StatsArgs {
-    /// The directory of the server to show statistics for. If not specified, current directory will be used.
+    /// The directory of the server to show statistics for. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Backup {
+    /// List backups of the server
+    List(BackupListArgs),
+    /// Create a backup of the server's worlds
+    Create(BackupCreateArgs),
+    /// Replace the server's worlds with a backup
+    Restore(BackupRestoreArgs),
+    /// Delete a backup
+    Delete(BackupDeleteArgs),
+    /// Check that backups are intact
+    Verify(BackupVerifyArgs),
+}
+
+#[derive(clap::Args)]
+pub struct BackupListArgs {
+    /// The directory of the server to list backups for. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct BackupCreateArgs {
+    /// The directory of the server to back up. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct BackupRestoreArgs {
+    /// The directory of the server to restore. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Do not ask for confirmation.
+    #[arg(short, long)]
+    pub yes: bool,
+    /// The ID of the backup to restore, as shown by `mcctl backup list`.
+    pub backup_id: String,
+}
+
+#[derive(clap::Args)]
+pub struct BackupDeleteArgs {
+    /// The directory of the server the backup belongs to. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Do not ask for confirmation.
+    #[arg(short, long)]
+    pub yes: bool,
+    /// The ID of the backup to delete, as shown by `mcctl backup list`.
+    pub backup_id: String,
+}
+
+#[derive(clap::Args)]
+pub struct BackupVerifyArgs {
+    /// The directory of the server whose backups to verify. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The ID of the backup to verify. If not specified, all backups of the server are verified.
+    pub backup_id: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Schedules {
+    /// List the scheduled tasks of the server and when they run next
+    List(SchedulesListArgs),
+}
+
+#[derive(clap::Args)]
+pub struct SchedulesListArgs {
+    /// The directory of the server to list scheduled tasks for. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Players {
+    /// List the players that are online and since when
+    Online(PlayersOnlineArgs),
+    /// List the past sessions of players
+    History(PlayersHistoryArgs),
+}
+
+#[derive(clap::Args)]
+pub struct PlayersOnlineArgs {
+    /// The directory of the server to list online players for. If neither this nor the server is specified, current directory will be used.
     #[arg(short = 'd', long)]
     pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct PlayersHistoryArgs {
+    /// The directory of the server to list player sessions for. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// Only show sessions from this long ago onwards (e.g. 7d).
+    #[arg(long, value_parser = parse_duration)]
+    pub since: Option<Duration>,
+    /// Only show sessions of the player with this name or UUID.
+    #[arg(short, long)]
+    pub player: Option<String>,
+    /// Only show the last this many sessions.
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum PlayerList {
+    /// Add a player. Applied with a command if the server is running.
+    Add(PlayerListAddArgs),
+    /// Remove a player. Applied with a command if the server is running.
+    Remove(PlayerListRemoveArgs),
+    /// List the players
+    List(PlayerListListArgs),
+}
+
+#[derive(clap::Subcommand)]
+pub enum BanList {
+    /// Ban a player. Applied with a command if the server is running.
+    Add(BanAddArgs),
+    /// Unban a player. Applied with a command if the server is running.
+    Remove(PlayerListRemoveArgs),
+    /// List the banned players
+    List(PlayerListListArgs),
+}
+
+#[derive(clap::Args)]
+pub struct PlayerListAddArgs {
+    /// The directory of the server. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The name of the player.
+    pub player: String,
+}
+
+#[derive(clap::Args)]
+pub struct BanAddArgs {
+    /// The directory of the server. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The name of the player.
+    pub player: String,
+    /// The reason shown to the player.
+    #[arg(short, long)]
+    pub reason: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct PlayerListRemoveArgs {
+    /// The directory of the server. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(short, long, conflicts_with = "server_dir")]
+    pub server: Option<String>,
+    /// The name of the player.
+    pub player: String,
+}
+
+#[derive(clap::Args)]
+pub struct PlayerListListArgs {
+    /// The directory of the server. If neither this nor the server is specified, current directory will be used.
+    #[arg(short = 'd', long)]
+    pub server_dir: Option<PathBuf>,
+    /// The name, ID or unique ID prefix of the server.
+    #[arg(conflicts_with = "server_dir")]
+    pub server: Option<String>,
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    duration_str::parse_std(s)
+}
+
+fn parse_detach_keys(s: &str) -> Result<DetachKeys, String> {
+    let keys = s
+        .split(',')
+        .map(|key| {
+            let byte = match key.strip_prefix("ctrl-") {
+                Some(ctrl) => match ctrl.to_ascii_lowercase().as_bytes() {
+                    [c @ b'a'..=b'z'] => Some(c - b'a' + 1),
+                    [c @ (b'@' | b'[' | b'\\' | b']' | b'^' | b'_')] => Some(c - b'@'),
+                    _ => None,
+                },
+                None => match key.as_bytes() {
+                    [c] if c.is_ascii_graphic() => Some(*c),
+                    _ => None,
+                },
+            };
+            byte.ok_or_else(|| format!("Invalid key '{key}'"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DetachKeys {
+        keys,
+        description: s.to_string(),
+    })
 }
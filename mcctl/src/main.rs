@@ -1,9 +1,11 @@
 use clap::Parser;
+use mcctl_protocol::PlayerListType;
 
 use crate::cli::Subcommand;
 
 mod cli;
 mod eula;
+mod server_dir;
 mod subcommands;
 
 #[tokio::main(flavor = "current_thread")]
@@ -36,18 +38,63 @@ async fn start() -> anyhow::Result<()> {
         Subcommand::Attach(args) => {
             subcommands::attach::attach(args).await?;
         }
+        Subcommand::Logs(args) => {
+            subcommands::logs::logs(args).await?;
+        }
+        Subcommand::Exec(args) => {
+            subcommands::exec::exec(args).await?;
+        }
+        Subcommand::Rcon(args) => {
+            subcommands::rcon::rcon(args).await?;
+        }
         Subcommand::Update(args) => {
             subcommands::update::update(args).await?;
         }
         Subcommand::Ps => {
             subcommands::ps::ps().await?;
         }
+        Subcommand::Ls => {
+            subcommands::ls::ls().await?;
+        }
+        Subcommand::Register(args) => {
+            subcommands::register::register(args).await?;
+        }
+        Subcommand::Import(args) => {
+            subcommands::import::import(args).await?;
+        }
+        Subcommand::Rm(args) => {
+            subcommands::rm::rm(args).await?;
+        }
+        Subcommand::Clone(args) => {
+            subcommands::clone::clone(args).await?;
+        }
+        Subcommand::Mv(args) => {
+            subcommands::mv::mv(args).await?;
+        }
         Subcommand::Extensions { command } => {
             subcommands::extensions::extensions(command).await?;
         }
         Subcommand::Stats(args) => {
             subcommands::stats::stats(args).await?;
         }
+        Subcommand::Backup { command } => {
+            subcommands::backup::backup(command).await?;
+        }
+        Subcommand::Schedules { command } => {
+            subcommands::schedules::schedules(command).await?;
+        }
+        Subcommand::Players { command } => {
+            subcommands::players::players(command).await?;
+        }
+        Subcommand::Whitelist { command } => {
+            subcommands::player_list::player_list(PlayerListType::Whitelist, command).await?;
+        }
+        Subcommand::Op { command } => {
+            subcommands::player_list::player_list(PlayerListType::Ops, command).await?;
+        }
+        Subcommand::Ban { command } => {
+            subcommands::player_list::ban_list(command).await?;
+        }
     }
 
     Ok(())
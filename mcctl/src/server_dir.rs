@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use mcctl_protocol::client::Client;
+use minecraftd_manifest::ServerManifest;
+
+/// Resolves the server given on the command line to its directory. A server name, ID or unique ID
+/// prefix is looked up in the registry of minecraftd. Otherwise the directory, or the current
+/// directory if neither is given, must contain a server manifest.
+pub async fn resolve_server_dir(
+    client: &mut Client,
+    server: Option<String>,
+    server_dir: Option<PathBuf>,
+) -> anyhow::Result<String> {
+    if let Some(server) = server {
+        return Ok(client.resolve_server(server).await?);
+    }
+
+    let server_dir = match server_dir {
+        Some(p) => p,
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+
+    if !ServerManifest::manifest_path(&server_dir).exists() {
+        bail!(
+            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
+            server_dir.display()
+        );
+    }
+
+    Ok(server_dir
+        .canonicalize()
+        .context("Failed to canonicalize path")?
+        .to_str()
+        .context("Path is not valid UTF-8")?
+        .to_string())
+}
+
+/// Resolves an argument that is either a server directory or a server name, ID or unique ID prefix.
+pub async fn resolve_server_dir_or_server(
+    client: &mut Client,
+    server: String,
+) -> anyhow::Result<String> {
+    if ServerManifest::manifest_path(Path::new(&server)).exists() {
+        resolve_server_dir(client, None, Some(PathBuf::from(server))).await
+    } else {
+        resolve_server_dir(client, Some(server), None).await
+    }
+}
+
+/// Makes a directory that does not exist yet absolute, relative to the current directory.
+pub fn absolute_new_dir(dir: PathBuf) -> anyhow::Result<String> {
+    let dir = std::path::absolute(dir).context("Failed to get absolute path")?;
+    Ok(dir.to_str().context("Path is not valid UTF-8")?.to_string())
+}
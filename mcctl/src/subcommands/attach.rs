@@ -1,51 +1,61 @@
-use anyhow::{Context, bail};
-use mcctl_protocol::client::Client;
-use minecraftd_manifest::ServerManifest;
-use nix::sys::termios::{LocalFlags, SetArg, tcgetattr, tcsetattr};
+use anyhow::Context;
+use mcctl_protocol::{AttachTerminalRequest, client::Client};
+use nix::sys::termios::{InputFlags, LocalFlags, SetArg, tcgetattr, tcsetattr};
 use terminal_size::{Height, Width, terminal_size};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::cli::AttachArgs;
+use crate::{cli::AttachArgs, server_dir::resolve_server_dir};
 
 pub async fn attach(args: AttachArgs) -> anyhow::Result<()> {
-    let client = Client::connect()
+    let mut client = Client::connect()
         .await
         .context("Failed to connect to minecraftd")?;
 
-    let server_dir = match args.server_dir {
-        Some(p) => p,
-        None => std::env::current_dir().context("Failed to get current directory")?,
-    };
-
-    if !ServerManifest::manifest_path(&server_dir).exists() {
-        bail!(
-            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
-            server_dir.display()
-        );
-    }
-
-    let server_dir = server_dir
-        .canonicalize()
-        .context("Failed to canonicalize path")?
-        .to_str()
-        .context("Path is not valid UTF-8")?
-        .to_string();
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
 
-    let (mut terminal_reader, mut terminal_writer) = client.attach_terminal(server_dir).await?;
+    let (mut terminal_reader, mut terminal_writer) = client
+        .attach_terminal(AttachTerminalRequest {
+            server_dir,
+            scrollback_lines: Some(args.scrollback),
+            read_only: args.read_only,
+        })
+        .await?;
 
     let mut stdin = tokio::io::stdin();
     let mut stdout = tokio::io::stdout();
 
-    let mut attr = tcgetattr(&stdin).context("Failed to get terminal attributes")?;
-    let orig_attr = attr.clone();
-    attr.local_flags
-        .remove(LocalFlags::ICANON | LocalFlags::ECHO);
-    tcsetattr(&stdin, SetArg::TCSANOW, &attr).context("Failed to set terminal attributes")?;
+    // in read-only mode, the terminal is left alone so that Ctrl+C detaches
+    let orig_attr = if args.read_only {
+        eprintln!("Attached read-only. Press Ctrl+C to detach.");
+        None
+    } else {
+        let mut attr = tcgetattr(&stdin).context("Failed to get terminal attributes")?;
+        let orig_attr = attr.clone();
+        // Ctrl+C and the like are sent to the server instead of signalling mcctl, and Ctrl+Q and
+        // Ctrl+S are not taken for flow control
+        attr.local_flags
+            .remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG);
+        attr.input_flags.remove(InputFlags::IXON);
+        tcsetattr(&stdin, SetArg::TCSANOW, &attr).context("Failed to set terminal attributes")?;
+
+        eprintln!(
+            "Attached. Press {} to detach.",
+            args.detach_keys.description
+        );
+        Some(orig_attr)
+    };
 
     let (exit_signal_tx, mut exit_signal_rx) = tokio::sync::mpsc::channel::<()>(1);
+    let (detach_signal_tx, mut detach_signal_rx) = tokio::sync::mpsc::channel::<()>(1);
 
     let input_task = tokio::spawn({
+        let read_only = args.read_only;
+        let mut detach_keys = DetachKeyMatcher::new(args.detach_keys.keys);
         async move {
+            if read_only {
+                return;
+            }
+
             let result: anyhow::Result<()> = async {
                 let mut buffer = [0u8; 1024];
                 let mut sigwinch =
@@ -59,10 +69,18 @@ pub async fn attach(args: AttachArgs) -> anyhow::Result<()> {
                             if n == 0 {
                                 break;
                             }
-                            terminal_writer
-                                .write(buffer[..n].to_vec())
-                                .await
-                                .context("Failed to send terminal input")?;
+
+                            let (input, detached) = detach_keys.feed(&buffer[..n]);
+                            if !input.is_empty() {
+                                terminal_writer
+                                    .write(input)
+                                    .await
+                                    .context("Failed to send terminal input")?;
+                            }
+                            if detached {
+                                detach_signal_tx.send(()).await.ok();
+                                break;
+                            }
                         }
                         _ = sigwinch.recv() => {
                             let (Width(cols), Height(rows)) =
@@ -89,15 +107,15 @@ pub async fn attach(args: AttachArgs) -> anyhow::Result<()> {
 
     let output_task = tokio::spawn({
         async move {
-            let result: anyhow::Result<()> = async {
+            let result: anyhow::Result<bool> = async {
                 loop {
-                    let Some(output) = terminal_reader
-                        .read()
-                        .await
-                        .context("Failed to read from terminal")?
-                    else {
+                    let output = tokio::select! {
+                        output = terminal_reader.read() => output,
+                        Some(()) = detach_signal_rx.recv() => break Ok(true),
+                    };
+                    let Some(output) = output.context("Failed to read from terminal")? else {
                         exit_signal_tx.send(()).await.ok();
-                        break Ok(());
+                        break Ok(false);
                     };
 
                     stdout
@@ -109,16 +127,96 @@ pub async fn attach(args: AttachArgs) -> anyhow::Result<()> {
             }
             .await;
 
-            if let Err(e) = result {
-                eprintln!("Error in output task: {:?}", e);
+            match result {
+                Ok(true) => eprintln!("\nDetached from the console."),
+                Ok(false) => eprintln!("\nThe server has stopped."),
+                Err(e) => eprintln!("Error in output task: {:?}", e),
             }
         }
     });
 
     tokio::try_join!(input_task, output_task).unwrap();
 
-    tcsetattr(tokio::io::stdin(), SetArg::TCSANOW, &orig_attr)
-        .context("Failed to restore terminal attributes")?;
+    if let Some(orig_attr) = orig_attr {
+        tcsetattr(tokio::io::stdin(), SetArg::TCSANOW, &orig_attr)
+            .context("Failed to restore terminal attributes")?;
+    }
 
     Ok(())
 }
+
+/// Finds the detach key sequence in the input. Keys that turn out not to be part of the sequence
+/// are sent to the server after all.
+struct DetachKeyMatcher {
+    keys: Vec<u8>,
+    matched: usize,
+}
+
+impl DetachKeyMatcher {
+    fn new(keys: Vec<u8>) -> Self {
+        Self { keys, matched: 0 }
+    }
+
+    /// Returns the input to send to the server, and whether the sequence has been completed.
+    fn feed(&mut self, input: &[u8]) -> (Vec<u8>, bool) {
+        let mut forward = Vec::with_capacity(input.len());
+        for &byte in input {
+            if byte == self.keys[self.matched] {
+                self.matched += 1;
+                if self.matched == self.keys.len() {
+                    return (forward, true);
+                }
+                continue;
+            }
+
+            // the sequence may start again within the keys matched so far, e.g. the last two of
+            // "aaab" for the sequence "aab"
+            let mut pending = self.keys[..self.matched].to_vec();
+            pending.push(byte);
+            let start = (1..=pending.len())
+                .find(|&start| self.keys.starts_with(&pending[start..]))
+                .unwrap();
+            forward.extend_from_slice(&pending[..start]);
+            self.matched = pending.len() - start;
+        }
+        (forward, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detaches_on_sequence() {
+        let mut matcher = DetachKeyMatcher::new(vec![0x10, 0x11]);
+        assert_eq!(matcher.feed(b"ab\x10\x11cd"), (b"ab".to_vec(), true));
+    }
+
+    #[test]
+    fn sequence_split_across_reads() {
+        let mut matcher = DetachKeyMatcher::new(vec![0x10, 0x11]);
+        assert_eq!(matcher.feed(b"ab\x10"), (b"ab".to_vec(), false));
+        assert_eq!(matcher.feed(b"\x11"), (Vec::new(), true));
+    }
+
+    #[test]
+    fn forwards_partial_sequence() {
+        let mut matcher = DetachKeyMatcher::new(vec![0x10, 0x11]);
+        assert_eq!(matcher.feed(b"\x10"), (Vec::new(), false));
+        assert_eq!(matcher.feed(b"x"), (b"\x10x".to_vec(), false));
+        assert_eq!(matcher.feed(b"\x10\x10\x11"), (b"\x10".to_vec(), true));
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let mut matcher = DetachKeyMatcher::new(b"aab".to_vec());
+        assert_eq!(matcher.feed(b"aaab"), (b"a".to_vec(), true));
+
+        let mut matcher = DetachKeyMatcher::new(b"abab".to_vec());
+        assert_eq!(matcher.feed(b"abaabab"), (b"aba".to_vec(), true));
+
+        let mut matcher = DetachKeyMatcher::new(b"aab".to_vec());
+        assert_eq!(matcher.feed(b"aaxaab"), (b"aax".to_vec(), true));
+    }
+}
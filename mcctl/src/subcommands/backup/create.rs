@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::BackupCreateArgs, server_dir::resolve_server_dir};
+
+pub async fn create(args: BackupCreateArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Creating backup...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let backup = client.create_backup(server_dir).await?;
+
+    pb.finish_with_message(format!("Backup '{}' created successfully.", backup.id));
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::BackupDeleteArgs, server_dir::resolve_server_dir};
+
+pub async fn delete(args: BackupDeleteArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    if !args.yes {
+        let confirmed = inquire::Confirm::new(&format!("Delete backup '{}'?", args.backup_id))
+            .with_default(false)
+            .prompt()?;
+
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    client
+        .delete_backup(server_dir, args.backup_id.clone())
+        .await?;
+
+    println!("Backup '{}' deleted.", args.backup_id);
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{
+    cli::BackupListArgs, server_dir::resolve_server_dir, subcommands::backup::format_created_at,
+};
+
+pub async fn list(args: BackupListArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let backups = client.list_backups(server_dir).await?;
+
+    if backups.is_empty() {
+        println!("No backups found.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<[String; 4]>::new();
+    table.push([
+        "ID".to_string(),
+        "LABEL".to_string(),
+        "CREATED".to_string(),
+        "WORLDS".to_string(),
+    ]);
+
+    for backup in backups {
+        table.push([
+            backup.id,
+            backup.label,
+            format_created_at(backup.created_at),
+            backup.worlds.join(", "),
+        ]);
+    }
+
+    let column_widths = (0..4)
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for row in table {
+        for (i, cell) in row.iter().enumerate() {
+            print!("{:width$}  ", cell, width = column_widths[i]);
+        }
+        println!();
+    }
+
+    Ok(())
+}
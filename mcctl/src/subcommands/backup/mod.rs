@@ -0,0 +1,39 @@
+use crate::cli::Backup;
+
+mod create;
+mod delete;
+mod list;
+mod restore;
+mod verify;
+
+pub async fn backup(command: Backup) -> anyhow::Result<()> {
+    match command {
+        Backup::List(args) => {
+            list::list(args).await?;
+        }
+        Backup::Create(args) => {
+            create::create(args).await?;
+        }
+        Backup::Restore(args) => {
+            restore::restore(args).await?;
+        }
+        Backup::Delete(args) => {
+            delete::delete(args).await?;
+        }
+        Backup::Verify(args) => {
+            verify::verify(args).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_created_at(timestamp: i64) -> String {
+    match chrono::DateTime::from_timestamp(timestamp, 0) {
+        Some(created_at) => created_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        None => "-".to_string(),
+    }
+}
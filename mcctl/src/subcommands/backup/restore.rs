@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::BackupRestoreArgs, server_dir::resolve_server_dir};
+
+pub async fn restore(args: BackupRestoreArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    if !args.yes {
+        let confirmed = inquire::Confirm::new(&format!(
+            "Replace the current worlds with backup '{}'? The current worlds will be kept as a separate backup.",
+            args.backup_id
+        ))
+        .with_default(false)
+        .prompt()?;
+
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Restoring backup...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let safety_backup = client.restore_backup(server_dir, args.backup_id).await?;
+
+    pb.finish_with_message("Backup restored successfully.");
+
+    if let Some(safety_backup) = safety_backup {
+        println!(
+            "The previous worlds were saved as backup '{}'.",
+            safety_backup.id
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use mcctl_protocol::client::Client;
+
+use crate::{cli::BackupVerifyArgs, server_dir::resolve_server_dir};
+
+pub async fn verify(args: BackupVerifyArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Verifying backups...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let results = client.verify_backups(server_dir, args.backup_id).await?;
+
+    pb.finish_and_clear();
+
+    let mut damaged = 0;
+    for result in &results {
+        if result.missing_chunks.is_empty() && result.corrupt_chunks.is_empty() {
+            println!(
+                "{}: OK ({} chunks)",
+                result.backup_id, result.checked_chunks
+            );
+            continue;
+        }
+
+        damaged += 1;
+        println!(
+            "{}: DAMAGED ({} of {} chunks missing, {} corrupt)",
+            result.backup_id,
+            result.missing_chunks.len(),
+            result.checked_chunks,
+            result.corrupt_chunks.len()
+        );
+        for hash in &result.missing_chunks {
+            println!("  missing: {hash}");
+        }
+        for hash in &result.corrupt_chunks {
+            println!("  corrupt: {hash}");
+        }
+    }
+
+    if damaged > 0 {
+        bail!("{damaged} of {} backups are damaged", results.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{
+    cli::CloneArgs,
+    server_dir::{absolute_new_dir, resolve_server_dir_or_server},
+};
+
+pub async fn clone(args: CloneArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir_or_server(&mut client, args.source).await?;
+
+    let name = match args.name {
+        Some(name) => name,
+        None => args
+            .destination
+            .file_name()
+            .context("Destination has no directory name")?
+            .to_str()
+            .context("Path is not valid UTF-8")?
+            .to_string(),
+    };
+    let destination = absolute_new_dir(args.destination)?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Cloning server...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    if let Err(e) = client
+        .clone_server(&server_dir, &destination, &name, args.hostname)
+        .await
+    {
+        pb.finish_and_clear();
+        return Err(e.into());
+    }
+
+    pb.finish_with_message(format!("Server '{name}' created at '{destination}'."));
+
+    Ok(())
+}
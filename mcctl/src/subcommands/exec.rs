@@ -0,0 +1,37 @@
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::ExecArgs, server_dir::resolve_server_dir};
+
+pub async fn exec(args: ExecArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let command = args.command.join(" ");
+    let output = client
+        .execute_command(server_dir, normalize_command(&command))
+        .await?;
+    print_output(&output);
+
+    Ok(())
+}
+
+/// RCON does not accept the leading slash that is typed in the chat.
+pub fn normalize_command(command: &str) -> &str {
+    let command = command.trim();
+    command.strip_prefix('/').unwrap_or(command)
+}
+
+pub fn print_output(output: &str) {
+    if output.is_empty() {
+        return;
+    }
+    if output.ends_with('\n') {
+        print!("{output}");
+    } else {
+        println!("{output}");
+    }
+}
@@ -1,29 +1,19 @@
-use std::{fmt::Display, time::Duration};
+use std::{fmt::Display, path::Path, time::Duration};
 
 use anyhow::{Context, bail};
 use mcctl_protocol::{ExtensionInfo, ExtensionType, client::Client};
 use minecraftd_manifest::ServerManifest;
 
-use crate::cli::ExtensionsAddArgs;
+use crate::{cli::ExtensionsAddArgs, server_dir::resolve_server_dir};
 
 pub async fn add(args: ExtensionsAddArgs) -> anyhow::Result<()> {
     let mut client = Client::connect()
         .await
         .context("Failed to connect to minecraftd")?;
 
-    let server_dir = match args.server_dir {
-        Some(p) => p,
-        None => std::env::current_dir().context("Failed to get current directory")?,
-    };
-
-    if !ServerManifest::manifest_path(&server_dir).exists() {
-        bail!(
-            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
-            server_dir.display()
-        );
-    }
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
 
-    let manifest = ServerManifest::load(&server_dir)
+    let manifest = ServerManifest::load(Path::new(&server_dir))
         .await
         .context("Failed to load server manifest")?;
 
@@ -141,12 +131,6 @@ pub async fn add(args: ExtensionsAddArgs) -> anyhow::Result<()> {
     pb.set_message("Adding extension to server...");
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    let server_dir = server_dir
-        .canonicalize()
-        .context("Failed to canonicalize path")?
-        .to_str()
-        .context("Path is not valid UTF-8")?
-        .to_string();
     let result = client
         .add_extension(
             &server_dir,
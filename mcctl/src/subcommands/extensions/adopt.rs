@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use mcctl_protocol::{AdoptExtensionsResponse, client::Client};
+
+use crate::{cli::ExtensionsAdoptArgs, server_dir::resolve_server_dir};
+
+pub async fn adopt(args: ExtensionsAdoptArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Looking up mods/plugins...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let result = match client.adopt_extensions(&server_dir, args.auto_update).await {
+        Ok(result) => result,
+        Err(e) => {
+            pb.finish_and_clear();
+            return Err(e.into());
+        }
+    };
+
+    pb.finish_and_clear();
+
+    if result.adopted.is_empty() && result.unmatched.is_empty() {
+        println!("There are no unmanaged mods/plugins.");
+        return Ok(());
+    }
+
+    print_adopt_report(&result);
+
+    Ok(())
+}
+
+pub fn print_adopt_report(result: &AdoptExtensionsResponse) {
+    if !result.adopted.is_empty() {
+        println!("Now managed by minecraftd:");
+        for adopted in &result.adopted {
+            let name = adopted
+                .extension
+                .as_ref()
+                .map(|e| e.name.as_str())
+                .unwrap_or_default();
+            println!("  {} -> {name}", adopted.path);
+        }
+    }
+
+    if !result.unmatched.is_empty() {
+        println!("Not found on any extension provider, left as they are:");
+        for path in &result.unmatched {
+            println!("  {path}");
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{fmt::Display, path::Path, time::Duration};
+
+use anyhow::Context;
+use mcctl_protocol::{ExtensionType, client::Client};
+use minecraftd_manifest::ServerManifest;
+
+/// Offers to replace jars that were put into `mods/` or `plugins/` by hand with managed
+/// mods/plugins. Each jar is searched for on an extension provider, and removed once the selected
+/// mod/plugin has been added.
+///
+/// `jars` are relative to the server directory.
+pub async fn offer_conversion(
+    client: &mut Client,
+    server_dir: &str,
+    jars: &[String],
+) -> anyhow::Result<()> {
+    if jars.is_empty()
+        || !inquire::Confirm::new("Convert them into managed mods/plugins?")
+            .with_default(true)
+            .prompt()?
+    {
+        return Ok(());
+    }
+
+    let manifest = ServerManifest::load(Path::new(server_dir))
+        .await
+        .context("Failed to load server manifest")?;
+
+    let providers = client.get_extension_providers().await?;
+    let provider = if providers.len() == 1 {
+        providers.into_iter().next().unwrap()
+    } else {
+        inquire::Select::new("Extension provider:", providers).prompt()?
+    };
+
+    struct ExtensionDisplay(mcctl_protocol::ExtensionInfo);
+    impl Display for ExtensionDisplay {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0.name)
+        }
+    }
+
+    struct ExtensionVersionDisplay(mcctl_protocol::ExtensionVersionInfo);
+    impl Display for ExtensionVersionDisplay {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if self.0.is_stable {
+                write!(f, "{}", self.0.version)
+            } else {
+                write!(f, "{} (unstable)", self.0.version)
+            }
+        }
+    }
+
+    for jar in jars {
+        println!("{jar}:");
+
+        let type_ = if jar.starts_with("plugins/") {
+            ExtensionType::Plugin
+        } else {
+            ExtensionType::Mod
+        };
+
+        let file_stem = Path::new(jar)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let Some(search_query) = inquire::Text::new("Search query (Esc to skip):")
+            .with_default(&file_stem)
+            .prompt_skippable()?
+        else {
+            continue;
+        };
+
+        let pb = indicatif::ProgressBar::new_spinner();
+        pb.set_message("Searching for extensions...");
+        pb.enable_steady_tick(Duration::from_millis(100));
+
+        let extensions = client
+            .search_extension(&provider, type_, &manifest.version, &search_query, true)
+            .await
+            .context("Failed to search for extensions")?;
+
+        pb.finish_and_clear();
+
+        if extensions.is_empty() {
+            println!("Nothing found, '{jar}' is left as it is.");
+            continue;
+        }
+
+        let Some(ExtensionDisplay(extension)) = inquire::Select::new(
+            "Select the mod/plugin (Esc to skip):",
+            extensions.into_iter().map(ExtensionDisplay).collect(),
+        )
+        .prompt_skippable()?
+        else {
+            continue;
+        };
+
+        let extension_versions = client
+            .get_extension_versions(&provider, type_, &manifest.version, &extension.id, true)
+            .await
+            .context("Failed to get extension versions")?;
+
+        let latest_stable_index = extension_versions
+            .iter()
+            .position(|v| v.is_stable)
+            .unwrap_or(0);
+
+        let Some(ExtensionVersionDisplay(extension_version)) = inquire::Select::new(
+            "Select the version of the jar (Esc to skip):",
+            extension_versions
+                .into_iter()
+                .map(ExtensionVersionDisplay)
+                .collect(),
+        )
+        .with_starting_cursor(latest_stable_index)
+        .prompt_skippable()?
+        else {
+            continue;
+        };
+
+        client
+            .add_extension(
+                server_dir,
+                &provider,
+                type_,
+                &extension.id,
+                &extension_version.id,
+                true,
+                false,
+            )
+            .await
+            .with_context(|| format!("Failed to add '{}' to server", extension.name))?;
+
+        // the managed jar is symlinked into the directory on the next start
+        std::fs::remove_file(Path::new(server_dir).join(jar))
+            .with_context(|| format!("Failed to remove '{jar}'"))?;
+
+        println!("Converted '{jar}' into '{}'.", extension.name);
+    }
+
+    Ok(())
+}
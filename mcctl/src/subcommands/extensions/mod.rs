@@ -1,12 +1,17 @@
 use crate::cli::Extensions;
 
 mod add;
+pub mod adopt;
+pub mod convert;
 
 pub async fn extensions(command: Extensions) -> anyhow::Result<()> {
     match command {
         Extensions::Add(args) => {
             add::add(args).await?;
         }
+        Extensions::Adopt(args) => {
+            adopt::adopt(args).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,130 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{Context, bail};
+use mcctl_protocol::{ConnectionType, client::Client};
+
+use crate::{
+    cli::ImportArgs,
+    subcommands::extensions::{adopt::print_adopt_report, convert::offer_conversion},
+};
+
+pub async fn import(args: ImportArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = match args.server_dir {
+        Some(p) => p,
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    let server_dir = server_dir
+        .canonicalize()
+        .context("Failed to canonicalize path")?
+        .to_str()
+        .context("Path is not valid UTF-8")?
+        .to_string();
+
+    let name = match args.name {
+        Some(n) => n,
+        None => inquire::Text::new("Server name:")
+            .with_default(
+                Path::new(&server_dir)
+                    .file_name()
+                    .map(|n| n.to_string_lossy())
+                    .unwrap_or_else(|| "A Minecraft Server".into())
+                    .as_ref(),
+            )
+            .prompt()?,
+    };
+
+    let connection = match &args.connection {
+        Some(c) => c,
+        None => {
+            let connections = vec!["direct", "proxy"];
+            inquire::Select::new("Connection type:", connections).prompt()?
+        }
+    };
+    let connection = match connection {
+        "direct" => ConnectionType::Direct,
+        "proxy" => ConnectionType::Proxy,
+        _ => bail!("Invalid connection type '{}'", connection),
+    };
+
+    let hostname = match args.hostname {
+        Some(h) => Some(h),
+        None => {
+            if connection == ConnectionType::Proxy {
+                Some(inquire::Text::new("Proxy hostname:").prompt()?)
+            } else {
+                None
+            }
+        }
+    };
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Importing server...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let result = match client
+        .import_server(&server_dir, &name, connection, hostname, args.jar)
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            pb.finish_and_clear();
+            return Err(e.into());
+        }
+    };
+
+    if result.server_implementation == "custom" {
+        pb.finish_with_message(format!(
+            "Imported '{name}' as a custom server. Check the command in minecraftd.yaml before starting it."
+        ));
+    } else {
+        pb.finish_with_message(format!(
+            "Imported '{name}' as a {} {} server (build {}).",
+            result.server_implementation, result.version, result.build
+        ));
+        if result.build_assumed {
+            println!(
+                "The build could not be detected from the jar, so the latest stable build was assumed. Correct `build` in minecraftd.yaml if it differs."
+            );
+        }
+    }
+
+    if result.unmanaged_jars.is_empty() {
+        return Ok(());
+    }
+
+    println!("These mods/plugins are not managed by minecraftd:");
+    for jar in &result.unmanaged_jars {
+        println!("  {jar}");
+    }
+
+    let adopt = args.adopt_extensions
+        || inquire::Confirm::new("Look them up on the extension providers by their hash?")
+            .with_default(true)
+            .prompt()?;
+    if !adopt {
+        return offer_conversion(&mut client, &server_dir, &result.unmanaged_jars).await;
+    }
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Looking up mods/plugins...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let result = match client.adopt_extensions(&server_dir, false).await {
+        Ok(result) => result,
+        Err(e) => {
+            pb.finish_and_clear();
+            return Err(e.into());
+        }
+    };
+
+    pb.finish_and_clear();
+
+    print_adopt_report(&result);
+
+    // jars that were not found can still be converted by searching for them
+    offer_conversion(&mut client, &server_dir, &result.unmatched).await
+}
@@ -0,0 +1,61 @@
+use std::io::{IsTerminal, Write};
+
+use anyhow::Context;
+use mcctl_protocol::{ReadLogsRequest, client::Client};
+
+use crate::{
+    cli::{AnsiMode, LogsArgs},
+    server_dir::resolve_server_dir,
+};
+
+pub async fn logs(args: LogsArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let since_timestamp_millis = match args.since {
+        Some(since) => Some(
+            (chrono::Utc::now()
+                - chrono::Duration::from_std(since).context("--since is too long")?)
+            .timestamp_millis(),
+        ),
+        None => None,
+    };
+
+    let strip_ansi = match args.ansi {
+        AnsiMode::Auto => !std::io::stdout().is_terminal(),
+        AnsiMode::Always => false,
+        AnsiMode::Never => true,
+    };
+
+    let mut reader = client
+        .read_logs(ReadLogsRequest {
+            server_dir,
+            since_timestamp_millis,
+            lines: args.lines,
+            grep: args.grep,
+            strip_ansi,
+            follow: args.follow,
+        })
+        .await?;
+
+    let mut stdout = std::io::stdout().lock();
+    while let Some(line) = reader.read().await? {
+        if args.timestamps {
+            let timestamp = chrono::DateTime::from_timestamp_millis(line.timestamp_millis)
+                .context("Invalid timestamp")?
+                .with_timezone(&chrono::Local);
+            write!(stdout, "{} ", timestamp.format("%Y-%m-%d %H:%M:%S%.3f"))?;
+        }
+        stdout.write_all(&line.content)?;
+        writeln!(stdout)?;
+        // flush each line when following, so that output appears as soon as it is logged
+        if args.follow {
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use anyhow::Context;
+use mcctl_protocol::{ConnectionType, ServerStatus, client::Client};
+
+pub async fn ls() -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let servers = client.list_servers().await?;
+
+    if servers.is_empty() {
+        println!("No servers found.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<[String; 7]>::new();
+    table.push([
+        "NAME".to_string(),
+        "STATUS".to_string(),
+        "IMPLEMENTATION".to_string(),
+        "VERSION".to_string(),
+        "CONNECTION".to_string(),
+        "HOSTNAME".to_string(),
+        "DIRECTORY".to_string(),
+    ]);
+
+    for server in servers {
+        let status = match server.status.map(ServerStatus::try_from) {
+            Some(Ok(ServerStatus::Starting)) => "Starting",
+            Some(Ok(ServerStatus::Ready)) => "Ready",
+            Some(Ok(ServerStatus::Stopping)) => "Stopping",
+            Some(Ok(ServerStatus::Restarting)) => "Restarting",
+            Some(Ok(ServerStatus::BackingOff)) => "Backing off",
+            Some(Ok(ServerStatus::Failed)) => "Failed",
+            Some(Err(_)) => "Unknown",
+            None if server.manifest.is_none() => "Missing",
+            None => "Stopped",
+        };
+
+        let row = match server.manifest {
+            Some(manifest) => [
+                manifest.name,
+                status.to_string(),
+                manifest.server_implementation,
+                if manifest.build.is_empty() {
+                    manifest.version
+                } else {
+                    format!("{} ({})", manifest.version, manifest.build)
+                },
+                match ConnectionType::try_from(manifest.connection) {
+                    Ok(ConnectionType::Direct) => "direct".to_string(),
+                    Ok(ConnectionType::Proxy) => "proxy".to_string(),
+                    Err(_) => "unknown".to_string(),
+                },
+                manifest.hostname.unwrap_or_else(|| "-".to_string()),
+                server.server_dir,
+            ],
+            None => [
+                "-".to_string(),
+                status.to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                server.server_dir,
+            ],
+        };
+        table.push(row);
+    }
+
+    let column_widths = (0..7)
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for row in table {
+        for (i, cell) in row.iter().enumerate() {
+            print!("{:width$}  ", cell, width = column_widths[i]);
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -1,9 +1,22 @@
 pub mod attach;
+pub mod backup;
+pub mod clone;
 pub mod create;
+pub mod exec;
 pub mod extensions;
+pub mod import;
 pub mod kill;
+pub mod logs;
+pub mod ls;
+pub mod mv;
+pub mod player_list;
+pub mod players;
 pub mod ps;
+pub mod rcon;
+pub mod register;
 pub mod restart;
+pub mod rm;
+pub mod schedules;
 pub mod start;
 pub mod stats;
 pub mod stop;
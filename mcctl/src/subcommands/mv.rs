@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{
+    cli::MvArgs,
+    server_dir::{absolute_new_dir, resolve_server_dir_or_server},
+};
+
+pub async fn mv(args: MvArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir_or_server(&mut client, args.source).await?;
+    let destination = absolute_new_dir(args.destination)?;
+
+    let pb = indicatif::ProgressBar::new_spinner();
+    pb.set_message("Moving server...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    if let Err(e) = client.move_server(&server_dir, &destination).await {
+        pb.finish_and_clear();
+        return Err(e.into());
+    }
+
+    pb.finish_with_message(format!("Server moved to '{destination}'."));
+
+    Ok(())
+}
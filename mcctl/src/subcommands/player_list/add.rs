@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use mcctl_protocol::{AddPlayerListEntryRequest, PlayerListType, client::Client};
+
+use crate::{server_dir::resolve_server_dir, subcommands::exec::print_output};
+
+pub async fn add(
+    list_type: PlayerListType,
+    server: Option<String>,
+    server_dir: Option<PathBuf>,
+    player: String,
+    reason: Option<String>,
+) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, server, server_dir).await?;
+
+    let message = client
+        .add_player_list_entry(AddPlayerListEntryRequest {
+            server_dir,
+            list_type: list_type as i32,
+            player,
+            reason,
+        })
+        .await?;
+
+    print_output(&message);
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anyhow::Context;
+use mcctl_protocol::{PlayerListType, client::Client};
+
+use crate::{cli::PlayerListListArgs, server_dir::resolve_server_dir};
+
+pub async fn list(list_type: PlayerListType, args: PlayerListListArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let entries = client.get_player_list(server_dir, list_type).await?;
+
+    if entries.is_empty() {
+        println!("No players found.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<Vec<String>>::new();
+    table.push(match list_type {
+        PlayerListType::Whitelist => vec!["NAME".to_string(), "UUID".to_string()],
+        PlayerListType::Ops => vec!["NAME".to_string(), "UUID".to_string(), "LEVEL".to_string()],
+        PlayerListType::BannedPlayers => vec![
+            "NAME".to_string(),
+            "UUID".to_string(),
+            "EXPIRES".to_string(),
+            "REASON".to_string(),
+        ],
+    });
+
+    for entry in entries {
+        let mut row = vec![entry.name, entry.uuid];
+        match list_type {
+            PlayerListType::Whitelist => {}
+            PlayerListType::Ops => {
+                row.push(
+                    entry
+                        .level
+                        .map_or("-".to_string(), |level| level.to_string()),
+                );
+            }
+            PlayerListType::BannedPlayers => {
+                row.push(entry.expires.unwrap_or_else(|| "-".to_string()));
+                row.push(entry.reason.unwrap_or_else(|| "-".to_string()));
+            }
+        }
+        table.push(row);
+    }
+
+    let column_widths = (0..table[0].len())
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for row in table {
+        for (i, cell) in row.iter().enumerate() {
+            print!("{:width$}  ", cell, width = column_widths[i]);
+        }
+        println!();
+    }
+
+    Ok(())
+}
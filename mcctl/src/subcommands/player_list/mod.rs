@@ -0,0 +1,47 @@
+use mcctl_protocol::PlayerListType;
+
+use crate::cli::{BanList, PlayerList};
+
+mod add;
+mod list;
+mod remove;
+
+pub async fn player_list(list_type: PlayerListType, command: PlayerList) -> anyhow::Result<()> {
+    match command {
+        PlayerList::Add(args) => {
+            add::add(list_type, args.server, args.server_dir, args.player, None).await?;
+        }
+        PlayerList::Remove(args) => {
+            remove::remove(list_type, args).await?;
+        }
+        PlayerList::List(args) => {
+            list::list(list_type, args).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn ban_list(command: BanList) -> anyhow::Result<()> {
+    let list_type = PlayerListType::BannedPlayers;
+    match command {
+        BanList::Add(args) => {
+            add::add(
+                list_type,
+                args.server,
+                args.server_dir,
+                args.player,
+                args.reason,
+            )
+            .await?;
+        }
+        BanList::Remove(args) => {
+            remove::remove(list_type, args).await?;
+        }
+        BanList::List(args) => {
+            list::list(list_type, args).await?;
+        }
+    }
+
+    Ok(())
+}
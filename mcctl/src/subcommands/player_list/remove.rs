@@ -0,0 +1,22 @@
+use anyhow::Context;
+use mcctl_protocol::{PlayerListType, client::Client};
+
+use crate::{
+    cli::PlayerListRemoveArgs, server_dir::resolve_server_dir, subcommands::exec::print_output,
+};
+
+pub async fn remove(list_type: PlayerListType, args: PlayerListRemoveArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let message = client
+        .remove_player_list_entry(server_dir, list_type, args.player)
+        .await?;
+
+    print_output(&message);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anyhow::Context;
+use mcctl_protocol::{GetPlayerSessionsRequest, client::Client};
+
+use crate::{
+    cli::PlayersHistoryArgs,
+    server_dir::resolve_server_dir,
+    subcommands::players::{format_duration, format_time, print_table},
+};
+
+pub async fn history(args: PlayersHistoryArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let mut sessions = client
+        .get_player_sessions(GetPlayerSessionsRequest {
+            server_dir,
+            online_only: false,
+            since: args
+                .since
+                .map(|since| chrono::Utc::now().timestamp() - since.as_secs() as i64),
+            player: args.player,
+        })
+        .await?;
+
+    if let Some(limit) = args.limit {
+        sessions.drain(..sessions.len().saturating_sub(limit));
+    }
+
+    if sessions.is_empty() {
+        println!("No player sessions found.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<[String; 6]>::new();
+    table.push([
+        "NAME".to_string(),
+        "UUID".to_string(),
+        "JOINED".to_string(),
+        "LEFT".to_string(),
+        "DURATION".to_string(),
+        "ADDRESS".to_string(),
+    ]);
+
+    for session in sessions {
+        table.push([
+            session.name.clone(),
+            session.uuid.clone().unwrap_or_else(|| "-".to_string()),
+            format_time(session.joined_at),
+            match session.left_at {
+                Some(left_at) => format_time(left_at),
+                None => "online".to_string(),
+            },
+            format_duration(&session),
+            session.address.unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    print_table(table);
+
+    Ok(())
+}
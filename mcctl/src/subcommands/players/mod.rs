@@ -0,0 +1,55 @@
+use mcctl_protocol::PlayerSession;
+
+use crate::cli::Players;
+
+mod history;
+mod online;
+
+pub async fn players(command: Players) -> anyhow::Result<()> {
+    match command {
+        Players::Online(args) => {
+            online::online(args).await?;
+        }
+        Players::History(args) => {
+            history::history(args).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_time(timestamp: i64) -> String {
+    match chrono::DateTime::from_timestamp(timestamp, 0) {
+        Some(time) => time
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        None => "-".to_string(),
+    }
+}
+
+/// How long the session lasted, or has lasted so far if the player is online.
+fn format_duration(session: &PlayerSession) -> String {
+    let end = session
+        .left_at
+        .unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let secs = (end - session.joined_at).max(0);
+    match secs {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m{}s", s / 60, s % 60),
+        s => format!("{}h{}m{}s", s / 3600, (s % 3600) / 60, s % 60),
+    }
+}
+
+fn print_table<const N: usize>(table: Vec<[String; N]>) {
+    let column_widths = (0..N)
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for row in table {
+        for (i, cell) in row.iter().enumerate() {
+            print!("{:width$}  ", cell, width = column_widths[i]);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,53 @@
+use anyhow::Context;
+use mcctl_protocol::{GetPlayerSessionsRequest, client::Client};
+
+use crate::{
+    cli::PlayersOnlineArgs,
+    server_dir::resolve_server_dir,
+    subcommands::players::{format_duration, format_time, print_table},
+};
+
+pub async fn online(args: PlayersOnlineArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let sessions = client
+        .get_player_sessions(GetPlayerSessionsRequest {
+            server_dir,
+            online_only: true,
+            since: None,
+            player: None,
+        })
+        .await?;
+
+    if sessions.is_empty() {
+        println!("No players online.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<[String; 5]>::new();
+    table.push([
+        "NAME".to_string(),
+        "UUID".to_string(),
+        "JOINED".to_string(),
+        "DURATION".to_string(),
+        "ADDRESS".to_string(),
+    ]);
+
+    for session in sessions {
+        table.push([
+            session.name.clone(),
+            session.uuid.clone().unwrap_or_else(|| "-".to_string()),
+            format_time(session.joined_at),
+            format_duration(&session),
+            session.address.unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    print_table(table);
+
+    Ok(())
+}
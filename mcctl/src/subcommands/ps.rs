@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use anyhow::Context;
-use mcctl_protocol::{ServerStatus, client::Client};
+use mcctl_protocol::{ServerHealth, ServerStatus, client::Client};
 
 pub async fn ps() -> anyhow::Result<()> {
     let mut client = Client::connect()
@@ -10,10 +10,11 @@ pub async fn ps() -> anyhow::Result<()> {
 
     let servers = client.get_running_servers().await?;
 
-    let mut table = Vec::<[String; 6]>::new();
+    let mut table = Vec::<[String; 7]>::new();
     table.push([
         "NAME".to_string(),
         "STATUS".to_string(),
+        "HEALTH".to_string(),
         "UPTIME".to_string(),
         "PORT".to_string(),
         "PLAYERS".to_string(),
@@ -21,17 +22,36 @@ pub async fn ps() -> anyhow::Result<()> {
     ]);
 
     for server in servers {
+        let status = ServerStatus::try_from(server.status);
+        let stopped = matches!(status, Ok(ServerStatus::BackingOff | ServerStatus::Failed));
+
         table.push([
             server.name,
-            match ServerStatus::try_from(server.status) {
-                Ok(ServerStatus::Starting) => "Starting",
-                Ok(ServerStatus::Ready) => "Ready",
-                Ok(ServerStatus::Stopping) => "Stopping",
-                Ok(ServerStatus::Restarting) => "Restarting",
-                Err(_) => "Unknown",
-            }
-            .to_string(),
+            match status {
+                Ok(ServerStatus::Starting) => "Starting".to_string(),
+                Ok(ServerStatus::Ready) => "Ready".to_string(),
+                Ok(ServerStatus::Stopping) => "Stopping".to_string(),
+                Ok(ServerStatus::Restarting) => "Restarting".to_string(),
+                Ok(ServerStatus::BackingOff) => match server.restart_in_seconds {
+                    Some(secs) => format!("Backing off ({secs}s)"),
+                    None => "Backing off".to_string(),
+                },
+                Ok(ServerStatus::Failed) => "Failed".to_string(),
+                Err(_) => "Unknown".to_string(),
+            },
+            match server.health.map(ServerHealth::try_from) {
+                Some(Ok(ServerHealth::Healthy)) if server.failed_probes > 0 => {
+                    format!("Healthy ({} failed)", server.failed_probes)
+                }
+                Some(Ok(ServerHealth::Healthy)) => "Healthy".to_string(),
+                Some(Ok(ServerHealth::Unhealthy)) => {
+                    format!("Unhealthy ({} failed)", server.failed_probes)
+                }
+                Some(Err(_)) => "Unknown".to_string(),
+                None => "-".to_string(),
+            },
             match Duration::from_secs(server.uptime_seconds) {
+                _ if stopped => "-".to_string(),
                 d if d.as_secs() < 60 => format!("{}s", d.as_secs()),
                 d if d.as_secs() < 3600 => format!("{}m{}s", d.as_secs() / 60, d.as_secs() % 60),
                 d => format!(
@@ -41,7 +61,11 @@ pub async fn ps() -> anyhow::Result<()> {
                     d.as_secs() % 60
                 ),
             },
-            server.port.to_string(),
+            if stopped {
+                "-".to_string()
+            } else {
+                server.port.to_string()
+            },
             if let Some(player_count) = server.player_count
                 && let Some(max_players) = server.max_players
             {
@@ -53,7 +77,7 @@ pub async fn ps() -> anyhow::Result<()> {
         ]);
     }
 
-    let column_widths = (0..6)
+    let column_widths = (0..7)
         .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
         .collect::<Vec<_>>();
 
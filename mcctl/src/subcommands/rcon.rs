@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+use rustyline::{DefaultEditor, error::ReadlineError};
+use tokio::runtime::Handle;
+
+use crate::{
+    cli::RconArgs,
+    server_dir::resolve_server_dir,
+    subcommands::exec::{normalize_command, print_output},
+};
+
+const HISTORY_SIZE: usize = 1000;
+
+pub async fn rcon(args: RconArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+        let mut editor = DefaultEditor::with_config(
+            rustyline::Config::builder()
+                .max_history_size(HISTORY_SIZE)?
+                .auto_add_history(true)
+                .build(),
+        )?;
+
+        let history_path = history_path();
+        if let Some(history_path) = &history_path {
+            // there is no history yet on first use
+            let _ = editor.load_history(history_path);
+        }
+
+        eprintln!("Type commands without the leading slash. Press Ctrl+D to exit.");
+
+        loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            let command = normalize_command(&line);
+            if command.is_empty() {
+                continue;
+            }
+
+            match handle.block_on(client.execute_command(server_dir.clone(), command)) {
+                Ok(output) => print_output(&output),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
+
+        if let Some(history_path) = &history_path {
+            if let Some(parent) = history_path.parent() {
+                std::fs::create_dir_all(parent).context("Failed to create history directory")?;
+            }
+            editor
+                .save_history(history_path)
+                .context("Failed to save history")?;
+        }
+
+        Ok(())
+    })
+    .await??;
+
+    Ok(())
+}
+
+fn history_path() -> Option<PathBuf> {
+    let mut path = dirs::data_dir()?;
+    path.push("minecraftd");
+    path.push("mcctl_rcon_history");
+    Some(path)
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context, bail};
+use mcctl_protocol::client::Client;
+use minecraftd_manifest::ServerManifest;
+
+use crate::cli::RegisterArgs;
+
+pub async fn register(args: RegisterArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = match args.server_dir {
+        Some(p) => p,
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+
+    if !ServerManifest::manifest_path(&server_dir).exists() {
+        bail!(
+            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
+            server_dir.display()
+        );
+    }
+
+    let server_dir = server_dir
+        .canonicalize()
+        .context("Failed to canonicalize path")?
+        .to_str()
+        .context("Path is not valid UTF-8")?
+        .to_string();
+
+    client.register_server(&server_dir).await?;
+
+    println!("Registered server at '{server_dir}'.");
+
+    Ok(())
+}
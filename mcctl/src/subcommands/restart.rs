@@ -1,40 +1,32 @@
 use std::time::Duration;
 
-use anyhow::{Context, bail};
+use anyhow::Context;
+use duration_str::HumanFormat;
 use mcctl_protocol::client::Client;
-use minecraftd_manifest::ServerManifest;
 
-use crate::cli::RestartArgs;
+use crate::{cli::RestartArgs, server_dir::resolve_server_dir};
 
 pub async fn restart(args: RestartArgs) -> anyhow::Result<()> {
     let mut client = Client::connect()
         .await
         .context("Failed to connect to minecraftd")?;
 
-    let server_dir = match args.server_dir {
-        Some(p) => p,
-        None => std::env::current_dir().context("Failed to get current directory")?,
-    };
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
 
-    if !ServerManifest::manifest_path(&server_dir).exists() {
-        bail!(
-            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
-            server_dir.display()
+    if let Some(countdown) = args.countdown {
+        client.restart_server(&server_dir, Some(countdown)).await?;
+        println!(
+            "Server will restart in {}. Run `mcctl restart` or `mcctl stop` to cancel the countdown.",
+            countdown.human_format()
         );
+        return Ok(());
     }
 
-    let server_dir = server_dir
-        .canonicalize()
-        .context("Failed to canonicalize path")?
-        .to_str()
-        .context("Path is not valid UTF-8")?
-        .to_string();
-
     let pb = indicatif::ProgressBar::new_spinner();
     pb.set_message("Restarting server...");
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    client.restart_server(&server_dir).await?;
+    client.restart_server(&server_dir, None).await?;
 
     client.wait_server_ready(server_dir).await?;
 
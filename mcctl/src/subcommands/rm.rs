@@ -0,0 +1,34 @@
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::RmArgs, server_dir::resolve_server_dir};
+
+pub async fn rm(args: RmArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    if !args.yes {
+        let message = if args.keep_world {
+            format!("Delete the server at '{server_dir}', keeping its worlds?")
+        } else {
+            format!("Delete the server at '{server_dir}' and its worlds?")
+        };
+        let confirmed = inquire::Confirm::new(&message)
+            .with_default(false)
+            .prompt()?;
+
+        if !confirmed {
+            return Ok(());
+        }
+    }
+
+    client.delete_server(&server_dir, args.keep_world).await?;
+
+    println!("Server at '{server_dir}' deleted.");
+    println!("Its backups are kept.");
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anyhow::Context;
+use mcctl_protocol::client::Client;
+
+use crate::{cli::SchedulesListArgs, server_dir::resolve_server_dir};
+
+pub async fn list(args: SchedulesListArgs) -> anyhow::Result<()> {
+    let mut client = Client::connect()
+        .await
+        .context("Failed to connect to minecraftd")?;
+
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
+
+    let response = client.list_schedules(server_dir).await?;
+
+    if response.tasks.is_empty() {
+        println!("No scheduled tasks.");
+        return Ok(());
+    }
+
+    let mut table = Vec::<[String; 4]>::new();
+    table.push([
+        "NAME".to_string(),
+        "CRON".to_string(),
+        "NEXT RUN".to_string(),
+        "ACTION".to_string(),
+    ]);
+
+    for task in response.tasks {
+        table.push([
+            task.name,
+            task.cron,
+            task.next_run
+                .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".to_string()),
+            task.action,
+        ]);
+    }
+
+    let column_widths = (0..4)
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for row in table {
+        for (i, cell) in row.iter().enumerate() {
+            print!("{:width$}  ", cell, width = column_widths[i]);
+        }
+        println!();
+    }
+
+    if !response.active {
+        println!();
+        println!(
+            "Note: these tasks are not active yet. minecraftd picks up the schedules of a server once it has been started."
+        );
+    }
+
+    Ok(())
+}
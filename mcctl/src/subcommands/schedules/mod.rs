@@ -0,0 +1,13 @@
+use crate::cli::Schedules;
+
+mod list;
+
+pub async fn schedules(command: Schedules) -> anyhow::Result<()> {
+    match command {
+        Schedules::List(args) => {
+            list::list(args).await?;
+        }
+    }
+
+    Ok(())
+}
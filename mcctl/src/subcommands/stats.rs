@@ -1,36 +1,17 @@
 use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::{Context, bail};
 use crossterm::event::{Event, KeyCode, KeyModifiers};
 use mcctl_protocol::{Aggregation, GetMetricsRequest, client::Client};
-use minecraftd_manifest::ServerManifest;
 use ratatui::{DefaultTerminal, prelude::*, widgets::*};
 use tokio::runtime::Handle;
 use tui_scrollview::{ScrollView, ScrollViewState};
 
-use crate::cli::StatsArgs;
+use crate::{cli::StatsArgs, server_dir::resolve_server_dir};
 
 pub async fn stats(args: StatsArgs) -> anyhow::Result<()> {
-    let client = Client::connect().await?;
+    let mut client = Client::connect().await?;
 
-    let server_dir = match args.server_dir {
-        Some(p) => p,
-        None => std::env::current_dir().context("Failed to get current directory")?,
-    };
-
-    if !ServerManifest::manifest_path(&server_dir).exists() {
-        bail!(
-            "No server manifest found in '{}'. Are you sure this is a valid server directory?",
-            server_dir.display()
-        );
-    }
-
-    let server_dir = server_dir
-        .canonicalize()
-        .context("Failed to canonicalize path")?
-        .to_str()
-        .context("Path is not valid UTF-8")?
-        .to_string();
+    let server_dir = resolve_server_dir(&mut client, args.server, args.server_dir).await?;
 
     tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
         ratatui::run(|terminal| App::new(client, server_dir).run(terminal))?;
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, io::Cursor};
+use std::{borrow::Cow, io::Cursor, time::Duration};
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -13,8 +13,16 @@ const SERVERDATA_AUTH_RESPONSE: i32 = 2;
 const SERVERDATA_EXECCOMMAND: i32 = 2;
 const SERVERDATA_RESPONSE_VALUE: i32 = 0;
 
+/// Minecraft splits responses into packets of this many characters. Only a packet of at least
+/// this many bytes can be followed by another one.
+const MAX_RESPONSE_FRAGMENT_LENGTH: usize = 4096;
+const MAX_PACKET_LENGTH: i32 = 64 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct Client {
     stream: TcpStream,
+    next_id: i32,
+    timeout: Duration,
 }
 
 struct Packet<'a> {
@@ -25,23 +33,52 @@ struct Packet<'a> {
 
 impl Client {
     pub async fn connect(address: impl ToSocketAddrs, password: &str) -> tokio::io::Result<Self> {
-        let stream = TcpStream::connect(address).await?;
-        let mut socket = Client { stream };
+        Self::connect_with_timeout(address, password, DEFAULT_TIMEOUT).await
+    }
 
-        socket
-            .send_packet(&Packet {
-                id: 1,
-                type_: SERVERDATA_AUTH,
-                body: Cow::Borrowed(password.as_bytes()),
-            })
-            .await?;
+    /// Connects and authenticates within `timeout`, which also applies to each command.
+    pub async fn connect_with_timeout(
+        address: impl ToSocketAddrs,
+        password: &str,
+        timeout: Duration,
+    ) -> tokio::io::Result<Self> {
+        with_timeout(timeout, async {
+            let stream = TcpStream::connect(address).await?;
+            let mut socket = Client {
+                stream,
+                next_id: 1,
+                timeout,
+            };
 
-        let response = socket.receive_packet().await?;
-        if response.type_ != SERVERDATA_AUTH_RESPONSE || response.id == -1 {
-            return Err(tokio::io::Error::other("Authentication failed"));
-        }
+            let id = socket.next_id();
+            socket
+                .send_packet(&Packet {
+                    id,
+                    type_: SERVERDATA_AUTH,
+                    body: Cow::Borrowed(password.as_bytes()),
+                })
+                .await?;
+
+            // some servers send an empty response value before the auth response
+            let response = loop {
+                let response = socket.receive_packet().await?;
+                if response.type_ == SERVERDATA_AUTH_RESPONSE {
+                    break response;
+                }
+            };
+            if response.id == -1 {
+                return Err(tokio::io::Error::other("Authentication failed"));
+            }
 
-        Ok(socket)
+            Ok(socket)
+        })
+        .await
+    }
+
+    fn next_id(&mut self) -> i32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        id
     }
 
     async fn send_packet(&mut self, packet: &Packet<'_>) -> tokio::io::Result<()> {
@@ -80,6 +117,9 @@ impl Client {
         self.stream.read_exact(&mut type_buf).await?;
         let type_ = i32::from_le_bytes(type_buf);
 
+        if length > MAX_PACKET_LENGTH {
+            return Err(tokio::io::Error::other("Packet is too long"));
+        }
         let body_length = length - 10;
         if body_length < 0 {
             return Err(tokio::io::Error::other("Invalid packet length"));
@@ -107,19 +147,182 @@ impl Client {
         })
     }
 
-    pub async fn execute_command(&mut self, command: &str) -> tokio::io::Result<()> {
+    /// Returns the response text of the command, reassembled if it was split into several
+    /// packets.
+    ///
+    /// If this fails, the state of the connection is unknown and a new client should be connected.
+    pub async fn execute_command(&mut self, command: &str) -> tokio::io::Result<String> {
+        with_timeout(self.timeout, self.do_execute_command(command)).await
+    }
+
+    async fn do_execute_command(&mut self, command: &str) -> tokio::io::Result<String> {
+        let id = self.next_id();
         self.send_packet(&Packet {
-            id: 2,
+            id,
             type_: SERVERDATA_EXECCOMMAND,
             body: Cow::Borrowed(command.as_bytes()),
         })
         .await?;
 
-        let response = self.receive_packet().await?;
-        if response.type_ != SERVERDATA_RESPONSE_VALUE {
-            return Err(tokio::io::Error::other("Invalid response type"));
+        let mut body = Vec::new();
+        let mut sentinel_id = None;
+        loop {
+            let response = self.receive_packet().await?;
+            if Some(response.id) == sentinel_id {
+                break;
+            }
+            if response.id != id {
+                // a late response to a sentinel of an earlier command
+                continue;
+            }
+            if response.type_ != SERVERDATA_RESPONSE_VALUE {
+                return Err(tokio::io::Error::other("Invalid response type"));
+            }
+
+            body.extend_from_slice(&response.body);
+
+            if sentinel_id.is_none() {
+                if response.body.len() < MAX_RESPONSE_FRAGMENT_LENGTH {
+                    break;
+                }
+
+                // the server handles packets in order, so the response to this request comes
+                // after the last packet of the command's response
+                let next_id = self.next_id();
+                sentinel_id = Some(next_id);
+                self.send_packet(&Packet {
+                    id: next_id,
+                    type_: SERVERDATA_RESPONSE_VALUE,
+                    body: Cow::Borrowed(&[]),
+                })
+                .await?;
+            }
         }
 
-        Ok(())
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl Future<Output = tokio::io::Result<T>>,
+) -> tokio::io::Result<T> {
+    match tokio::time::timeout(timeout, future).await {
+        Ok(result) => result,
+        Err(_) => Err(tokio::io::Error::new(
+            tokio::io::ErrorKind::TimedOut,
+            "RCON request timed out",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves one connection, replying to the command with the given fragments, and to every
+    /// later request the way Minecraft replies to packets of an unknown type.
+    async fn serve(listener: TcpListener, fragments: Vec<Vec<u8>>, stray_packet: bool) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut server = Client {
+            stream,
+            next_id: 1,
+            timeout: DEFAULT_TIMEOUT,
+        };
+
+        let auth = server.receive_packet().await.unwrap();
+        assert_eq!(auth.type_, SERVERDATA_AUTH);
+        server
+            .send_packet(&Packet {
+                id: auth.id,
+                type_: SERVERDATA_AUTH_RESPONSE,
+                body: Cow::Borrowed(&[]),
+            })
+            .await
+            .unwrap();
+
+        let command = server.receive_packet().await.unwrap();
+        assert_eq!(command.type_, SERVERDATA_EXECCOMMAND);
+
+        if stray_packet {
+            server
+                .send_packet(&Packet {
+                    id: command.id - 1,
+                    type_: SERVERDATA_RESPONSE_VALUE,
+                    body: Cow::Borrowed(b"late"),
+                })
+                .await
+                .unwrap();
+        }
+
+        for fragment in fragments {
+            server
+                .send_packet(&Packet {
+                    id: command.id,
+                    type_: SERVERDATA_RESPONSE_VALUE,
+                    body: Cow::Owned(fragment),
+                })
+                .await
+                .unwrap();
+        }
+
+        while let Ok(request) = server.receive_packet().await {
+            server
+                .send_packet(&Packet {
+                    id: request.id,
+                    type_: SERVERDATA_RESPONSE_VALUE,
+                    body: Cow::Borrowed(b"Unknown request 0"),
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn execute(fragments: Vec<Vec<u8>>, stray_packet: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener, fragments, stray_packet));
+
+        let mut client = Client::connect(address, "password").await.unwrap();
+        let response = client.execute_command("list").await.unwrap();
+
+        drop(client);
+        server.await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn single_packet_response() {
+        assert_eq!(execute(vec![b"hello".to_vec()], false).await, "hello");
+    }
+
+    #[tokio::test]
+    async fn fragmented_response() {
+        let fragments = vec![
+            vec![b'a'; MAX_RESPONSE_FRAGMENT_LENGTH],
+            vec![b'b'; MAX_RESPONSE_FRAGMENT_LENGTH],
+            vec![b'c'; 10],
+        ];
+        let expected = fragments.concat();
+        assert_eq!(
+            execute(fragments, false).await.as_bytes(),
+            expected.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn response_of_exactly_one_fragment() {
+        let fragment = vec![b'a'; MAX_RESPONSE_FRAGMENT_LENGTH];
+        assert_eq!(
+            execute(vec![fragment.clone()], false).await.as_bytes(),
+            fragment.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_late_responses() {
+        assert_eq!(execute(vec![b"hello".to_vec()], true).await, "hello");
     }
 }
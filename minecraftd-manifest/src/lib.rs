@@ -1,8 +1,16 @@
 use std::{
+    collections::HashSet,
     ffi::OsString,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
+use croner::{
+    Cron,
+    parser::{CronParser, Seconds, Year},
+};
+use duration_str::HumanFormat;
+use minecraft_protocol::text_component::TextComponent;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
@@ -23,6 +31,16 @@ pub struct ServerManifest {
     #[serde(default)]
     pub restart_on_failure: bool,
     #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    /// Overrides `runner.start_timeout` of the daemon configuration.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub start_timeout: Option<Duration>,
+    #[serde(default)]
     pub auto_start: bool,
     #[serde(default)]
     pub auto_update: bool,
@@ -30,6 +48,18 @@ pub struct ServerManifest {
     pub connection: Connection,
     #[serde(default)]
     pub extensions: Vec<ExtensionEntry>,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default, deserialize_with = "deserialize_scheduled_tasks")]
+    pub schedules: Vec<ScheduledTask>,
+    #[serde(default)]
+    pub sleep: SleepConfig,
+    #[serde(default)]
+    pub liveness_probe: LivenessProbe,
+    /// Shares the whitelist, operators and bans with the other servers of this group in
+    /// `player_lists` of the daemon configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_list_group: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,12 +98,225 @@ pub enum ExtensionType {
     Plugin,
 }
 
+/// How a server is restarted after crashes when `restart_on_failure` is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartPolicy {
+    /// Number of restarts allowed within `window`. Once exceeded, the server is given up on.
+    #[serde(default = "default_restart_policy_max_retries")]
+    pub max_retries: u32,
+    #[serde(
+        default = "default_restart_policy_window",
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub window: Duration,
+    /// Delay before the first restart. It doubles with each crash within `window`.
+    #[serde(
+        default = "default_restart_policy_initial_backoff",
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub initial_backoff: Duration,
+    #[serde(
+        default = "default_restart_policy_max_backoff",
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: default_restart_policy_max_retries(),
+            window: default_restart_policy_window(),
+            initial_backoff: default_restart_policy_initial_backoff(),
+            max_backoff: default_restart_policy_max_backoff(),
+        }
+    }
+}
+
+fn default_restart_policy_max_retries() -> u32 {
+    5
+}
+
+fn default_restart_policy_window() -> Duration {
+    Duration::from_mins(30)
+}
+
+fn default_restart_policy_initial_backoff() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_restart_policy_max_backoff() -> Duration {
+    Duration::from_mins(5)
+}
+
+/// Labels of the backups minecraftd takes by itself, which schedules must not use.
+const RESERVED_BACKUP_LABELS: &[&str] = &["manual", "pre-restore", "pre-update"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupConfig {
+    #[serde(default, deserialize_with = "deserialize_backup_schedules")]
+    pub schedules: Vec<BackupSchedule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSchedule {
+    /// Used to tell backups of different schedules apart, e.g. "hourly" or "daily". Part of the
+    /// backup IDs, so only ASCII letters, digits, `-` and `_` are allowed.
+    #[serde(deserialize_with = "deserialize_backup_label")]
+    pub name: String,
+    #[serde(
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub interval: Duration,
+    /// Number of backups of this schedule to keep. Older ones are deleted.
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SleepConfig {
+    /// Starts the stopped server when a player tries to join through the proxy. Only applies to
+    /// `Connection::Proxy`.
+    #[serde(default)]
+    pub wake_on_connect: bool,
+    /// Stops the server after it has had no players for this long.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub idle_timeout: Option<Duration>,
+}
+
+/// Periodic check that a ready server is still responsive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LivenessProbe {
+    #[serde(default = "default_liveness_probe_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub method: LivenessProbeMethod,
+    #[serde(
+        default = "default_liveness_probe_interval",
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub interval: Duration,
+    #[serde(
+        default = "default_liveness_probe_timeout",
+        serialize_with = "serialize_duration",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub timeout: Duration,
+    /// Number of consecutive failed probes after which the server is considered unhealthy.
+    #[serde(default = "default_liveness_probe_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Sends SIGQUIT to the server process when it becomes unhealthy, which makes the JVM print a
+    /// thread dump to the console.
+    #[serde(default)]
+    pub dump_threads: bool,
+    /// Kills and restarts the server when it becomes unhealthy.
+    #[serde(default)]
+    pub restart: bool,
+}
+
+impl Default for LivenessProbe {
+    fn default() -> Self {
+        Self {
+            enabled: default_liveness_probe_enabled(),
+            method: LivenessProbeMethod::default(),
+            interval: default_liveness_probe_interval(),
+            timeout: default_liveness_probe_timeout(),
+            failure_threshold: default_liveness_probe_failure_threshold(),
+            dump_threads: false,
+            restart: false,
+        }
+    }
+}
+
+fn default_liveness_probe_enabled() -> bool {
+    true
+}
+
+fn default_liveness_probe_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_liveness_probe_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_liveness_probe_failure_threshold() -> u32 {
+    3
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LivenessProbeMethod {
+    /// Server List Ping to the server port.
+    #[default]
+    Slp,
+    /// Runs the `list` command over RCON.
+    Rcon,
+    /// Requests the server metrics from the minecraftd bridge plugin.
+    Bridge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    /// Identifies the task in logs and in `mcctl schedules list`. Backup tasks label their backups
+    /// with it, so the same rules as for backup schedule names apply.
+    #[serde(deserialize_with = "deserialize_backup_label")]
+    pub name: String,
+    /// Standard 5-field cron expression evaluated in the local time zone, e.g. `0 4 * * *` for
+    /// every day at 4:00.
+    #[serde(deserialize_with = "deserialize_cron")]
+    pub cron: Cron,
+    pub action: ScheduledAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduledAction {
+    Restart {
+        /// Warns the players and waits this long before restarting.
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_duration",
+            deserialize_with = "duration_str::deserialize_duration"
+        )]
+        countdown: Option<Duration>,
+    },
+    Stop,
+    Start,
+    /// Runs a console command over RCON.
+    Command {
+        command: String,
+    },
+    /// Shows a message to all players with `tellraw`.
+    Broadcast {
+        message: TextComponent,
+    },
+    /// Takes a backup labeled with the task name.
+    Backup {
+        /// Number of backups of this task to keep. Older ones are deleted. All are kept if unset.
+        #[serde(default)]
+        keep: Option<usize>,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("Failed to parse manifest file: {0}")]
     ParseError(#[from] serde_yml::Error),
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
 }
 
 impl ServerManifest {
@@ -98,10 +341,17 @@ impl ServerManifest {
             ],
             java_runtime,
             restart_on_failure: true,
+            restart_policy: RestartPolicy::default(),
+            start_timeout: None,
             auto_start: true,
             auto_update: false,
             connection: Connection::Direct,
             extensions: Vec::new(),
+            backup: BackupConfig::default(),
+            schedules: Vec::new(),
+            sleep: SleepConfig::default(),
+            liveness_probe: LivenessProbe::default(),
+            player_list_group: None,
         }
     }
 
@@ -113,6 +363,23 @@ impl ServerManifest {
         let manifest_path = Self::manifest_path(server_dir);
         let manifest_data = tokio::fs::read_to_string(&manifest_path).await?;
         let manifest: ServerManifest = serde_yml::from_str(&manifest_data)?;
+
+        // backup tasks and backup schedules label their backups with their names, so they would
+        // prune each other's backups
+        if let Some(task) = manifest.schedules.iter().find(|task| {
+            matches!(task.action, ScheduledAction::Backup { .. })
+                && manifest
+                    .backup
+                    .schedules
+                    .iter()
+                    .any(|schedule| schedule.name == task.name)
+        }) {
+            return Err(Error::InvalidManifest(format!(
+                "Backup task '{}' has the same name as a backup schedule",
+                task.name
+            )));
+        }
+
         Ok(manifest)
     }
 
@@ -151,3 +418,93 @@ where
     let command_strs: Vec<String> = Vec::deserialize(deserializer)?;
     Ok(command_strs.into_iter().map(OsString::from).collect())
 }
+
+fn serialize_duration<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    duration.human_format().serialize(serializer)
+}
+
+fn serialize_option_duration<S>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    duration
+        .map(|duration| duration.human_format())
+        .serialize(serializer)
+}
+
+fn deserialize_cron<'de, D>(deserializer: D) -> Result<Cron, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    // tasks are checked a few times a minute, so sub-minute schedules are not supported
+    CronParser::builder()
+        .seconds(Seconds::Disallowed)
+        .year(Year::Disallowed)
+        .build()
+        .parse(&pattern)
+        .map_err(serde::de::Error::custom)
+}
+
+fn deserialize_backup_label<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let label = String::deserialize(deserializer)?;
+    validate_backup_label(&label).map_err(serde::de::Error::custom)?;
+    Ok(label)
+}
+
+fn validate_backup_label(label: &str) -> Result<(), String> {
+    if label.is_empty()
+        || !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid name '{label}': only ASCII letters, digits, '-' and '_' are allowed"
+        ));
+    }
+    if RESERVED_BACKUP_LABELS.contains(&label) {
+        return Err(format!(
+            "Invalid name '{label}': it is reserved for backups taken by minecraftd"
+        ));
+    }
+    Ok(())
+}
+
+fn deserialize_backup_schedules<'de, D>(deserializer: D) -> Result<Vec<BackupSchedule>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let schedules = Vec::<BackupSchedule>::deserialize(deserializer)?;
+    ensure_unique_names(schedules.iter().map(|schedule| schedule.name.as_str()))
+        .map_err(serde::de::Error::custom)?;
+    Ok(schedules)
+}
+
+fn deserialize_scheduled_tasks<'de, D>(deserializer: D) -> Result<Vec<ScheduledTask>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let tasks = Vec::<ScheduledTask>::deserialize(deserializer)?;
+    ensure_unique_names(tasks.iter().map(|task| task.name.as_str()))
+        .map_err(serde::de::Error::custom)?;
+    Ok(tasks)
+}
+
+fn ensure_unique_names<'a>(names: impl Iterator<Item = &'a str>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(format!("Duplicate name '{name}'"));
+        }
+    }
+    Ok(())
+}
@@ -2,7 +2,7 @@ use tokio::task::JoinSet;
 
 use crate::{
     config::get_config, extension::providers::get_extension_provider, runner,
-    server_implementations::get_server_implementation,
+    server_implementations::get_server_implementation, update_rollback,
 };
 
 pub fn init() {
@@ -48,6 +48,7 @@ async fn do_auto_update() -> anyhow::Result<()> {
         if let Some((version, build)) = server_implementation
             .is_newer_version_available(&manifest.version, &manifest.build, true)
             .await?
+            && !update_rollback::is_failed_server_version(id, &version.name, &build.name).await
         {
             debug!(
                 "New version available for server {id} (version: {}, build: {})",
@@ -68,6 +69,13 @@ async fn do_auto_update() -> anyhow::Result<()> {
                 )
                 .await
                 .unwrap_or(None)
+                && !update_rollback::is_failed_extension_version(
+                    id,
+                    &extension.provider,
+                    &extension.id,
+                    &new_version.id,
+                )
+                .await
             {
                 debug!(
                     "New version available for extension {} (version: {})",
@@ -97,14 +105,16 @@ async fn do_auto_update() -> anyhow::Result<()> {
                 {
                     let message = get_config().messages.server_restarting_for_update.clone();
                     runner::tellraw(id, "@a", message).await?;
-                    tokio::time::sleep(
+                    runner::restart_server_with_countdown(
+                        &server_dir,
                         get_config()
                             .auto_update
                             .notify_players_before_restart_interval,
                     )
-                    .await;
+                    .await?;
+                } else {
+                    runner::restart_server(&server_dir).await?;
                 }
-                runner::restart_server(&server_dir).await?;
                 Ok(())
             }
             .await;
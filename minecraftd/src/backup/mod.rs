@@ -0,0 +1,644 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    backup::store::{ChunkState, ChunkStore},
+    config::get_config,
+    runner::{self, ServerStatus},
+    util::server_properties::ServerProperties,
+};
+
+mod offsite;
+mod s3;
+mod snapshot;
+mod store;
+
+const BACKUP_CHECK_INTERVAL_SECS: u64 = 60;
+const METADATA_FILE_NAME: &str = "backup.json";
+const INDEX_FILE_NAME: &str = "index.json";
+const PARTIAL_BACKUP_SUFFIX: &str = ".partial";
+const DEFAULT_LEVEL_NAME: &str = "world";
+pub const MANUAL_BACKUP_LABEL: &str = "manual";
+const PRE_RESTORE_BACKUP_LABEL: &str = "pre-restore";
+
+// only one backup runs at a time to avoid saturating the disk
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    pub created_at: DateTime<Utc>,
+    /// Name of the schedule that created the backup, or a fixed label for other backups.
+    pub label: String,
+    /// World directories contained in the backup, relative to the server directory.
+    pub worlds: Vec<String>,
+}
+
+pub struct Backup {
+    pub id: String,
+    pub path: PathBuf,
+    pub metadata: BackupMetadata,
+}
+
+impl Backup {
+    fn index_path(&self) -> PathBuf {
+        self.path.join(INDEX_FILE_NAME)
+    }
+}
+
+pub struct VerificationResult {
+    pub backup_id: String,
+    pub checked_chunks: usize,
+    pub missing_chunks: Vec<String>,
+    pub corrupt_chunks: Vec<String>,
+}
+
+pub fn init() {
+    offsite::init();
+
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(BACKUP_CHECK_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = run_scheduled_backups().await {
+                error!("Scheduled backup error: {err:?}");
+            }
+        }
+    });
+}
+
+async fn run_scheduled_backups() -> anyhow::Result<()> {
+    for id in runner::get_running_server_ids().await {
+        let Some(manifest) = runner::get_server_manifest(id).await else {
+            continue;
+        };
+
+        if manifest.backup.schedules.is_empty() {
+            continue;
+        }
+
+        if runner::get_server_status(id).await != Some(ServerStatus::Ready) {
+            continue;
+        }
+
+        let Some(server_dir) = runner::get_server_dir(id).await else {
+            continue;
+        };
+
+        let backups = match list_backups(id).await {
+            Ok(backups) => backups,
+            Err(err) => {
+                error!(
+                    "Failed to list backups for server at '{}': {err:?}",
+                    server_dir.display()
+                );
+                continue;
+            }
+        };
+
+        for schedule in &manifest.backup.schedules {
+            let last_backup = backups
+                .iter()
+                .filter(|b| b.metadata.label == schedule.name)
+                .map(|b| b.metadata.created_at)
+                .max();
+
+            let due = match last_backup {
+                Some(last_backup) => (Utc::now() - last_backup)
+                    .to_std()
+                    .is_ok_and(|elapsed| elapsed >= schedule.interval),
+                None => true,
+            };
+
+            if !due {
+                continue;
+            }
+
+            info!(
+                "Creating scheduled backup '{}' for server at '{}'",
+                schedule.name,
+                server_dir.display()
+            );
+
+            if let Err(err) = create_backup(&server_dir, id, &schedule.name).await {
+                error!(
+                    "Failed to create backup for server at '{}': {err:?}",
+                    server_dir.display()
+                );
+                send_alert("backup_failed", || Alert {
+                    severity: Severity::Error,
+                    title: "Backup failed".to_string(),
+                    message: format!(
+                        "Scheduled backup '{}' of server at `{}` failed: {err}",
+                        schedule.name,
+                        server_dir.display()
+                    ),
+                })
+                .await;
+                continue;
+            }
+
+            if let Err(err) = prune_backups(id, &schedule.name, schedule.keep).await {
+                error!(
+                    "Failed to prune backups of schedule '{}' for server at '{}': {err:?}",
+                    schedule.name,
+                    server_dir.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn backups_root_dir() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("backups");
+    Ok(path)
+}
+
+fn backups_dir(server_id: Uuid) -> anyhow::Result<PathBuf> {
+    Ok(backups_root_dir()?.join(server_id.to_string()))
+}
+
+fn chunk_store() -> anyhow::Result<ChunkStore> {
+    Ok(ChunkStore::new(backups_root_dir()?.join("chunks")))
+}
+
+/// Returns the world directories of the server, relative to the server directory.
+pub async fn world_dirs(server_dir: &Path) -> Vec<String> {
+    let level_name = ServerProperties::load(server_dir)
+        .await
+        .ok()
+        .and_then(|p| p.get("level-name").map(str::to_string))
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| DEFAULT_LEVEL_NAME.to_string());
+
+    // Bukkit-based servers keep the nether and the end in separate directories
+    [
+        level_name.clone(),
+        format!("{level_name}_nether"),
+        format!("{level_name}_the_end"),
+    ]
+    .into_iter()
+    .filter(|world| server_dir.join(world).is_dir())
+    .collect()
+}
+
+pub async fn create_backup(
+    server_dir: &Path,
+    server_id: Uuid,
+    label: &str,
+) -> anyhow::Result<Backup> {
+    let _lock = BACKUP_LOCK.lock().await;
+    create_backup_locked(server_dir, server_id, label).await
+}
+
+async fn create_backup_locked(
+    server_dir: &Path,
+    server_id: Uuid,
+    label: &str,
+) -> anyhow::Result<Backup> {
+    let worlds = world_dirs(server_dir).await;
+    if worlds.is_empty() {
+        bail!("No world directories found in '{}'", server_dir.display());
+    }
+
+    let created_at = Utc::now();
+    let backup_id = format!("{}-{}", created_at.format("%Y%m%dT%H%M%SZ"), label);
+    let backups_dir = backups_dir(server_id)?;
+    let path = backups_dir.join(&backup_id);
+    let partial_path = backups_dir.join(format!("{backup_id}{PARTIAL_BACKUP_SUFFIX}"));
+
+    if path.exists() {
+        bail!("Backup '{}' already exists", backup_id);
+    }
+
+    let running = match runner::get_server_status(server_id).await {
+        Some(ServerStatus::Ready) => true,
+        Some(_) => bail!("Cannot back up a server while it is starting or stopping"),
+        None => false,
+    };
+
+    // unchanged files are taken over from the newest backup without being read again
+    let previous_index = match list_backups(server_id).await?.last() {
+        Some(previous) => {
+            let index_path = previous.index_path();
+            tokio::task::spawn_blocking(move || snapshot::load_index(&index_path))
+                .await
+                .unwrap()
+                .unwrap_or_else(|err| {
+                    warn!("Ignoring unreadable index of previous backup: {err:?}");
+                    Vec::new()
+                })
+        }
+        None => Vec::new(),
+    };
+
+    // the server must not write to the world while it is being copied
+    if running {
+        runner::execute_command(server_id, "save-off")
+            .await
+            .context("Failed to disable world saving")?;
+    }
+
+    let metadata = BackupMetadata {
+        created_at,
+        label: label.to_string(),
+        worlds,
+    };
+
+    let result: anyhow::Result<()> = async {
+        if running {
+            runner::execute_command(server_id, "save-all flush")
+                .await
+                .context("Failed to save the world")?;
+        }
+
+        let store = chunk_store()?;
+        let snapshot_server_dir = server_dir.to_path_buf();
+        let worlds = metadata.worlds.clone();
+        let index = tokio::task::spawn_blocking(move || {
+            snapshot::take(&store, &snapshot_server_dir, &worlds, &previous_index)
+        })
+        .await
+        .unwrap()?;
+
+        tokio::fs::create_dir_all(&partial_path)
+            .await
+            .context("Failed to create backup directory")?;
+
+        let index_path = partial_path.join(INDEX_FILE_NAME);
+        tokio::task::spawn_blocking(move || snapshot::save_index(&index_path, &index))
+            .await
+            .unwrap()?;
+
+        tokio::fs::write(
+            partial_path.join(METADATA_FILE_NAME),
+            serde_json::to_string(&metadata)?,
+        )
+        .await
+        .context("Failed to write backup metadata")?;
+
+        tokio::fs::rename(&partial_path, &path)
+            .await
+            .context("Failed to finalize backup")?;
+
+        Ok(())
+    }
+    .await;
+
+    if running && let Err(err) = runner::execute_command(server_id, "save-on").await {
+        error!(
+            "Failed to re-enable world saving for server at '{}': {err:?}",
+            server_dir.display()
+        );
+    }
+
+    if let Err(err) = result {
+        let _ = tokio::fs::remove_dir_all(&partial_path).await;
+        return Err(err);
+    }
+
+    info!("Created backup '{}' at '{}'", backup_id, path.display());
+
+    offsite::request_sync();
+
+    Ok(Backup {
+        id: backup_id,
+        path,
+        metadata,
+    })
+}
+
+/// Replaces the worlds of a stopped server with the ones in the backup.
+/// The current worlds are backed up first and the safety backup is returned,
+/// unless the server has no world yet.
+pub async fn restore_backup(
+    server_dir: &Path,
+    server_id: Uuid,
+    backup_id: &str,
+) -> anyhow::Result<Option<Backup>> {
+    let _lock = BACKUP_LOCK.lock().await;
+
+    if runner::is_server_running(server_dir).await? {
+        bail!("Cannot restore a backup while the server is running");
+    }
+
+    let backup = find_backup(server_id, backup_id).await?;
+    let store = chunk_store()?;
+
+    let index_path = backup.index_path();
+    let index = tokio::task::spawn_blocking(move || snapshot::load_index(&index_path))
+        .await
+        .unwrap()?;
+
+    // refuse before touching the current worlds rather than leaving a half-restored server
+    if let Some(missing) = index
+        .iter()
+        .flat_map(|entry| entry.chunks())
+        .find(|hash| !store.contains(hash))
+    {
+        bail!(
+            "Backup '{}' is incomplete: chunk {missing} is missing",
+            backup.id
+        );
+    }
+
+    let current_worlds = world_dirs(server_dir).await;
+    let safety_backup = if current_worlds.is_empty() {
+        None
+    } else {
+        Some(
+            create_backup_locked(server_dir, server_id, PRE_RESTORE_BACKUP_LABEL)
+                .await
+                .context("Failed to back up the current worlds")?,
+        )
+    };
+
+    for world in &current_worlds {
+        tokio::fs::remove_dir_all(server_dir.join(world))
+            .await
+            .with_context(|| format!("Failed to remove world '{world}'"))?;
+    }
+
+    let restore_server_dir = server_dir.to_path_buf();
+    let worlds = backup.metadata.worlds.clone();
+    tokio::task::spawn_blocking(move || {
+        snapshot::restore(&store, &restore_server_dir, &worlds, &index)
+    })
+    .await
+    .unwrap()?;
+
+    info!(
+        "Restored backup '{}' to server at '{}'",
+        backup.id,
+        server_dir.display()
+    );
+
+    // pruned only now, since the restored backup may itself be an old safety backup. The one
+    // just taken is always kept.
+    if safety_backup.is_some()
+        && let Err(err) = prune_backups_locked(
+            server_id,
+            PRE_RESTORE_BACKUP_LABEL,
+            get_config().backup.keep_pre_restore.max(1),
+        )
+        .await
+    {
+        error!(
+            "Failed to prune pre-restore backups for server at '{}': {err:?}",
+            server_dir.display()
+        );
+    }
+
+    Ok(safety_backup)
+}
+
+pub async fn delete_backup(server_id: Uuid, backup_id: &str) -> anyhow::Result<()> {
+    let _lock = BACKUP_LOCK.lock().await;
+
+    let backup = find_backup(server_id, backup_id).await?;
+    tokio::fs::remove_dir_all(&backup.path)
+        .await
+        .with_context(|| format!("Failed to remove backup '{}'", backup.id))?;
+
+    info!("Deleted backup '{}'", backup.id);
+    offsite::request_sync();
+
+    collect_garbage_locked().await
+}
+
+/// Checks that every chunk referenced by the backups is present and intact.
+/// If `backup_id` is `None`, all backups of the server are checked.
+pub async fn verify_backups(
+    server_id: Uuid,
+    backup_id: Option<&str>,
+) -> anyhow::Result<Vec<VerificationResult>> {
+    let _lock = BACKUP_LOCK.lock().await;
+
+    let backups = match backup_id {
+        Some(backup_id) => vec![find_backup(server_id, backup_id).await?],
+        None => list_backups(server_id).await?,
+    };
+
+    let store = chunk_store()?;
+    let index_paths = backups
+        .iter()
+        .map(|backup| (backup.id.clone(), backup.index_path()))
+        .collect::<Vec<_>>();
+
+    tokio::task::spawn_blocking(move || {
+        index_paths
+            .into_iter()
+            .map(|(backup_id, index_path)| {
+                let index = snapshot::load_index(&index_path)?;
+                let chunks = index
+                    .iter()
+                    .flat_map(|entry| entry.chunks())
+                    .collect::<HashSet<_>>();
+
+                let mut result = VerificationResult {
+                    backup_id,
+                    checked_chunks: chunks.len(),
+                    missing_chunks: Vec::new(),
+                    corrupt_chunks: Vec::new(),
+                };
+                for hash in chunks {
+                    match store.check_chunk(hash) {
+                        ChunkState::Ok => {}
+                        ChunkState::Missing => result.missing_chunks.push(hash.clone()),
+                        ChunkState::Corrupt => result.corrupt_chunks.push(hash.clone()),
+                    }
+                }
+
+                Ok(result)
+            })
+            .collect()
+    })
+    .await
+    .unwrap()
+}
+
+/// Removes chunks that are no longer referenced by any backup of any server, along with
+/// leftovers of interrupted backups. The caller must hold `BACKUP_LOCK`.
+///
+/// If the index of a backup cannot be read, no chunks are removed, as they may belong to it.
+async fn collect_garbage_locked() -> anyhow::Result<()> {
+    let mut referenced = HashSet::new();
+    let mut all_indexes_read = true;
+
+    for server_id in server_ids_with_backups().await? {
+        let mut entries = tokio::fs::read_dir(backups_dir(server_id)?).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with(PARTIAL_BACKUP_SUFFIX)
+            {
+                debug!("Removing partial backup '{}'", entry.path().display());
+                tokio::fs::remove_dir_all(entry.path()).await?;
+            }
+        }
+
+        for backup in list_backups(server_id).await? {
+            let index_path = backup.index_path();
+            match tokio::task::spawn_blocking(move || snapshot::load_index(&index_path))
+                .await
+                .unwrap()
+            {
+                Ok(index) => {
+                    referenced.extend(index.into_iter().flat_map(|entry| entry.chunks().to_vec()))
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to read the index of backup '{}', keeping all chunks: {err:?}",
+                        backup.id
+                    );
+                    all_indexes_read = false;
+                }
+            }
+        }
+    }
+
+    if !all_indexes_read {
+        return Ok(());
+    }
+
+    let store = chunk_store()?;
+    let (removed_chunks, freed_bytes) =
+        tokio::task::spawn_blocking(move || store.remove_unreferenced(&referenced))
+            .await
+            .unwrap()?;
+
+    if removed_chunks > 0 {
+        info!("Removed {removed_chunks} unreferenced backup chunks ({freed_bytes} bytes)");
+    }
+
+    Ok(())
+}
+
+async fn find_backup(server_id: Uuid, backup_id: &str) -> anyhow::Result<Backup> {
+    // looked up from the listing rather than joined onto the path so that ids
+    // coming from clients cannot point outside the backups directory
+    list_backups(server_id)
+        .await?
+        .into_iter()
+        .find(|b| b.id == backup_id)
+        .with_context(|| format!("Backup '{backup_id}' not found"))
+}
+
+async fn server_ids_with_backups() -> anyhow::Result<Vec<Uuid>> {
+    let root_dir = backups_root_dir()?;
+    if !root_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut server_ids = Vec::new();
+
+    let mut entries = tokio::fs::read_dir(&root_dir)
+        .await
+        .context("Failed to read backups directory")?;
+    while let Some(entry) = entries.next_entry().await? {
+        // the chunk store and other files live next to the per-server directories
+        if let Ok(server_id) = entry.file_name().to_string_lossy().parse::<Uuid>() {
+            server_ids.push(server_id);
+        }
+    }
+
+    Ok(server_ids)
+}
+
+/// Ordered from oldest to newest
+pub async fn list_backups(server_id: Uuid) -> anyhow::Result<Vec<Backup>> {
+    let backups_dir = backups_dir(server_id)?;
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+
+    let mut entries = tokio::fs::read_dir(&backups_dir)
+        .await
+        .context("Failed to read backups directory")?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        // partial backups do not have a metadata file yet
+        let Ok(metadata) = tokio::fs::read_to_string(path.join(METADATA_FILE_NAME)).await else {
+            continue;
+        };
+        let metadata: BackupMetadata = match serde_json::from_str(&metadata) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                warn!(
+                    "Ignoring backup at '{}' with invalid metadata: {err:?}",
+                    path.display()
+                );
+                continue;
+            }
+        };
+        if let Some(world) = metadata
+            .worlds
+            .iter()
+            .find(|world| !snapshot::is_valid_relative_path(world))
+        {
+            warn!(
+                "Ignoring backup at '{}' with invalid world directory '{world}'",
+                path.display()
+            );
+            continue;
+        }
+
+        backups.push(Backup {
+            id: entry.file_name().to_string_lossy().to_string(),
+            path,
+            metadata,
+        });
+    }
+
+    backups.sort_by_key(|b| b.metadata.created_at);
+
+    Ok(backups)
+}
+
+pub async fn prune_backups(server_id: Uuid, label: &str, keep: usize) -> anyhow::Result<()> {
+    let _lock = BACKUP_LOCK.lock().await;
+    prune_backups_locked(server_id, label, keep).await
+}
+
+async fn prune_backups_locked(server_id: Uuid, label: &str, keep: usize) -> anyhow::Result<()> {
+    let backups = list_backups(server_id)
+        .await?
+        .into_iter()
+        .filter(|b| b.metadata.label == label)
+        .collect::<Vec<_>>();
+
+    let excess = backups.len().saturating_sub(keep);
+    for backup in &backups[..excess] {
+        debug!("Removing old backup '{}'", backup.id);
+        tokio::fs::remove_dir_all(&backup.path)
+            .await
+            .with_context(|| format!("Failed to remove backup '{}'", backup.id))?;
+    }
+
+    if excess > 0 {
+        offsite::request_sync();
+        collect_garbage_locked().await?;
+    }
+
+    Ok(())
+}
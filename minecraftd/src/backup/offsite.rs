@@ -0,0 +1,340 @@
+//! Uploads local backups to S3-compatible object storage.
+//!
+//! Objects are laid out under the configured prefix:
+//! - `packs/<sha256>.pack`, chunks that were not uploaded before, concatenated. Chunks are at
+//!   most 1 MiB, so they are bundled to keep the number of requests down and to let large packs
+//!   be uploaded in parts.
+//! - `packs/<sha256>.json`, the hash, offset and length of each chunk in the pack, uploaded after
+//!   the pack
+//! - `<server id>/<backup id>/index.json`, the same index as in the local store
+//! - `<server id>/<backup id>/backup.json`, uploaded last so that its presence marks a complete
+//!   backup
+//!
+//! Backups that are deleted or pruned locally are deleted from the bucket as well, metadata
+//! first. A pack is deleted once none of its chunks are used by an uploaded backup anymore.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{sync::Notify, task::JoinSet};
+use uuid::Uuid;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    backup::{
+        self, Backup, INDEX_FILE_NAME, METADATA_FILE_NAME, s3::S3Client, snapshot,
+        store::CHUNK_SIZE,
+    },
+    config::{OffsiteBackupConfig, get_config},
+};
+
+const SYNC_RETRY_INTERVAL: Duration = Duration::from_hours(1);
+const STATE_FILE_NAME: &str = "offsite.json";
+
+static SYNC_REQUESTED: Notify = Notify::const_new();
+
+/// What has already been uploaded, so that unchanged chunks are not uploaded again.
+#[derive(Default, Serialize, Deserialize)]
+struct OffsiteState {
+    /// The bucket and prefix the state refers to. The state is discarded when the configuration
+    /// points somewhere else.
+    target: String,
+    chunks: HashSet<String>,
+    /// The chunks of each uploaded pack, by pack ID
+    packs: HashMap<String, Vec<String>>,
+    /// `<server id>/<backup id>`
+    backups: HashSet<String>,
+}
+
+pub fn init() {
+    let Some(config) = &get_config().backup.offsite else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = sync(config).await {
+                error!("Failed to upload backups to offsite storage: {err:?}");
+                send_alert("offsite_backup_failed", || Alert {
+                    severity: Severity::Error,
+                    title: "Offsite backup failed".to_string(),
+                    message: format!("Uploading backups to `{}` failed: {err}", config.endpoint),
+                })
+                .await;
+            }
+
+            // failed uploads are retried periodically even if no new backup is created
+            tokio::select! {
+                _ = SYNC_REQUESTED.notified() => {}
+                _ = tokio::time::sleep(SYNC_RETRY_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+/// Wakes up the upload task. Does nothing if offsite storage is not configured.
+pub fn request_sync() {
+    SYNC_REQUESTED.notify_one();
+}
+
+fn state_path() -> anyhow::Result<PathBuf> {
+    Ok(backup::backups_root_dir()?.join(STATE_FILE_NAME))
+}
+
+fn target(config: &OffsiteBackupConfig) -> String {
+    format!(
+        "{}/{}/{}",
+        config.endpoint,
+        config.bucket,
+        config.key_prefix()
+    )
+}
+
+async fn load_state(config: &OffsiteBackupConfig) -> anyhow::Result<OffsiteState> {
+    let target = target(config);
+
+    let state = match tokio::fs::read(state_path()?).await {
+        Ok(data) => serde_json::from_slice::<OffsiteState>(&data)
+            .context("Failed to parse offsite backup state")?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => OffsiteState::default(),
+        Err(err) => return Err(err).context("Failed to read offsite backup state"),
+    };
+
+    if state.target != target {
+        return Ok(OffsiteState {
+            target,
+            ..Default::default()
+        });
+    }
+
+    Ok(state)
+}
+
+async fn save_state(state: &OffsiteState) -> anyhow::Result<()> {
+    let path = state_path()?;
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec(state)?)
+        .await
+        .context("Failed to write offsite backup state")?;
+    tokio::fs::rename(&temp_path, &path)
+        .await
+        .context("Failed to write offsite backup state")?;
+    Ok(())
+}
+
+async fn sync(config: &'static OffsiteBackupConfig) -> anyhow::Result<()> {
+    let mut state = load_state(config).await?;
+    let mut local_backups = HashMap::new();
+
+    for server_id in backup::server_ids_with_backups().await? {
+        for backup in backup::list_backups(server_id).await? {
+            let key = format!("{server_id}/{}", backup.id);
+            local_backups.insert(key.clone(), backup.index_path());
+            if state.backups.contains(&key) {
+                continue;
+            }
+
+            let result = upload_backup(config, &mut state, server_id, &backup).await;
+            save_state(&state).await?;
+
+            match result {
+                Ok(()) => {
+                    info!("Uploaded backup '{}' to offsite storage", backup.id);
+                    state.backups.insert(key);
+                    save_state(&state).await?;
+                }
+                // deleted or pruned while it was being uploaded
+                Err(_) if !backup.path.exists() => {}
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to upload backup '{}'", backup.id));
+                }
+            }
+        }
+    }
+
+    delete_removed_backups(config, &mut state, &local_backups).await
+}
+
+/// Deletes the uploaded backups that no longer exist locally, and the packs only they used.
+async fn delete_removed_backups(
+    config: &OffsiteBackupConfig,
+    state: &mut OffsiteState,
+    local_backups: &HashMap<String, PathBuf>,
+) -> anyhow::Result<()> {
+    let client = S3Client::new(config);
+
+    let removed = state
+        .backups
+        .iter()
+        .filter(|key| !local_backups.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    for key in removed {
+        // the metadata goes first so that a backup never looks complete without its index
+        for file_name in [METADATA_FILE_NAME, INDEX_FILE_NAME] {
+            client
+                .delete_object(&format!("{}{key}/{file_name}", config.key_prefix()))
+                .await?;
+        }
+        state.backups.remove(&key);
+        save_state(state).await?;
+        info!("Deleted backup '{key}' from offsite storage");
+    }
+
+    let mut referenced = HashSet::new();
+    for key in &state.backups {
+        let Some(index_path) = local_backups.get(key).cloned() else {
+            continue;
+        };
+        match tokio::task::spawn_blocking(move || snapshot::load_index(&index_path))
+            .await
+            .unwrap()
+        {
+            Ok(index) => {
+                referenced.extend(index.into_iter().flat_map(|entry| entry.chunks().to_vec()))
+            }
+            Err(err) => {
+                error!("Failed to read the index of backup '{key}', keeping all packs: {err:?}");
+                return Ok(());
+            }
+        }
+    }
+
+    let unused = state
+        .packs
+        .iter()
+        .filter(|(_, hashes)| hashes.iter().all(|hash| !referenced.contains(hash)))
+        .map(|(pack_id, _)| pack_id.clone())
+        .collect::<Vec<_>>();
+    for pack_id in unused {
+        let pack_prefix = format!("{}packs/{pack_id}", config.key_prefix());
+        client.delete_object(&format!("{pack_prefix}.json")).await?;
+        client.delete_object(&format!("{pack_prefix}.pack")).await?;
+
+        // the chunks are uploaded again if a later backup contains them
+        if let Some(hashes) = state.packs.remove(&pack_id) {
+            for hash in hashes {
+                state.chunks.remove(&hash);
+            }
+        }
+        save_state(state).await?;
+        debug!("Deleted pack '{pack_id}' from offsite storage");
+    }
+
+    Ok(())
+}
+
+async fn upload_backup(
+    config: &'static OffsiteBackupConfig,
+    state: &mut OffsiteState,
+    server_id: Uuid,
+    backup: &Backup,
+) -> anyhow::Result<()> {
+    let client = S3Client::new(config);
+    let backup_prefix = format!("{}{server_id}/{}", config.key_prefix(), backup.id);
+
+    let index_path = backup.index_path();
+    let index = tokio::task::spawn_blocking(move || snapshot::load_index(&index_path))
+        .await
+        .unwrap()?;
+
+    let pending = index
+        .iter()
+        .flat_map(|entry| entry.chunks())
+        .filter(|hash| !state.chunks.contains(*hash))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let chunks_per_pack = (config.pack_size / CHUNK_SIZE).max(1) as usize;
+
+    let mut uploads = JoinSet::new();
+    for hashes in pending.chunks(chunks_per_pack) {
+        if uploads.len() >= config.upload_concurrency.max(1)
+            && let Some(result) = uploads.join_next().await
+        {
+            record_pack(state, result.unwrap()?);
+        }
+
+        let hashes = hashes.to_vec();
+        uploads.spawn(upload_pack(config, hashes));
+    }
+    while let Some(result) = uploads.join_next().await {
+        record_pack(state, result.unwrap()?);
+    }
+
+    // the metadata goes last so that a backup never looks complete without its index
+    for file_name in [INDEX_FILE_NAME, METADATA_FILE_NAME] {
+        let data = tokio::fs::read(backup.path.join(file_name))
+            .await
+            .with_context(|| format!("Failed to read '{file_name}'"))?;
+        client
+            .put_object(&format!("{backup_prefix}/{file_name}"), data)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn record_pack(state: &mut OffsiteState, (pack_id, hashes): (String, Vec<String>)) {
+    state.chunks.extend(hashes.iter().cloned());
+    state.packs.insert(pack_id, hashes);
+}
+
+#[derive(Serialize)]
+struct PackEntry {
+    hash: String,
+    offset: u64,
+    length: u64,
+}
+
+/// Uploads chunks as a pack and returns its ID and the hashes of the chunks.
+async fn upload_pack(
+    config: &'static OffsiteBackupConfig,
+    hashes: Vec<String>,
+) -> anyhow::Result<(String, Vec<String>)> {
+    let store = backup::chunk_store()?;
+    let (data, entries) = tokio::task::spawn_blocking(move || {
+        let mut data = Vec::new();
+        let mut entries = Vec::new();
+        for hash in hashes {
+            let chunk = store.read_chunk(&hash)?;
+            entries.push(PackEntry {
+                hash,
+                offset: data.len() as u64,
+                length: chunk.len() as u64,
+            });
+            data.extend_from_slice(&chunk);
+        }
+        anyhow::Ok((data, entries))
+    })
+    .await
+    .unwrap()?;
+
+    let pack_id = hex::encode(Sha256::digest(&data));
+    let pack_prefix = format!("{}packs/{pack_id}", config.key_prefix());
+
+    let client = S3Client::new(config);
+    client
+        .put_object(&format!("{pack_prefix}.pack"), data)
+        .await?;
+    client
+        .put_object(
+            &format!("{pack_prefix}.json"),
+            serde_json::to_vec(&entries)?,
+        )
+        .await?;
+
+    Ok((
+        pack_id,
+        entries.into_iter().map(|entry| entry.hash).collect(),
+    ))
+}
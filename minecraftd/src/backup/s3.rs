@@ -0,0 +1,319 @@
+//! Minimal S3 client covering what offsite backups need: single and multipart uploads and
+//! deletions signed with AWS Signature Version 4. Works with AWS S3 and compatible servers such as MinIO.
+
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use crate::{config::OffsiteBackupConfig, util::lazy_init_http_client::LazyInitHttpClient};
+
+static CLIENT: LazyInitHttpClient = LazyInitHttpClient::new();
+
+/// S3 rejects parts smaller than this, except for the last one.
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+pub struct S3Client<'a> {
+    config: &'a OffsiteBackupConfig,
+}
+
+impl<'a> S3Client<'a> {
+    pub fn new(config: &'a OffsiteBackupConfig) -> Self {
+        Self { config }
+    }
+
+    /// Uploads an object, switching to a multipart upload above the configured threshold.
+    pub async fn put_object(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        if data.len() as u64 <= self.config.multipart_threshold {
+            self.request(Method::PUT, key, &[], data)
+                .await
+                .with_context(|| format!("Failed to upload '{key}'"))?;
+            return Ok(());
+        }
+
+        self.put_object_multipart(key, &data)
+            .await
+            .with_context(|| format!("Failed to upload '{key}' in parts"))
+    }
+
+    /// Deletes an object. Deleting an object that does not exist succeeds.
+    pub async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+        self.request(Method::DELETE, key, &[], Vec::new())
+            .await
+            .with_context(|| format!("Failed to delete '{key}'"))?;
+        Ok(())
+    }
+
+    async fn put_object_multipart(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let response = self
+            .request(Method::POST, key, &[("uploads", "")], Vec::new())
+            .await?;
+        let upload_id = xml_element(&response.text, "UploadId")
+            .context("CreateMultipartUpload response has no UploadId")?
+            .to_string();
+
+        let result: anyhow::Result<()> = async {
+            let part_size = (self.config.multipart_part_size as usize).max(MIN_PART_SIZE);
+
+            let mut etags = Vec::new();
+            for (i, part) in data.chunks(part_size).enumerate() {
+                let part_number = (i + 1).to_string();
+                let response = self
+                    .request(
+                        Method::PUT,
+                        key,
+                        &[("partNumber", &part_number), ("uploadId", &upload_id)],
+                        part.to_vec(),
+                    )
+                    .await?;
+                etags.push(response.etag.context("UploadPart response has no ETag")?);
+            }
+
+            let mut body = String::from("<CompleteMultipartUpload>");
+            for (i, etag) in etags.iter().enumerate() {
+                body.push_str(&format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    i + 1,
+                    etag
+                ));
+            }
+            body.push_str("</CompleteMultipartUpload>");
+
+            let response = self
+                .request(
+                    Method::POST,
+                    key,
+                    &[("uploadId", &upload_id)],
+                    body.into_bytes(),
+                )
+                .await?;
+
+            // CompleteMultipartUpload can fail after a 200 status has been sent
+            if let Some(message) = xml_element(&response.text, "Message")
+                && response.text.contains("<Error>")
+            {
+                bail!("CompleteMultipartUpload failed: {message}");
+            }
+
+            Ok(())
+        }
+        .await;
+
+        if result.is_err()
+            && let Err(err) = self
+                .request(Method::DELETE, key, &[("uploadId", &upload_id)], Vec::new())
+                .await
+        {
+            warn!("Failed to abort multipart upload of '{key}': {err:?}");
+        }
+
+        result
+    }
+
+    /// Sends a signed request, retrying with exponential backoff on network errors, throttling
+    /// and server errors.
+    async fn request(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> anyhow::Result<S3Response> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .request_once(method.clone(), key, query, body.clone())
+                .await;
+
+            let err = match result {
+                Ok(response) => return Ok(response),
+                Err(err) => err,
+            };
+
+            let retryable = match &err {
+                RequestError::Status(status, _) => {
+                    status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+                }
+                RequestError::Other(_) => true,
+            };
+
+            if !retryable || attempt >= self.config.max_retries {
+                return Err(err.into_anyhow(&method, key));
+            }
+
+            let delay = self
+                .config
+                .retry_initial_delay
+                .saturating_mul(2u32.saturating_pow(attempt))
+                .min(MAX_RETRY_DELAY);
+            debug!(
+                "S3 {method} '{key}' failed (attempt {}), retrying in {delay:?}",
+                attempt + 1
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn request_once(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<S3Response, RequestError> {
+        let url = self.object_url(key, query)?;
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+            .collect::<Vec<_>>();
+        canonical_query.sort();
+
+        let canonical_request = format!(
+            "{method}\n{}\n{}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}",
+            url.path(),
+            canonical_query.join("&"),
+        );
+
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [
+            date.as_bytes(),
+            self.config.region.as_bytes(),
+            b"s3",
+            b"aws4_request",
+        ]
+        .iter()
+        .fold(
+            format!("AWS4{}", self.config.secret_access_key).into_bytes(),
+            |key, data| hmac_sha256(&key, data),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}",
+            self.config.access_key_id
+        );
+
+        let response = CLIENT
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| RequestError::Other(err.into()))?;
+
+        let status = response.status();
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let text = response
+            .text()
+            .await
+            .map_err(|err| RequestError::Other(err.into()))?;
+
+        if !status.is_success() {
+            return Err(RequestError::Status(status, text));
+        }
+
+        Ok(S3Response { etag, text })
+    }
+
+    fn object_url(&self, key: &str, query: &[(&str, &str)]) -> Result<Url, RequestError> {
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let encoded_key = uri_encode(key, false);
+
+        let url = if self.config.path_style {
+            format!("{endpoint}/{}/{encoded_key}", self.config.bucket)
+        } else {
+            let (scheme, host) = endpoint
+                .split_once("://")
+                .ok_or_else(|| RequestError::Other(anyhow::anyhow!("Invalid endpoint URL")))?;
+            format!("{scheme}://{}.{host}/{encoded_key}", self.config.bucket)
+        };
+
+        let mut url = Url::parse(&url).map_err(|err| RequestError::Other(err.into()))?;
+        if !query.is_empty() {
+            url.set_query(Some(
+                &query
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            ));
+        }
+
+        Ok(url)
+    }
+}
+
+struct S3Response {
+    etag: Option<String>,
+    text: String,
+}
+
+enum RequestError {
+    Status(StatusCode, String),
+    Other(anyhow::Error),
+}
+
+impl RequestError {
+    fn into_anyhow(self, method: &Method, key: &str) -> anyhow::Error {
+        match self {
+            RequestError::Status(status, body) => {
+                let message = xml_element(&body, "Message").unwrap_or(&body);
+                anyhow::anyhow!("S3 {method} '{key}' failed with {status}: {message}")
+            }
+            RequestError::Other(err) => err.context(format!("S3 {method} '{key}' failed")),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything except unreserved characters, as required by SigV4.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{name}>"))?;
+    Some(&xml[start..end])
+}
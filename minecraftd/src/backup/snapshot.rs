@@ -0,0 +1,341 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write,
+    path::{Component, Path},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::store::{self, ChunkStore};
+
+/// An entry of a backup index. Paths are relative to the server directory and use `/` as the
+/// separator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexEntry {
+    Directory {
+        path: String,
+    },
+    File {
+        path: String,
+        size: u64,
+        /// Modification time in nanoseconds since the Unix epoch
+        modified: i64,
+        chunks: Vec<String>,
+    },
+    Symlink {
+        path: String,
+        target: String,
+    },
+}
+
+impl IndexEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            IndexEntry::Directory { path }
+            | IndexEntry::File { path, .. }
+            | IndexEntry::Symlink { path, .. } => path,
+        }
+    }
+
+    pub fn chunks(&self) -> &[String] {
+        match self {
+            IndexEntry::File { chunks, .. } => chunks,
+            _ => &[],
+        }
+    }
+}
+
+pub fn load_index(path: &Path) -> anyhow::Result<Vec<IndexEntry>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read backup index '{}'", path.display()))?;
+    let entries: Vec<IndexEntry> = serde_json::from_slice(&data)
+        .with_context(|| format!("Failed to parse backup index '{}'", path.display()))?;
+
+    if let Some(hash) = entries
+        .iter()
+        .flat_map(|entry| entry.chunks())
+        .find(|hash| !store::is_valid_hash(hash))
+    {
+        bail!(
+            "Backup index '{}' contains an invalid chunk hash '{hash}'",
+            path.display()
+        );
+    }
+
+    if let Some(entry) = entries
+        .iter()
+        .find(|entry| !is_valid_relative_path(entry.path()))
+    {
+        bail!(
+            "Backup index '{}' contains an invalid path '{}'",
+            path.display(),
+            entry.path()
+        );
+    }
+
+    // writing below a restored symlink would follow it out of the server directory
+    let symlinks = entries
+        .iter()
+        .filter(|entry| matches!(entry, IndexEntry::Symlink { .. }))
+        .map(|entry| Path::new(entry.path()))
+        .collect::<HashSet<_>>();
+    if let Some(entry) = entries.iter().find(|entry| {
+        Path::new(entry.path())
+            .ancestors()
+            .skip(1)
+            .any(|ancestor| symlinks.contains(ancestor))
+    }) {
+        bail!(
+            "Backup index '{}' contains a path '{}' below a symlink",
+            path.display(),
+            entry.path()
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Whether `path` is a non-empty relative path that cannot leave the directory it is joined onto.
+pub fn is_valid_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn is_in_worlds(path: &str, worlds: &[String]) -> bool {
+    worlds.iter().any(|world| {
+        path.strip_prefix(world.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+pub fn save_index(path: &Path, entries: &[IndexEntry]) -> anyhow::Result<()> {
+    let mut file = File::create(path)
+        .with_context(|| format!("Failed to create backup index '{}'", path.display()))?;
+    serde_json::to_writer(&mut file, entries)?;
+    file.flush()?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Stores the worlds into the chunk store and returns the index describing them.
+///
+/// Files whose size and modification time match the `previous` index are not read again, which
+/// keeps backups of large, mostly unchanged worlds cheap.
+pub fn take(
+    store: &ChunkStore,
+    server_dir: &Path,
+    worlds: &[String],
+    previous: &[IndexEntry],
+) -> anyhow::Result<Vec<IndexEntry>> {
+    let previous = previous
+        .iter()
+        .map(|entry| (entry.path(), entry))
+        .collect::<HashMap<_, _>>();
+
+    let mut entries = Vec::new();
+    for world in worlds {
+        walk(store, server_dir, world, &previous, &mut entries)?;
+    }
+
+    Ok(entries)
+}
+
+fn walk(
+    store: &ChunkStore,
+    server_dir: &Path,
+    relative_path: &str,
+    previous: &HashMap<&str, &IndexEntry>,
+    entries: &mut Vec<IndexEntry>,
+) -> anyhow::Result<()> {
+    let path = server_dir.join(relative_path);
+
+    entries.push(IndexEntry::Directory {
+        path: relative_path.to_string(),
+    });
+
+    let mut children = std::fs::read_dir(&path)
+        .with_context(|| format!("Failed to read directory '{}'", path.display()))?
+        .collect::<Result<Vec<_>, _>>()?;
+    children.sort_by_key(|entry| entry.file_name());
+
+    for child in children {
+        let Some(name) = child.file_name().to_str().map(str::to_string) else {
+            bail!("File name '{}' is not valid UTF-8", child.path().display());
+        };
+        let child_relative_path = format!("{relative_path}/{name}");
+        let file_type = child.file_type()?;
+
+        if file_type.is_dir() {
+            walk(store, server_dir, &child_relative_path, previous, entries)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(child.path())?;
+            entries.push(IndexEntry::Symlink {
+                path: child_relative_path,
+                target: target
+                    .to_str()
+                    .context("Symlink target is not valid UTF-8")?
+                    .to_string(),
+            });
+        } else {
+            let metadata = child.metadata()?;
+            let size = metadata.len();
+            let modified = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as i64)
+                .unwrap_or(0);
+
+            let unchanged = match previous.get(child_relative_path.as_str()) {
+                Some(IndexEntry::File {
+                    size: prev_size,
+                    modified: prev_modified,
+                    chunks,
+                    ..
+                }) if *prev_size == size
+                    && *prev_modified == modified
+                    && chunks.iter().all(|hash| store.contains(hash)) =>
+                {
+                    Some(chunks.clone())
+                }
+                _ => None,
+            };
+
+            let chunks = match unchanged {
+                Some(chunks) => chunks,
+                None => store.put_file(&child.path())?,
+            };
+
+            entries.push(IndexEntry::File {
+                path: child_relative_path,
+                size,
+                modified,
+                chunks,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the files described by the index into the server directory.
+/// Every entry must be inside one of `worlds`, the world directories the backup was taken of.
+pub fn restore(
+    store: &ChunkStore,
+    server_dir: &Path,
+    worlds: &[String],
+    entries: &[IndexEntry],
+) -> anyhow::Result<()> {
+    if let Some(entry) = entries
+        .iter()
+        .find(|entry| !is_in_worlds(entry.path(), worlds))
+    {
+        bail!(
+            "Backup index contains '{}', which is outside of the backed-up worlds",
+            entry.path()
+        );
+    }
+
+    for entry in entries {
+        let path = server_dir.join(entry.path());
+
+        match entry {
+            IndexEntry::Directory { .. } => {
+                std::fs::create_dir_all(&path)
+                    .with_context(|| format!("Failed to create directory '{}'", path.display()))?;
+            }
+            IndexEntry::File {
+                chunks, modified, ..
+            } => {
+                let mut file = File::create(&path)
+                    .with_context(|| format!("Failed to create '{}'", path.display()))?;
+                for hash in chunks {
+                    file.write_all(&store.read_chunk(hash)?)?;
+                }
+                file.set_modified(UNIX_EPOCH + std::time::Duration::from_nanos(*modified as u64))?;
+            }
+            IndexEntry::Symlink { target, .. } => {
+                std::os::unix::fs::symlink(target, &path)
+                    .with_context(|| format!("Failed to create symlink '{}'", path.display()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_index(dir: &Path, entries: &[IndexEntry]) -> std::path::PathBuf {
+        let path = dir.join("index.json");
+        save_index(&path, entries).unwrap();
+        path
+    }
+
+    fn directory(path: &str) -> IndexEntry {
+        IndexEntry::Directory {
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn load_index_rejects_escaping_paths() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = write_index(dir.path(), &[directory("world"), directory("world/region")]);
+        assert!(load_index(&path).is_ok());
+
+        for invalid in ["", "/etc", "world/../..", "./world"] {
+            let path = write_index(dir.path(), &[directory(invalid)]);
+            assert!(load_index(&path).is_err(), "{invalid:?} was accepted");
+        }
+
+        let path = write_index(
+            dir.path(),
+            &[
+                directory("world"),
+                IndexEntry::Symlink {
+                    path: "world/link".to_string(),
+                    target: "/etc".to_string(),
+                },
+                directory("world/link/cron.d"),
+            ],
+        );
+        assert!(load_index(&path).is_err());
+    }
+
+    #[test]
+    fn restore_rejects_entries_outside_worlds() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ChunkStore::new(dir.path().join("chunks"));
+        let server_dir = dir.path().join("server");
+        let worlds = ["world".to_string()];
+
+        assert!(
+            restore(
+                &store,
+                &server_dir,
+                &worlds,
+                &[directory("world"), directory("world_nether")]
+            )
+            .is_err()
+        );
+        assert!(!server_dir.join("world").exists());
+
+        restore(
+            &store,
+            &server_dir,
+            &worlds,
+            &[directory("world"), directory("world/region")],
+        )
+        .unwrap();
+        assert!(server_dir.join("world/region").is_dir());
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use sha2::{Digest, Sha256};
+
+/// Region files are rewritten in place in 4 KiB sectors, so fixed-size chunks
+/// deduplicate well without content-defined chunking.
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+/// Content-addressed chunk storage shared by all backups of all servers.
+/// Chunks are stored at `<root>/<first 2 hex digits>/<sha256>`.
+pub struct ChunkStore {
+    root: PathBuf,
+}
+
+pub enum ChunkState {
+    Ok,
+    Missing,
+    Corrupt,
+}
+
+/// Whether `hash` is a SHA-256 hash as produced by the store: 64 lowercase hex digits.
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+impl ChunkStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// `hash` must be valid, see [`is_valid_hash`].
+    fn chunk_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.chunk_path(hash).is_file()
+    }
+
+    /// Splits the file into chunks, stores the ones not yet in the store and returns the hashes
+    /// of all chunks in order.
+    pub fn put_file(&self, path: &Path) -> anyhow::Result<Vec<String>> {
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open '{}'", path.display()))?;
+
+        let mut hashes = Vec::new();
+        let mut buffer = Vec::with_capacity(CHUNK_SIZE as usize);
+        loop {
+            buffer.clear();
+            (&mut file)
+                .take(CHUNK_SIZE)
+                .read_to_end(&mut buffer)
+                .with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+            if buffer.is_empty() {
+                break;
+            }
+
+            hashes.push(self.put_chunk(&buffer)?);
+        }
+
+        Ok(hashes)
+    }
+
+    fn put_chunk(&self, data: &[u8]) -> anyhow::Result<String> {
+        let hash = hex::encode(Sha256::digest(data));
+        let path = self.chunk_path(&hash);
+
+        if path.exists() {
+            return Ok(hash);
+        }
+
+        let dir = path.parent().unwrap();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+
+        // written to a temporary file first so that a crash never leaves a truncated chunk
+        // under its final name
+        let temp_path = path.with_extension(TEMP_FILE_EXTENSION);
+        let mut file = File::create(&temp_path)
+            .with_context(|| format!("Failed to create '{}'", temp_path.display()))?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to store chunk {hash}"))?;
+
+        Ok(hash)
+    }
+
+    /// Reads a chunk, failing if its content does not match its hash.
+    pub fn read_chunk(&self, hash: &str) -> anyhow::Result<Vec<u8>> {
+        let data = std::fs::read(self.chunk_path(hash))
+            .with_context(|| format!("Failed to read chunk {hash}"))?;
+
+        if hex::encode(Sha256::digest(&data)) != hash {
+            bail!("Chunk {hash} is corrupt");
+        }
+
+        Ok(data)
+    }
+
+    pub fn check_chunk(&self, hash: &str) -> ChunkState {
+        match std::fs::read(self.chunk_path(hash)) {
+            Ok(data) if hex::encode(Sha256::digest(&data)) == hash => ChunkState::Ok,
+            Ok(_) => ChunkState::Corrupt,
+            Err(_) => ChunkState::Missing,
+        }
+    }
+
+    /// Removes all chunks not in `referenced`, along with leftover temporary files.
+    /// Returns the number of removed chunks and the number of bytes freed.
+    ///
+    /// Must not run concurrently with a backup, as the chunks of a backup in progress are not
+    /// referenced by any index yet.
+    pub fn remove_unreferenced(
+        &self,
+        referenced: &HashSet<String>,
+    ) -> anyhow::Result<(usize, u64)> {
+        if !self.root.exists() {
+            return Ok((0, 0));
+        }
+
+        let mut removed_chunks = 0;
+        let mut freed_bytes = 0;
+
+        for dir in std::fs::read_dir(&self.root).context("Failed to read chunk directory")? {
+            let dir = dir?;
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+
+            for entry in std::fs::read_dir(dir.path())? {
+                let entry = entry?;
+                let path = entry.path();
+                let is_temp = path
+                    .extension()
+                    .is_some_and(|ext| ext == TEMP_FILE_EXTENSION);
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                if !is_temp && referenced.contains(&name) {
+                    continue;
+                }
+
+                let size = entry.metadata()?.len();
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+
+                if !is_temp {
+                    removed_chunks += 1;
+                }
+                freed_bytes += size;
+            }
+        }
+
+        Ok((removed_chunks, freed_bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_gc() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ChunkStore::new(dir.path().join("chunks"));
+
+        // two identical full chunks followed by a partial one
+        let mut content = vec![1u8; CHUNK_SIZE as usize * 2];
+        content.extend_from_slice(b"tail");
+        let file_path = dir.path().join("region.mca");
+        std::fs::write(&file_path, &content).unwrap();
+
+        let hashes = store.put_file(&file_path).unwrap();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0], hashes[1]);
+        assert!(hashes.iter().all(|hash| is_valid_hash(hash)));
+
+        let restored = hashes
+            .iter()
+            .flat_map(|hash| store.read_chunk(hash).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(restored, content);
+
+        // a second file shares the first chunk
+        let other_path = dir.path().join("other.mca");
+        std::fs::write(&other_path, vec![2u8; 16]).unwrap();
+        let other_hashes = store.put_file(&other_path).unwrap();
+
+        std::fs::write(
+            store.chunk_path(&hashes[0]).with_extension("tmp"),
+            b"partial",
+        )
+        .unwrap();
+
+        let referenced = hashes.iter().cloned().collect::<HashSet<_>>();
+        let (removed_chunks, freed_bytes) = store.remove_unreferenced(&referenced).unwrap();
+        assert_eq!(removed_chunks, 1);
+        assert_eq!(freed_bytes, 16 + b"partial".len() as u64);
+
+        assert!(!store.contains(&other_hashes[0]));
+        assert!(hashes.iter().all(|hash| store.contains(hash)));
+        assert!(matches!(
+            store.check_chunk(&other_hashes[0]),
+            ChunkState::Missing
+        ));
+    }
+
+    #[test]
+    fn corrupt_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ChunkStore::new(dir.path().to_path_buf());
+
+        let hash = store.put_chunk(b"data").unwrap();
+        assert!(matches!(store.check_chunk(&hash), ChunkState::Ok));
+
+        std::fs::write(store.chunk_path(&hash), b"other data").unwrap();
+        assert!(matches!(store.check_chunk(&hash), ChunkState::Corrupt));
+        assert!(store.read_chunk(&hash).is_err());
+    }
+
+    #[test]
+    fn valid_hashes() {
+        assert!(is_valid_hash(&"0123456789abcdef".repeat(4)));
+        assert!(!is_valid_hash(&"0123456789ABCDEF".repeat(4)));
+        assert!(!is_valid_hash("abc"));
+        assert!(!is_valid_hash(&format!("../{}", "a".repeat(61))));
+    }
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashSet, path::PathBuf, sync::OnceLock, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::OnceLock,
+    time::Duration,
+};
 
 use anyhow::Context;
 use minecraft_protocol::text_component::{Color, Object, TextComponent};
@@ -38,6 +43,17 @@ pub struct Config {
     pub metrics: MetricsConfig,
     #[serde(default)]
     pub messages: MessagesConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub update_rollback: UpdateRollbackConfig,
+    #[serde(default)]
+    pub restart_countdown: RestartCountdownConfig,
+    #[serde(default)]
+    pub console_log: ConsoleLogConfig,
+    /// Player lists shared by the servers whose manifest refers to them, by group name.
+    #[serde(default)]
+    pub player_lists: HashMap<String, PlayerListGroupConfig>,
 }
 
 impl Config {
@@ -129,12 +145,31 @@ pub struct RunnerConfig {
         deserialize_with = "duration_str::deserialize_duration"
     )]
     pub stop_timeout: Duration,
+    /// How long a server may take to become ready before it is considered stuck and killed.
+    #[serde(
+        default = "default_start_timeout",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub start_timeout: Duration,
+    /// How long to wait for a server to respond to an RCON command.
+    #[serde(
+        default = "default_rcon_timeout",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub rcon_timeout: Duration,
+    /// Leave the servers running when the daemon shuts down, so that the next daemon reattaches
+    /// to them.
+    #[serde(default)]
+    pub detach_on_shutdown: bool,
 }
 
 impl Default for RunnerConfig {
     fn default() -> Self {
         Self {
             stop_timeout: default_stop_timeout_secs(),
+            start_timeout: default_start_timeout(),
+            rcon_timeout: default_rcon_timeout(),
+            detach_on_shutdown: false,
         }
     }
 }
@@ -143,6 +178,85 @@ fn default_stop_timeout_secs() -> Duration {
     Duration::from_secs(180)
 }
 
+fn default_start_timeout() -> Duration {
+    Duration::from_mins(10)
+}
+
+fn default_rcon_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsoleLogConfig {
+    /// Write the console output of the servers to log files.
+    #[serde(default = "default_console_log_enabled")]
+    pub enabled: bool,
+    /// The log file is rotated and compressed once it grows larger than this (in bytes).
+    #[serde(default = "default_console_log_max_file_size")]
+    pub max_file_size: u64,
+    /// Number of rotated log files kept per server.
+    #[serde(default = "default_console_log_max_files")]
+    pub max_files: usize,
+    /// Rotated log files older than this are deleted.
+    #[serde(
+        default = "default_console_log_max_age",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub max_age: Duration,
+    /// Size of the most recent console output kept in memory per server (in bytes).
+    #[serde(default = "default_console_log_scrollback_size")]
+    pub scrollback_size: usize,
+}
+
+impl Default for ConsoleLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_console_log_enabled(),
+            max_file_size: default_console_log_max_file_size(),
+            max_files: default_console_log_max_files(),
+            max_age: default_console_log_max_age(),
+            scrollback_size: default_console_log_scrollback_size(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerListGroupConfig {
+    /// Share `whitelist.json`.
+    #[serde(default = "default_player_list_shared")]
+    pub whitelist: bool,
+    /// Share `ops.json`.
+    #[serde(default = "default_player_list_shared")]
+    pub ops: bool,
+    /// Share `banned-players.json`.
+    #[serde(default = "default_player_list_shared")]
+    pub banned_players: bool,
+}
+
+fn default_player_list_shared() -> bool {
+    true
+}
+
+fn default_console_log_enabled() -> bool {
+    true
+}
+
+fn default_console_log_max_file_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_console_log_max_files() -> usize {
+    20
+}
+
+fn default_console_log_max_age() -> Duration {
+    Duration::from_hours(30 * 24)
+}
+
+fn default_console_log_scrollback_size() -> usize {
+    256 * 1024
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AutoUpdateConfig {
     #[serde(
@@ -186,6 +300,107 @@ fn default_notify_players_before_restart_interval() -> Duration {
     Duration::from_mins(1)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateRollbackConfig {
+    #[serde(default = "default_update_rollback_enabled")]
+    pub enabled: bool,
+    /// An updated server that does not become ready within this time is rolled back.
+    #[serde(
+        default = "default_update_rollback_ready_timeout",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub ready_timeout: Duration,
+    /// An updated server that crashes within this time after becoming ready is rolled back.
+    #[serde(
+        default = "default_update_rollback_stable_period",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub stable_period: Duration,
+    /// Number of world backups taken before updates to keep per server. Older ones are deleted.
+    #[serde(default = "default_update_rollback_keep_backups")]
+    pub keep_backups: usize,
+}
+
+impl Default for UpdateRollbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_update_rollback_enabled(),
+            ready_timeout: default_update_rollback_ready_timeout(),
+            stable_period: default_update_rollback_stable_period(),
+            keep_backups: default_update_rollback_keep_backups(),
+        }
+    }
+}
+
+fn default_update_rollback_enabled() -> bool {
+    true
+}
+
+fn default_update_rollback_ready_timeout() -> Duration {
+    Duration::from_mins(10)
+}
+
+fn default_update_rollback_stable_period() -> Duration {
+    Duration::from_mins(5)
+}
+
+fn default_update_rollback_keep_backups() -> usize {
+    3
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestartCountdownConfig {
+    /// Remaining times at which players are warned. The start of the countdown is always announced.
+    #[serde(
+        default = "default_restart_countdown_warnings",
+        deserialize_with = "deserialize_durations"
+    )]
+    pub warnings: Vec<Duration>,
+    #[serde(default = "default_restart_countdown_display")]
+    pub display: Vec<CountdownDisplay>,
+}
+
+impl Default for RestartCountdownConfig {
+    fn default() -> Self {
+        Self {
+            warnings: default_restart_countdown_warnings(),
+            display: default_restart_countdown_display(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountdownDisplay {
+    Chat,
+    Title,
+    Actionbar,
+}
+
+fn default_restart_countdown_warnings() -> Vec<Duration> {
+    vec![
+        Duration::from_mins(10),
+        Duration::from_mins(5),
+        Duration::from_mins(1),
+        Duration::from_secs(30),
+        Duration::from_secs(10),
+    ]
+}
+
+fn default_restart_countdown_display() -> Vec<CountdownDisplay> {
+    vec![CountdownDisplay::Chat, CountdownDisplay::Actionbar]
+}
+
+fn deserialize_durations<'de, D>(deserializer: D) -> Result<Vec<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| duration_str::parse(s).map_err(serde::de::Error::custom))
+        .collect()
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MetricsConfig {
     #[serde(
@@ -227,6 +442,100 @@ fn default_metrics_storage_retention() -> Duration {
     Duration::from_secs(3600 * 24 * 30)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BackupConfig {
+    /// Number of backups taken before restores to keep per server. Older ones are deleted.
+    #[serde(default = "default_backup_keep_pre_restore")]
+    pub keep_pre_restore: usize,
+    /// Backups are uploaded to S3-compatible object storage if set.
+    #[serde(default)]
+    pub offsite: Option<OffsiteBackupConfig>,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            keep_pre_restore: default_backup_keep_pre_restore(),
+            offsite: None,
+        }
+    }
+}
+
+fn default_backup_keep_pre_restore() -> usize {
+    3
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OffsiteBackupConfig {
+    pub endpoint: String,
+    #[serde(default = "default_offsite_region")]
+    pub region: String,
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Use `<endpoint>/<bucket>/<key>` URLs instead of `<bucket>.<endpoint>/<key>`.
+    #[serde(default)]
+    pub path_style: bool,
+    /// Objects larger than this (in bytes) are uploaded in parts.
+    #[serde(default = "default_offsite_multipart_threshold")]
+    pub multipart_threshold: u64,
+    #[serde(default = "default_offsite_multipart_part_size")]
+    pub multipart_part_size: u64,
+    /// Chunks are uploaded bundled into packs of about this size (in bytes).
+    #[serde(default = "default_offsite_pack_size")]
+    pub pack_size: u64,
+    #[serde(default = "default_offsite_max_retries")]
+    pub max_retries: u32,
+    #[serde(
+        default = "default_offsite_retry_initial_delay",
+        deserialize_with = "duration_str::deserialize_duration"
+    )]
+    pub retry_initial_delay: Duration,
+    #[serde(default = "default_offsite_upload_concurrency")]
+    pub upload_concurrency: usize,
+}
+
+impl OffsiteBackupConfig {
+    /// The prefix of every object key, ending with `/` unless it is empty.
+    pub fn key_prefix(&self) -> String {
+        if self.prefix.is_empty() || self.prefix.ends_with('/') {
+            self.prefix.clone()
+        } else {
+            format!("{}/", self.prefix)
+        }
+    }
+}
+
+fn default_offsite_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_offsite_multipart_threshold() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_offsite_multipart_part_size() -> u64 {
+    8 * 1024 * 1024
+}
+
+fn default_offsite_pack_size() -> u64 {
+    32 * 1024 * 1024
+}
+
+fn default_offsite_max_retries() -> u32 {
+    5
+}
+
+fn default_offsite_retry_initial_delay() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_offsite_upload_concurrency() -> usize {
+    8
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MessagesConfig {
     #[serde(default = "default_server_restarting_for_update_message")]
@@ -241,17 +550,25 @@ pub struct MessagesConfig {
     pub server_restarting: TextComponent,
     #[serde(default = "default_server_restarting_kick_message")]
     pub server_restarting_kick: String,
+    /// Shown in the server list while a server with `wake_on_connect` is stopped.
+    #[serde(default = "default_server_sleeping_message")]
+    pub server_sleeping: TextComponent,
+    /// Shown during restart countdowns. `{remaining}` is replaced with the remaining time.
+    #[serde(default = "default_server_restart_countdown_message")]
+    pub server_restart_countdown: TextComponent,
 }
 
 impl Default for MessagesConfig {
     fn default() -> Self {
         Self {
-            server_restarting_for_update: default_server_starting_message(),
+            server_restarting_for_update: default_server_restarting_for_update_message(),
             server_not_found: default_server_not_found_message(),
             server_starting: default_server_starting_message(),
             server_stopping: default_server_stopping_message(),
             server_restarting: default_server_restarting_message(),
             server_restarting_kick: default_server_restarting_kick_message(),
+            server_sleeping: default_server_sleeping_message(),
+            server_restart_countdown: default_server_restart_countdown_message(),
         }
     }
 }
@@ -265,7 +582,7 @@ fn default_server_restarting_for_update_message() -> TextComponent {
                 color: Some(Color::Red),
                 ..Default::default()
             }),
-            TextComponent::String("\nThe server will restart in one minute to apply the updates.\nPlease log out to avoid interruptions.".to_string()),
+            TextComponent::String("\nThe server will restart soon to apply the updates.\nPlease log out to avoid interruptions.".to_string()),
         ]),
         ..Default::default()
     })
@@ -290,3 +607,15 @@ fn default_server_restarting_message() -> TextComponent {
 fn default_server_restarting_kick_message() -> String {
     "The server is restarting, please try connecting again after a while.".to_string()
 }
+
+fn default_server_sleeping_message() -> TextComponent {
+    TextComponent::String("The server is sleeping. Join to wake it up.".to_string())
+}
+
+fn default_server_restart_countdown_message() -> TextComponent {
+    TextComponent::Object(Object {
+        text: Some("The server will restart in {remaining}.".to_string()),
+        color: Some(Color::Yellow),
+        ..Default::default()
+    })
+}
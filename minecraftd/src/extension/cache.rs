@@ -34,6 +34,30 @@ pub async fn get_or_download(
     Ok(path)
 }
 
+/// Puts a jar that was obtained elsewhere into the cache, unless the version is already cached.
+pub async fn insert(
+    provider: &dyn ExtensionProvider,
+    type_: ExtensionType,
+    extension_id: &str,
+    extension_version_id: &str,
+    jar: &[u8],
+) -> anyhow::Result<PathBuf> {
+    let path = extension_cache_path(provider.name(), type_, extension_id, extension_version_id)?;
+
+    if path.exists() {
+        return Ok(path);
+    }
+
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create extension directory")?;
+    tokio::fs::write(&path, jar)
+        .await
+        .context("Failed to write extension jar to disk")?;
+
+    Ok(path)
+}
+
 pub struct ExtensionSymlinkInfo {
     pub provider: OsString,
     pub ty: ExtensionType,
@@ -1,2 +1,3 @@
 pub mod cache;
 pub mod providers;
+pub mod unmanaged;
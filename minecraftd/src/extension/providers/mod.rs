@@ -42,6 +42,13 @@ pub trait ExtensionProvider: Send + Sync {
         &'a self,
         url: &'a str,
     ) -> BoxedFuture<'a, anyhow::Result<ExtensionInfo>>;
+    /// Finds the extension version a jar was downloaded from. Returns `None` if the provider does
+    /// not know the jar.
+    fn get_extension_version_by_jar<'a>(
+        &'a self,
+        type_: ExtensionType,
+        jar: &'a [u8],
+    ) -> BoxedFuture<'a, anyhow::Result<Option<ExtensionJarMatch>>>;
 
     fn is_newer_version_available<'a>(
         &'a self,
@@ -86,6 +93,11 @@ pub struct ExtensionDependency {
     pub extension_version_id: Option<String>,
 }
 
+pub struct ExtensionJarMatch {
+    pub extension_id: String,
+    pub extension_version_id: String,
+}
+
 pub const EXTENSION_PROVIDERS: &[&dyn ExtensionProvider] = &[&modrinth::Modrinth];
 
 pub fn get_extension_provider(name: &str) -> Option<&'static dyn ExtensionProvider> {
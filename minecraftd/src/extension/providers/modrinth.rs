@@ -6,11 +6,13 @@ use modrinth_api::{
     apis::configuration::Configuration,
     models::{version::VersionType, version_dependency::DependencyType},
 };
-use reqwest::Url;
+use reqwest::{StatusCode, Url};
+use sha2::{Digest, Sha512};
 
 use crate::{
     extension::providers::{
-        ExtensionDependency, ExtensionInfo, ExtensionProvider, ExtensionVersionInfo,
+        ExtensionDependency, ExtensionInfo, ExtensionJarMatch, ExtensionProvider,
+        ExtensionVersionInfo,
     },
     util::BoxedFuture,
 };
@@ -233,4 +235,33 @@ impl ExtensionProvider for Modrinth {
             })
         })
     }
+
+    fn get_extension_version_by_jar<'a>(
+        &'a self,
+        _type: ExtensionType,
+        jar: &'a [u8],
+    ) -> BoxedFuture<'a, anyhow::Result<Option<ExtensionJarMatch>>> {
+        Box::pin(async move {
+            let hash = hex::encode(Sha512::digest(jar));
+
+            let version = match modrinth_api::apis::version_files_api::version_from_hash(
+                &CONFIG, &hash, "sha512", None,
+            )
+            .await
+            {
+                Ok(version) => version,
+                Err(modrinth_api::apis::Error::ResponseError(response))
+                    if response.status == StatusCode::NOT_FOUND =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            Ok(Some(ExtensionJarMatch {
+                extension_id: version.project_id,
+                extension_version_id: version.id,
+            }))
+        })
+    }
 }
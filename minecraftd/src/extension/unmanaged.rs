@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use minecraftd_manifest::ExtensionType;
+
+/// Finds the jars in the `mods` and `plugins` directories of a server that were put there by hand
+/// instead of being added as extensions.
+pub async fn find_unmanaged_jars(
+    server_dir: &Path,
+) -> anyhow::Result<Vec<(ExtensionType, PathBuf)>> {
+    let mut jars = Vec::new();
+
+    for (type_, dir) in [
+        (ExtensionType::Mod, "mods"),
+        (ExtensionType::Plugin, "plugins"),
+    ] {
+        let dir = server_dir.join(dir);
+        if !dir.exists() {
+            continue;
+        }
+
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            // managed extensions are symlinks into the extension cache
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("jar"))
+            {
+                jars.push((type_, path));
+            }
+        }
+    }
+
+    jars.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+    Ok(jars)
+}
@@ -0,0 +1,431 @@
+//! Turning directories that already contain a Minecraft server into minecraftd servers.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::File,
+    io::{Cursor, Read, Seek},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use minecraftd_manifest::{Connection, ServerManifest};
+use serde::Deserialize;
+use zip::{ZipArchive, result::ZipError};
+
+use crate::{
+    extension, java_runtime::JavaRuntimeExt, registry,
+    server_implementations::get_server_implementation,
+};
+
+pub struct ImportedServer {
+    pub server_implementation: String,
+    pub version: String,
+    pub build: String,
+    /// Whether the build could not be detected and the latest stable one of the version was
+    /// recorded instead.
+    pub build_assumed: bool,
+    /// Paths relative to the server directory of the jars in `mods` and `plugins` that are not
+    /// managed by minecraftd.
+    pub unmanaged_jars: Vec<String>,
+}
+
+/// Generates a manifest for a server that was set up without minecraftd and registers it. The
+/// implementation and version are detected from the server jar, which is looked for in the server
+/// directory unless `jar` is given. Jars that are not recognized are imported as custom servers.
+///
+/// Everything else in the directory, including `server.properties`, is left as it is.
+pub async fn import_server(
+    server_dir: &Path,
+    name: &str,
+    connection: Connection,
+    jar: Option<&str>,
+) -> anyhow::Result<ImportedServer> {
+    let server_dir = server_dir.canonicalize()?;
+    if ServerManifest::manifest_path(&server_dir).exists() {
+        bail!(
+            "'{}' is already a minecraftd server; register it instead",
+            server_dir.display()
+        );
+    }
+
+    registry::ensure_name_available(name).await?;
+    if let Connection::Proxy { hostname } = &connection {
+        registry::ensure_hostname_available(hostname).await?;
+    }
+
+    let detected = {
+        let server_dir = server_dir.clone();
+        let jar = jar.map(|jar| jar.to_string());
+        tokio::task::spawn_blocking(move || detect_server(&server_dir, jar.as_deref())).await??
+    };
+
+    let implementation = get_server_implementation(detected.server_implementation).unwrap();
+
+    let build_assumed = detected.build.is_none();
+    let build = match detected.build {
+        Some(build) => build,
+        // the build could not be told from the jar, assume the latest one of the version
+        None => {
+            let builds = implementation
+                .get_builds(&detected.version)
+                .await
+                .context("Failed to get builds")?;
+            let index = builds.iter().position(|b| b.is_stable).unwrap_or(0);
+            builds
+                .into_iter()
+                .nth(index)
+                .with_context(|| format!("No builds found for version '{}'", detected.version))?
+                .name
+        }
+    };
+
+    let default_java_runtime = implementation
+        .default_java_runtime(&detected.version, &build)
+        .await
+        .context("Failed to determine default Java runtime for server")?;
+
+    default_java_runtime
+        .prepare()
+        .await
+        .context("Failed to prepare Java runtime")?;
+
+    let mut manifest = ServerManifest::default(
+        detected.server_implementation,
+        &detected.version,
+        &build,
+        default_java_runtime,
+    );
+
+    manifest.name = name.to_string();
+    manifest.connection = connection;
+
+    if detected.server_implementation == "custom" {
+        // custom servers have no jar in the cache, so the command refers to the jar directly
+        for arg in &mut manifest.command {
+            if arg == "${server_jar}" {
+                *arg = OsString::from(&detected.jar);
+            }
+        }
+    }
+
+    manifest
+        .save(&server_dir)
+        .await
+        .context("Failed to save server manifest")?;
+
+    registry::register_server(&manifest, &server_dir).await?;
+
+    let unmanaged_jars = extension::unmanaged::find_unmanaged_jars(&server_dir)
+        .await?
+        .into_iter()
+        .map(|(_, path)| {
+            path.strip_prefix(&server_dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+
+    Ok(ImportedServer {
+        server_implementation: detected.server_implementation.to_string(),
+        version: detected.version,
+        build,
+        build_assumed,
+        unmanaged_jars,
+    })
+}
+
+struct DetectedServer {
+    server_implementation: &'static str,
+    version: String,
+    /// `None` if the build could not be detected.
+    build: Option<String>,
+    /// File name of the server jar.
+    jar: String,
+}
+
+fn detect_server(server_dir: &Path, jar: Option<&str>) -> anyhow::Result<DetectedServer> {
+    let jars = match jar {
+        Some(jar) => {
+            if !server_dir.join(jar).is_file() {
+                bail!("'{jar}' does not exist in '{}'", server_dir.display());
+            }
+            vec![jar.to_string()]
+        }
+        None => {
+            let mut jars = Vec::new();
+            for entry in std::fs::read_dir(server_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|e| e.eq_ignore_ascii_case("jar"))
+                {
+                    jars.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+            jars.sort();
+            jars
+        }
+    };
+
+    // jars that cannot be read, e.g. broken downloads, are skipped, since another jar may be the
+    // server
+    let mut candidates = Vec::new();
+    for jar in &jars {
+        let path = server_dir.join(jar);
+        let inspected = File::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Ok(ZipArchive::new(file)?))
+            .and_then(|archive| inspect_jar(server_dir, archive));
+
+        match inspected {
+            Ok(Some((server_implementation, version, build))) => {
+                return Ok(DetectedServer {
+                    server_implementation,
+                    version,
+                    build,
+                    jar: jar.clone(),
+                });
+            }
+            Ok(None) => candidates.push(jar.clone()),
+            Err(e) => warn!("Skipping '{}', which cannot be read: {e:?}", path.display()),
+        }
+    }
+
+    match candidates.as_slice() {
+        [] if jars.is_empty() => bail!("No server jar found in '{}'", server_dir.display()),
+        [] => bail!(
+            "None of the jars in '{}' could be read: {}",
+            server_dir.display(),
+            jars.join(", ")
+        ),
+        [jar] => Ok(DetectedServer {
+            server_implementation: "custom",
+            version: String::new(),
+            build: Some(String::new()),
+            jar: jar.clone(),
+        }),
+        _ => bail!(
+            "Could not tell which of {} is the server jar; specify it explicitly",
+            candidates.join(", ")
+        ),
+    }
+}
+
+/// Returns the implementation, version and build of a server jar, or `None` if it is neither a
+/// vanilla nor a Paper server.
+fn inspect_jar(
+    server_dir: &Path,
+    mut archive: ZipArchive<impl Read + Seek>,
+) -> anyhow::Result<Option<(&'static str, String, Option<String>)>> {
+    let manifest = read_entry(&mut archive, "META-INF/MANIFEST.MF")?
+        .map(|manifest| parse_jar_manifest(&manifest))
+        .unwrap_or_default();
+    let versions_list = read_entry(&mut archive, "META-INF/versions.list")?;
+
+    let is_paperclip = versions_list.is_some()
+        || manifest
+            .get("Main-Class")
+            .is_some_and(|main_class| main_class.starts_with("io.papermc.paperclip."));
+
+    if is_paperclip {
+        // each line is "<sha256>\t<id>\t<path>", e.g. "...\tpaper-1.21.4\t1.21.4/paper-1.21.4.jar"
+        let Some((id, path)) = versions_list.as_deref().and_then(|versions_list| {
+            versions_list.lines().find_map(|line| {
+                let mut columns = line.split('\t').skip(1);
+                Some((columns.next()?.to_string(), columns.next()?.to_string()))
+            })
+        }) else {
+            return Ok(None);
+        };
+
+        // other servers based on Paper are launched the same way, but have other builds
+        let Some(version) = id.strip_prefix("paper-") else {
+            return Ok(None);
+        };
+
+        let mut build = None;
+
+        if let Some(server_jar) =
+            read_entry_bytes(&mut archive, &format!("META-INF/versions/{path}"))?
+        {
+            let mut server_jar = ZipArchive::new(Cursor::new(server_jar))?;
+            if let Some(server_manifest) = read_entry(&mut server_jar, "META-INF/MANIFEST.MF")? {
+                build = parse_jar_manifest(&server_manifest)
+                    .get("Implementation-Version")
+                    .and_then(|v| parse_paper_build(v, version));
+            }
+        }
+
+        if build.is_none() {
+            build = read_paper_version_history(server_dir)
+                .and_then(|current_version| parse_paper_build(&current_version, version));
+        }
+
+        return Ok(Some(("paper", version.to_string(), build)));
+    }
+
+    if let Some(version_json) = read_entry(&mut archive, "version.json")? {
+        #[derive(Deserialize)]
+        struct VersionJson {
+            id: String,
+        }
+
+        let version_json = serde_json::from_str::<VersionJson>(&version_json)
+            .context("Failed to parse version.json")?;
+
+        // vanilla does not have builds, the version is used as the build
+        return Ok(Some((
+            "vanilla",
+            version_json.id.clone(),
+            Some(version_json.id),
+        )));
+    }
+
+    Ok(None)
+}
+
+fn read_entry_bytes(
+    archive: &mut ZipArchive<impl Read + Seek>,
+    name: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+fn read_entry(
+    archive: &mut ZipArchive<impl Read + Seek>,
+    name: &str,
+) -> anyhow::Result<Option<String>> {
+    read_entry_bytes(archive, name)?
+        .map(|bytes| String::from_utf8(bytes).with_context(|| format!("'{name}' is not UTF-8")))
+        .transpose()
+}
+
+/// Parses the main section of a `MANIFEST.MF`. Continuation lines are ignored.
+fn parse_jar_manifest(manifest: &str) -> HashMap<String, String> {
+    manifest
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Paper records the version it last ran as in `version_history.json`.
+fn read_paper_version_history(server_dir: &Path) -> Option<String> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct VersionHistory {
+        current_version: String,
+    }
+
+    let content = std::fs::read_to_string(server_dir.join("version_history.json")).ok()?;
+    serde_json::from_str::<VersionHistory>(&content)
+        .ok()
+        .map(|history| history.current_version)
+}
+
+/// Extracts the build from a Paper version string, which is either "git-Paper-196 (MC: 1.20.4)"
+/// or "1.21.4-232-5e2a3bc (MC: 1.21.4)", the suffix being optional. Returns `None` if the version
+/// string belongs to another Minecraft version.
+fn parse_paper_build(version_string: &str, version: &str) -> Option<String> {
+    let (version_string, mc_version) = match version_string.split_once(" (MC: ") {
+        Some((version_string, mc_version)) => (version_string, mc_version.strip_suffix(')')),
+        None => (version_string, None),
+    };
+    if mc_version.is_some_and(|mc_version| mc_version != version) {
+        return None;
+    }
+
+    let build = match version_string.strip_prefix("git-Paper-") {
+        Some(build) => build,
+        None => {
+            let (mc_version, rest) = version_string.split_once('-')?;
+            if mc_version != version {
+                return None;
+            }
+            rest.split('-').next()?
+        }
+    };
+
+    (!build.is_empty() && build.bytes().all(|b| b.is_ascii_digit())).then(|| build.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn legacy_paper_build() {
+        assert_eq!(
+            parse_paper_build("git-Paper-196 (MC: 1.20.4)", "1.20.4").as_deref(),
+            Some("196")
+        );
+        assert_eq!(
+            parse_paper_build("git-Paper-196", "1.20.4").as_deref(),
+            Some("196")
+        );
+    }
+
+    #[test]
+    fn paper_build() {
+        assert_eq!(
+            parse_paper_build("1.21.4-232-5e2a3bc (MC: 1.21.4)", "1.21.4").as_deref(),
+            Some("232")
+        );
+        assert_eq!(
+            parse_paper_build("1.21.4-232-5e2a3bc", "1.21.4").as_deref(),
+            Some("232")
+        );
+    }
+
+    #[test]
+    fn other_minecraft_version() {
+        assert_eq!(
+            parse_paper_build("git-Paper-196 (MC: 1.20.4)", "1.20.6"),
+            None
+        );
+        assert_eq!(parse_paper_build("1.21.4-232-5e2a3bc", "1.21.3"), None);
+    }
+
+    #[test]
+    fn not_a_build() {
+        assert_eq!(
+            parse_paper_build("git-Paper-abc (MC: 1.20.4)", "1.20.4"),
+            None
+        );
+        assert_eq!(parse_paper_build("git-Paper- (MC: 1.20.4)", "1.20.4"), None);
+        assert_eq!(parse_paper_build("1.21.4", "1.21.4"), None);
+        assert_eq!(parse_paper_build("", "1.21.4"), None);
+    }
+
+    #[test]
+    fn unreadable_jars_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("broken.jar"), b"not a zip").unwrap();
+
+        assert!(detect_server(dir.path(), None).is_err());
+
+        let mut server_jar =
+            zip::ZipWriter::new(File::create(dir.path().join("server.jar")).unwrap());
+        server_jar
+            .start_file("Main.class", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        server_jar.finish().unwrap();
+
+        let detected = detect_server(dir.path(), None).unwrap();
+        assert_eq!(detected.server_implementation, "custom");
+        assert_eq!(detected.jar, "server.jar");
+    }
+}
@@ -3,18 +3,27 @@ use crate::{config::init_config, lock::Lock};
 mod alert;
 mod auto_start;
 mod auto_update;
+mod backup;
 mod bridge;
 mod config;
 mod extension;
+mod import;
 mod java_runtime;
 mod lock;
 mod metrics;
+mod player_lists;
+mod player_sessions;
 mod port_pool;
 mod proxy_server;
+mod registry;
 mod runner;
+mod scheduler;
 mod server;
 mod server_implementations;
+mod sleep;
 mod socket;
+mod supervisor;
+mod update_rollback;
 mod util;
 
 #[macro_use]
@@ -24,6 +33,14 @@ extern crate log;
 async fn main() {
     pretty_env_logger::init();
 
+    if let Some(result) = supervisor::run_if_requested().await {
+        if let Err(e) = result {
+            error!("{e:?}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     if let Err(e) = start().await {
         error!("{e:?}");
         std::process::exit(1);
@@ -37,9 +54,14 @@ async fn start() -> anyhow::Result<()> {
 
     port_pool::init();
     metrics::init().await?;
+    player_sessions::init().await;
     proxy_server::init().await?;
     runner::init().await?;
     auto_update::init();
+    backup::init();
+    scheduler::init();
+    sleep::init();
+    player_lists::init();
 
     socket::start_server().await?;
 
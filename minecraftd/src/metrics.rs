@@ -7,6 +7,7 @@ use std::{
 };
 
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use tokio::{
     sync::{Mutex, MutexGuard},
     time::MissedTickBehavior,
@@ -29,6 +30,14 @@ struct MetricsManager {
     storage: Arc<dyn Storage>,
     metrics_collectors: Vec<Box<dyn MetricsCollector>>,
     alert_rules: HashMap<&'static str, AlertRuleEntry>, // key: metric name
+    deleted_servers: DeletedServers,
+}
+
+/// Servers that were deleted, with the time of deletion in seconds since the epoch. The storage
+/// cannot delete series, so their data points are hidden until the retention removes them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DeletedServers {
+    servers: HashMap<Uuid, i64>,
 }
 
 struct AlertRuleEntry {
@@ -98,6 +107,60 @@ fn metrics_storage_path() -> anyhow::Result<PathBuf> {
     Ok(path)
 }
 
+fn deleted_servers_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("deleted_metrics.json");
+    Ok(path)
+}
+
+async fn load_deleted_servers() -> anyhow::Result<DeletedServers> {
+    let content = match tokio::fs::read_to_string(deleted_servers_path()?).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(DeletedServers::default()),
+        Err(e) => return Err(e).context("Failed to read deleted metrics file"),
+    };
+    serde_json::from_str(&content).context("Failed to parse deleted metrics file")
+}
+
+async fn save_deleted_servers(deleted_servers: &DeletedServers) -> anyhow::Result<()> {
+    let path = deleted_servers_path()?;
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create data directory")?;
+    tokio::fs::write(path, serde_json::to_string(deleted_servers)?)
+        .await
+        .context("Failed to write deleted metrics file")?;
+    Ok(())
+}
+
+fn unix_timestamp(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+impl DeletedServers {
+    /// Whether a data point of the server is hidden because it was recorded before the server was
+    /// deleted.
+    fn is_hidden(&self, server_id: Uuid, timestamp: i64) -> bool {
+        self.servers
+            .get(&server_id)
+            .is_some_and(|deleted_at| timestamp <= *deleted_at)
+    }
+
+    /// Forgets the servers whose data points have all been removed by the retention. Returns
+    /// whether any were forgotten.
+    fn prune(&mut self) -> bool {
+        let retention = get_config().metrics.storage_retention.as_secs() as i64;
+        let now = unix_timestamp(SystemTime::now());
+        let len = self.servers.len();
+        self.servers
+            .retain(|_, deleted_at| *deleted_at + retention >= now);
+        self.servers.len() != len
+    }
+}
+
 async fn get_metrics_manager() -> MutexGuard<'static, MetricsManager> {
     METRICS_MANAGER
         .get()
@@ -119,11 +182,17 @@ pub async fn init() -> anyhow::Result<()> {
     .await
     .unwrap()?;
 
+    let mut deleted_servers = load_deleted_servers().await?;
+    if deleted_servers.prune() {
+        save_deleted_servers(&deleted_servers).await?;
+    }
+
     METRICS_MANAGER
         .set(Mutex::new(MetricsManager {
             storage,
             metrics_collectors: Vec::new(),
             alert_rules: HashMap::new(),
+            deleted_servers,
         }))
         .ok()
         .expect("init_metrics called multiple times");
@@ -153,6 +222,12 @@ async fn collect_metrics() {
         }
     }
 
+    // a deleted server may have been collected while it was being deleted
+    let deleted_servers = &manager.deleted_servers;
+    context.metrics.retain(|metric| {
+        !deleted_servers.is_hidden(metric.server_id, unix_timestamp(metric.timestamp))
+    });
+
     for metric in &context.metrics {
         if let Some(alert_rule_entry) = manager.alert_rules.get_mut(&*metric.metric) {
             let condition_met = match alert_rule_entry.rule.condition {
@@ -205,11 +280,7 @@ async fn collect_metrics() {
                 vec![Label::new("server_id", entry.server_id.to_string())],
                 DataPoint {
                     value: entry.value,
-                    timestamp: entry
-                        .timestamp
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as i64,
+                    timestamp: unix_timestamp(entry.timestamp),
                 },
             )
         })
@@ -238,6 +309,18 @@ pub async fn register_metrics_collector(collector: impl MetricsCollector) {
     manager.metrics_collectors.push(Box::new(collector));
 }
 
+/// Hides the metrics of a deleted server. They are removed from the storage once they are older
+/// than `metrics.storage_retention`.
+pub async fn delete_server_metrics(server_id: Uuid) -> anyhow::Result<()> {
+    let mut manager = get_metrics_manager().await;
+    manager.deleted_servers.prune();
+    manager
+        .deleted_servers
+        .servers
+        .insert(server_id, unix_timestamp(SystemTime::now()));
+    save_deleted_servers(&manager.deleted_servers).await
+}
+
 pub async fn shutdown() {
     let manager = get_metrics_manager().await;
     if let Err(err) = tokio::task::spawn_blocking(move || manager.storage.close())
@@ -262,12 +345,21 @@ pub struct MetricsQuery {
 pub async fn query(query: MetricsQuery) -> anyhow::Result<Vec<DataPoint>> {
     let manager = get_metrics_manager().await;
 
+    // only data points recorded after the deletion of a server with the same ID are returned
+    let start_timestamp = match manager.deleted_servers.servers.get(&query.server_id) {
+        Some(deleted_at) => query.start_timestamp.max(deleted_at + 1),
+        None => query.start_timestamp,
+    };
+    if start_timestamp != query.start_timestamp && start_timestamp >= query.end_timestamp {
+        return Ok(Vec::new());
+    }
+
     tokio::task::spawn_blocking(move || {
         let rows = manager.storage.select_with_options(
             &query.metric,
             QueryOptions {
                 labels: vec![Label::new("server_id", query.server_id.to_string())],
-                start: query.start_timestamp,
+                start: start_timestamp,
                 end: query.end_timestamp,
                 aggregation: query.aggregation,
                 downsample: query
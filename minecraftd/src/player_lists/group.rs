@@ -0,0 +1,354 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use minecraftd_manifest::ServerManifest;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    config::get_config,
+    player_lists::{
+        PLAYER_NAME_REGEX, PlayerListType, entry_uuid, find_entry, load_player_list,
+        remove_by_name, save_player_list, uuid_resolver::GameProfile,
+    },
+    runner::{self, ServerStatus},
+};
+
+const SYNC_INTERVAL_SECS: u64 = 5;
+
+/// Never hold this while calling into the runner, since the runner holds its lock while syncing a
+/// starting server.
+static SYNC_STATE: LazyLock<Mutex<HashMap<Uuid, MemberState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// What is known about the lists of a running server in a group.
+#[derive(Default)]
+struct MemberState {
+    /// The lists as they were when last synced, to tell which entries were changed on the server.
+    snapshots: HashMap<PlayerListType, Vec<Value>>,
+    /// Whether each entry was last added (true) or removed (false) with a command, by list and
+    /// UUID, so that a command the server ignores is not sent again and again.
+    pushed: HashMap<(PlayerListType, String), bool>,
+}
+
+/// Keeps the lists of running servers in sync with their groups.
+pub fn init() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(SYNC_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            sync_running_servers().await;
+        }
+    });
+}
+
+/// The group of the server if it shares this list.
+pub fn get_shared_group(
+    manifest: &ServerManifest,
+    list_type: PlayerListType,
+) -> anyhow::Result<Option<&str>> {
+    let Some(group) = &manifest.player_list_group else {
+        return Ok(None);
+    };
+    let Some(config) = get_config().player_lists.get(group) else {
+        bail!("Player list group '{group}' is not defined in the minecraftd configuration");
+    };
+
+    let shared = match list_type {
+        PlayerListType::Whitelist => config.whitelist,
+        PlayerListType::Ops => config.ops,
+        PlayerListType::BannedPlayers => config.banned_players,
+    };
+    Ok(shared.then_some(group.as_str()))
+}
+
+pub fn group_dir(group: &str) -> anyhow::Result<PathBuf> {
+    if group.is_empty()
+        || !group
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("Invalid player list group name '{group}'");
+    }
+
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("player_lists");
+    path.push(group);
+    Ok(path)
+}
+
+/// Writes the lists of the group into the files of a server that is about to start. A list the
+/// group does not have yet is taken from the server instead.
+pub async fn sync_to_server(
+    id: Uuid,
+    server_dir: &Path,
+    manifest: &ServerManifest,
+) -> anyhow::Result<()> {
+    let mut state = SYNC_STATE.lock().await;
+    let member = state.entry(id).or_default();
+    member.pushed.clear();
+
+    for list_type in PlayerListType::ALL {
+        let Some(group) = get_shared_group(manifest, list_type)? else {
+            member.snapshots.remove(&list_type);
+            continue;
+        };
+        let dir = group_dir(group)?;
+
+        let entries = if tokio::fs::try_exists(dir.join(list_type.file_name())).await? {
+            let entries = load_player_list(&dir, list_type).await?;
+            save_player_list(server_dir, list_type, &entries).await?;
+            entries
+        } else {
+            info!(
+                "Initializing {} of player list group '{group}' from server at '{}'",
+                list_type.file_name(),
+                server_dir.display()
+            );
+            let entries = load_player_list(server_dir, list_type).await?;
+            tokio::fs::create_dir_all(&dir)
+                .await
+                .context("Failed to create player list group directory")?;
+            save_player_list(&dir, list_type, &entries).await?;
+            entries
+        };
+        member.snapshots.insert(list_type, entries);
+    }
+
+    Ok(())
+}
+
+pub async fn add_entry(
+    group: &str,
+    list_type: PlayerListType,
+    profile: &GameProfile,
+    entry: Value,
+) -> anyhow::Result<String> {
+    let dir = group_dir(group)?;
+    {
+        let _state = SYNC_STATE.lock().await;
+        let mut entries = load_player_list(&dir, list_type).await?;
+        if find_entry(&entries, &profile.uuid.to_string()).is_some() {
+            bail!(
+                "{} is already in {} of player list group '{group}'",
+                profile.name,
+                list_type.file_name()
+            );
+        }
+        entries.push(entry);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .context("Failed to create player list group directory")?;
+        save_player_list(&dir, list_type, &entries).await?;
+    }
+
+    sync_running_servers().await;
+
+    Ok(format!(
+        "Added {} ({}) to {} of player list group '{group}'",
+        profile.name,
+        profile.uuid,
+        list_type.file_name()
+    ))
+}
+
+pub async fn remove_entry(
+    group: &str,
+    list_type: PlayerListType,
+    player: &str,
+) -> anyhow::Result<String> {
+    let dir = group_dir(group)?;
+    {
+        let _state = SYNC_STATE.lock().await;
+        let mut entries = load_player_list(&dir, list_type).await?;
+        if !remove_by_name(&mut entries, player) {
+            bail!(
+                "{player} is not in {} of player list group '{group}'",
+                list_type.file_name()
+            );
+        }
+        save_player_list(&dir, list_type, &entries).await?;
+    }
+
+    sync_running_servers().await;
+
+    Ok(format!(
+        "Removed {player} from {} of player list group '{group}'",
+        list_type.file_name()
+    ))
+}
+
+struct Member {
+    id: Uuid,
+    server_dir: PathBuf,
+    manifest: ServerManifest,
+}
+
+/// Merges the changes made on each ready server, e.g. with `/ban` in game, into its groups, then
+/// sends commands to the servers whose lists differ from their groups.
+async fn sync_running_servers() {
+    let mut members = Vec::new();
+    let running_server_ids = runner::get_running_server_ids().await;
+    for id in &running_server_ids {
+        // commands can only be sent to ready servers, and the others are synced when they start
+        if runner::get_server_status(*id).await != Some(ServerStatus::Ready) {
+            continue;
+        }
+        let (Some(server_dir), Some(manifest)) = (
+            runner::get_server_dir(*id).await,
+            runner::get_server_manifest(*id).await,
+        ) else {
+            continue;
+        };
+        if manifest.player_list_group.is_some() {
+            members.push(Member {
+                id: *id,
+                server_dir,
+                manifest: (*manifest).clone(),
+            });
+        }
+    }
+
+    let mut commands = Vec::new();
+    {
+        let mut state = SYNC_STATE.lock().await;
+        state.retain(|id, _| running_server_ids.contains(id));
+
+        for member in &members {
+            let member_state = state.entry(member.id).or_default();
+            for list_type in PlayerListType::ALL {
+                match sync_list(member, member_state, list_type).await {
+                    Ok(list_commands) => commands.extend(
+                        list_commands
+                            .into_iter()
+                            .map(|command| (member.id, command)),
+                    ),
+                    Err(e) => error!(
+                        "Failed to sync {} of server {}: {e:?}",
+                        list_type.file_name(),
+                        member.id
+                    ),
+                }
+            }
+        }
+    }
+
+    for (id, command) in commands {
+        debug!("Syncing player list of server {id}: {command}");
+        if let Err(e) = runner::execute_command(id, &command).await {
+            warn!("Failed to sync player list of server {id}: {e:?}");
+        }
+    }
+}
+
+/// Returns the commands to send to the server.
+async fn sync_list(
+    member: &Member,
+    state: &mut MemberState,
+    list_type: PlayerListType,
+) -> anyhow::Result<Vec<String>> {
+    let Some(group) = get_shared_group(&member.manifest, list_type)? else {
+        return Ok(Vec::new());
+    };
+    let dir = group_dir(group)?;
+
+    let current = load_player_list(&member.server_dir, list_type).await?;
+    let mut group_entries = load_player_list(&dir, list_type).await?;
+
+    // a server that was running before minecraftd started has nothing to compare with yet
+    if let Some(snapshot) = state.snapshots.get(&list_type) {
+        let mut changed = false;
+        for entry in &current {
+            if let Some(uuid) = entry_uuid(entry)
+                && find_entry(snapshot, uuid).is_none()
+                && find_entry(&group_entries, uuid).is_none()
+            {
+                group_entries.push(entry.clone());
+                changed = true;
+            }
+        }
+        for entry in snapshot {
+            if let Some(uuid) = entry_uuid(entry)
+                && find_entry(&current, uuid).is_none()
+                && find_entry(&group_entries, uuid).is_some()
+            {
+                group_entries.retain(|entry| entry_uuid(entry) != Some(uuid));
+                changed = true;
+            }
+        }
+        if changed {
+            info!(
+                "Updating {} of player list group '{group}' with changes made on server {}",
+                list_type.file_name(),
+                member.id
+            );
+            save_player_list(&dir, list_type, &group_entries).await?;
+        }
+    }
+
+    // commands that have taken effect may be needed again later
+    state.pushed.retain(|(pushed_list_type, uuid), add| {
+        *pushed_list_type != list_type || find_entry(&current, uuid).is_some() != *add
+    });
+
+    let mut commands = Vec::new();
+    for entry in &group_entries {
+        if let Some(command) = push_command(state, list_type, entry, &current, true) {
+            commands.push(command);
+        }
+    }
+    for entry in &current {
+        if let Some(command) = push_command(state, list_type, entry, &group_entries, false) {
+            commands.push(command);
+        }
+    }
+
+    state.snapshots.insert(list_type, current);
+    Ok(commands)
+}
+
+/// The command that adds `entry` to or removes it from the server, if `others` (the server's list
+/// when adding, the group's when removing) does not have it and the command has not been sent yet.
+fn push_command(
+    state: &mut MemberState,
+    list_type: PlayerListType,
+    entry: &Value,
+    others: &[Value],
+    add: bool,
+) -> Option<String> {
+    let uuid = entry_uuid(entry)?;
+    if find_entry(others, uuid).is_some() {
+        return None;
+    }
+    let name = entry
+        .get("name")
+        .and_then(Value::as_str)
+        .filter(|name| PLAYER_NAME_REGEX.is_match(name))?;
+
+    if state
+        .pushed
+        .insert((list_type, uuid.to_string()), add)
+        .is_some_and(|pushed| pushed == add)
+    {
+        return None;
+    }
+
+    Some(if add {
+        let reason = entry
+            .get("reason")
+            .and_then(Value::as_str)
+            .filter(|reason| !reason.contains(['\n', '\r']));
+        list_type.add_command(name, reason)
+    } else {
+        list_type.remove_command(name)
+    })
+}
@@ -0,0 +1,270 @@
+use std::{path::Path, sync::LazyLock};
+
+use anyhow::{Context, bail};
+use minecraftd_manifest::ServerManifest;
+use regex::Regex;
+use serde_json::{Value, json};
+
+use crate::{
+    player_lists::uuid_resolver::{GameProfile, get_uuid_resolver},
+    runner,
+    util::server_properties::ServerProperties,
+};
+
+pub mod group;
+pub mod uuid_resolver;
+
+const DEFAULT_OP_PERMISSION_LEVEL: u32 = 4;
+const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+/// Player names are put into commands, so anything else must not get through.
+static PLAYER_NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\w{1,16}$").unwrap());
+
+pub fn init() {
+    group::init();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerListType {
+    Whitelist,
+    Ops,
+    BannedPlayers,
+}
+
+impl PlayerListType {
+    pub const ALL: [PlayerListType; 3] = [
+        PlayerListType::Whitelist,
+        PlayerListType::Ops,
+        PlayerListType::BannedPlayers,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            PlayerListType::Whitelist => "whitelist.json",
+            PlayerListType::Ops => "ops.json",
+            PlayerListType::BannedPlayers => "banned-players.json",
+        }
+    }
+
+    fn add_command(self, player: &str, reason: Option<&str>) -> String {
+        match (self, reason) {
+            (PlayerListType::Whitelist, _) => format!("whitelist add {player}"),
+            (PlayerListType::Ops, _) => format!("op {player}"),
+            (PlayerListType::BannedPlayers, Some(reason)) => format!("ban {player} {reason}"),
+            (PlayerListType::BannedPlayers, None) => format!("ban {player}"),
+        }
+    }
+
+    fn remove_command(self, player: &str) -> String {
+        match self {
+            PlayerListType::Whitelist => format!("whitelist remove {player}"),
+            PlayerListType::Ops => format!("deop {player}"),
+            PlayerListType::BannedPlayers => format!("pardon {player}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerListEntry {
+    pub uuid: String,
+    pub name: String,
+    pub level: Option<u32>,
+    pub reason: Option<String>,
+    pub expires: Option<String>,
+}
+
+impl PlayerListEntry {
+    fn from_json(entry: &Value) -> Self {
+        let string = |key: &str| entry.get(key).and_then(Value::as_str).map(str::to_string);
+        Self {
+            uuid: string("uuid").unwrap_or_default(),
+            name: string("name").unwrap_or_default(),
+            level: entry
+                .get("level")
+                .and_then(Value::as_u64)
+                .map(|level| level as u32),
+            reason: string("reason"),
+            expires: string("expires"),
+        }
+    }
+}
+
+/// The list of the group if the server shares it, or of the server otherwise.
+pub async fn get_player_list(
+    server_dir: &Path,
+    list_type: PlayerListType,
+) -> anyhow::Result<Vec<PlayerListEntry>> {
+    let manifest = ServerManifest::load(server_dir).await?;
+    let dir = match group::get_shared_group(&manifest, list_type)? {
+        Some(group) => group::group_dir(group)?,
+        None => server_dir.to_path_buf(),
+    };
+
+    Ok(load_player_list(&dir, list_type)
+        .await?
+        .iter()
+        .map(PlayerListEntry::from_json)
+        .collect())
+}
+
+/// Adds a player with a command if the server is running, since the server would overwrite changes
+/// to the file, or by editing the file otherwise. If the server shares the list, the player is added
+/// to the group instead. Returns a message describing the result.
+pub async fn add_player_list_entry(
+    server_dir: &Path,
+    list_type: PlayerListType,
+    player: &str,
+    reason: Option<&str>,
+) -> anyhow::Result<String> {
+    validate_player_name(player)?;
+    if let Some(reason) = reason
+        && reason.contains(['\n', '\r'])
+    {
+        bail!("Ban reason must be a single line");
+    }
+
+    let manifest = ServerManifest::load(server_dir).await?;
+    if let Some(group) = group::get_shared_group(&manifest, list_type)? {
+        let (profile, entry) = new_entry(server_dir, list_type, player, reason).await?;
+        return group::add_entry(group, list_type, &profile, entry).await;
+    }
+
+    if let Some(id) = runner::get_running_server_id(server_dir).await? {
+        return runner::execute_command(id, &list_type.add_command(player, reason)).await;
+    }
+
+    let (profile, entry) = new_entry(server_dir, list_type, player, reason).await?;
+
+    let mut entries = load_player_list(server_dir, list_type).await?;
+    if find_entry(&entries, &profile.uuid.to_string()).is_some() {
+        bail!("{} is already in {}", profile.name, list_type.file_name());
+    }
+
+    entries.push(entry);
+    save_player_list(server_dir, list_type, &entries).await?;
+
+    Ok(format!(
+        "Added {} ({}) to {}",
+        profile.name,
+        profile.uuid,
+        list_type.file_name()
+    ))
+}
+
+/// Looks up the player and builds an entry like the server would.
+async fn new_entry(
+    server_dir: &Path,
+    list_type: PlayerListType,
+    player: &str,
+    reason: Option<&str>,
+) -> anyhow::Result<(GameProfile, Value)> {
+    let properties = ServerProperties::load(server_dir).await.ok();
+    let online_mode = properties
+        .as_ref()
+        .and_then(|properties| properties.get("online-mode"))
+        .is_none_or(|online_mode| online_mode == "true");
+    let profile = get_uuid_resolver(online_mode).resolve(player).await?;
+
+    let entry = match list_type {
+        PlayerListType::Whitelist => json!({
+            "uuid": profile.uuid,
+            "name": profile.name,
+        }),
+        PlayerListType::Ops => json!({
+            "uuid": profile.uuid,
+            "name": profile.name,
+            "level": properties
+                .as_ref()
+                .and_then(|properties| properties.get("op-permission-level"))
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(DEFAULT_OP_PERMISSION_LEVEL),
+            "bypassesPlayerLimit": false,
+        }),
+        PlayerListType::BannedPlayers => json!({
+            "uuid": profile.uuid,
+            "name": profile.name,
+            "created": chrono::Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(),
+            "source": "Server",
+            "expires": "forever",
+            "reason": reason.unwrap_or(DEFAULT_BAN_REASON),
+        }),
+    };
+
+    Ok((profile, entry))
+}
+
+/// Removes a player the same way as `add_player_list_entry`. Returns a message describing the
+/// result.
+pub async fn remove_player_list_entry(
+    server_dir: &Path,
+    list_type: PlayerListType,
+    player: &str,
+) -> anyhow::Result<String> {
+    validate_player_name(player)?;
+
+    let manifest = ServerManifest::load(server_dir).await?;
+    if let Some(group) = group::get_shared_group(&manifest, list_type)? {
+        return group::remove_entry(group, list_type, player).await;
+    }
+
+    if let Some(id) = runner::get_running_server_id(server_dir).await? {
+        return runner::execute_command(id, &list_type.remove_command(player)).await;
+    }
+
+    let mut entries = load_player_list(server_dir, list_type).await?;
+    if !remove_by_name(&mut entries, player) {
+        bail!("{player} is not in {}", list_type.file_name());
+    }
+    save_player_list(server_dir, list_type, &entries).await?;
+
+    Ok(format!("Removed {player} from {}", list_type.file_name()))
+}
+
+fn find_entry<'a>(entries: &'a [Value], uuid: &str) -> Option<&'a Value> {
+    entries.iter().find(|entry| entry_uuid(entry) == Some(uuid))
+}
+
+fn entry_uuid(entry: &Value) -> Option<&str> {
+    entry.get("uuid").and_then(Value::as_str)
+}
+
+/// Returns false if there was no entry with the name.
+fn remove_by_name(entries: &mut Vec<Value>, player: &str) -> bool {
+    let count = entries.len();
+    entries.retain(|entry| {
+        !entry
+            .get("name")
+            .and_then(Value::as_str)
+            .is_some_and(|name| name.eq_ignore_ascii_case(player))
+    });
+    entries.len() != count
+}
+
+fn validate_player_name(player: &str) -> anyhow::Result<()> {
+    if !PLAYER_NAME_REGEX.is_match(player) {
+        bail!("Invalid player name '{player}'");
+    }
+    Ok(())
+}
+
+/// Reads a list from a server directory or the directory of a group.
+async fn load_player_list(dir: &Path, list_type: PlayerListType) -> anyhow::Result<Vec<Value>> {
+    let path = dir.join(list_type.file_name());
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+async fn save_player_list(
+    dir: &Path,
+    list_type: PlayerListType,
+    entries: &[Value],
+) -> anyhow::Result<()> {
+    let path = dir.join(list_type.file_name());
+    tokio::fs::write(&path, serde_json::to_string_pretty(entries)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, bail};
+use md5::{Digest, Md5};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::util::{BoxedFuture, lazy_init_http_client::LazyInitHttpClient};
+
+static CLIENT: LazyInitHttpClient = LazyInitHttpClient::new();
+
+const MOJANG_PROFILE_API_URL: &str = "https://api.mojang.com/users/profiles/minecraft";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameProfile {
+    #[serde(rename = "id")]
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// Looks up the UUID of a player by name, the way the server would.
+pub trait UuidResolver: Send + Sync {
+    fn resolve<'a>(&'a self, name: &'a str) -> BoxedFuture<'a, anyhow::Result<GameProfile>>;
+}
+
+/// Resolves names of Minecraft accounts, for servers in online mode.
+pub struct MojangUuidResolver;
+
+impl UuidResolver for MojangUuidResolver {
+    fn resolve<'a>(&'a self, name: &'a str) -> BoxedFuture<'a, anyhow::Result<GameProfile>> {
+        Box::pin(async move {
+            let response = CLIENT
+                .get(format!("{MOJANG_PROFILE_API_URL}/{name}"))
+                .send()
+                .await
+                .context("Failed to look up player")?;
+
+            if matches!(
+                response.status(),
+                StatusCode::NOT_FOUND | StatusCode::NO_CONTENT
+            ) {
+                bail!("Player '{name}' does not exist");
+            }
+
+            response
+                .error_for_status()
+                .context("Failed to look up player")?
+                .json()
+                .await
+                .context("Failed to parse player profile")
+        })
+    }
+}
+
+/// Derives the UUID from the name, for servers in offline mode.
+pub struct OfflineUuidResolver;
+
+impl UuidResolver for OfflineUuidResolver {
+    fn resolve<'a>(&'a self, name: &'a str) -> BoxedFuture<'a, anyhow::Result<GameProfile>> {
+        Box::pin(async move {
+            Ok(GameProfile {
+                uuid: offline_uuid(name),
+                name: name.to_string(),
+            })
+        })
+    }
+}
+
+/// The UUID the server assigns to a player in offline mode, the same as Java's
+/// `UUID.nameUUIDFromBytes("OfflinePlayer:" + name)`.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{name}"));
+    uuid::Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+pub fn get_uuid_resolver(online_mode: bool) -> &'static dyn UuidResolver {
+    if online_mode {
+        &MojangUuidResolver
+    } else {
+        &OfflineUuidResolver
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    net::IpAddr,
+    path::PathBuf,
+    sync::LazyLock,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    metrics::{self, MetricsCollector, MetricsCollectorContext},
+    runner,
+    util::BoxedFuture,
+};
+
+/// Sessions of the servers that have been loaded, by server ID.
+static PLAYER_SESSIONS: LazyLock<Mutex<HashMap<Uuid, Vec<PlayerSession>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSession {
+    /// Not known for servers in offline mode.
+    pub uuid: Option<Uuid>,
+    pub name: String,
+    pub joined_at: DateTime<Utc>,
+    /// `None` while the player is online.
+    pub left_at: Option<DateTime<Utc>>,
+    /// The address the player connected from. For connections through the proxy, this is the
+    /// address the proxy accepted the connection from.
+    pub address: Option<IpAddr>,
+}
+
+fn player_sessions_path(server_id: Uuid) -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("player_sessions");
+    path.push(format!("{server_id}.json"));
+    Ok(path)
+}
+
+async fn load_player_sessions(server_id: Uuid) -> anyhow::Result<Vec<PlayerSession>> {
+    let content = match tokio::fs::read_to_string(player_sessions_path(server_id)?).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read player sessions file"),
+    };
+    serde_json::from_str(&content).context("Failed to parse player sessions file")
+}
+
+async fn save_player_sessions(server_id: Uuid, sessions: &[PlayerSession]) -> anyhow::Result<()> {
+    let path = player_sessions_path(server_id)?;
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create player sessions directory")?;
+    tokio::fs::write(path, serde_json::to_string(sessions)?)
+        .await
+        .context("Failed to write player sessions file")?;
+    Ok(())
+}
+
+pub async fn init() {
+    metrics::register_metrics_collector(PlayerSessionMetricsCollector).await;
+}
+
+async fn get_or_load(
+    player_sessions: &mut HashMap<Uuid, Vec<PlayerSession>>,
+    server_id: Uuid,
+) -> anyhow::Result<&mut Vec<PlayerSession>> {
+    Ok(match player_sessions.entry(server_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(load_player_sessions(server_id).await?),
+    })
+}
+
+/// Applies `f` to the sessions of a server and saves them.
+async fn update_player_sessions(
+    server_id: Uuid,
+    f: impl FnOnce(&mut Vec<PlayerSession>),
+) -> anyhow::Result<()> {
+    let mut player_sessions = PLAYER_SESSIONS.lock().await;
+    let sessions = get_or_load(&mut player_sessions, server_id).await?;
+
+    f(sessions);
+
+    save_player_sessions(server_id, sessions).await
+}
+
+pub async fn record_join(
+    server_id: Uuid,
+    name: &str,
+    uuid: Option<Uuid>,
+    address: Option<IpAddr>,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    update_player_sessions(server_id, |sessions| {
+        // the previous session ended without being noticed, e.g. while minecraftd was not running
+        for session in sessions.iter_mut() {
+            if session.name == name && session.left_at.is_none() {
+                session.left_at = Some(now);
+            }
+        }
+
+        sessions.push(PlayerSession {
+            uuid,
+            name: name.to_string(),
+            joined_at: now,
+            left_at: None,
+            address,
+        });
+    })
+    .await
+}
+
+pub async fn record_leave(server_id: Uuid, name: &str) -> anyhow::Result<()> {
+    let now = Utc::now();
+    update_player_sessions(server_id, |sessions| {
+        for session in sessions.iter_mut() {
+            if session.name == name && session.left_at.is_none() {
+                session.left_at = Some(now);
+            }
+        }
+    })
+    .await
+}
+
+/// Ends the sessions of all players that are still online, e.g. when the server has stopped.
+pub async fn end_open_sessions(server_id: Uuid) -> anyhow::Result<()> {
+    let now = Utc::now();
+    update_player_sessions(server_id, |sessions| {
+        for session in sessions.iter_mut() {
+            if session.left_at.is_none() {
+                session.left_at = Some(now);
+            }
+        }
+    })
+    .await
+}
+
+/// Forgets the sessions of a server that was deleted.
+pub async fn delete_player_sessions(server_id: Uuid) -> anyhow::Result<()> {
+    let mut player_sessions = PLAYER_SESSIONS.lock().await;
+    player_sessions.remove(&server_id);
+    match tokio::fs::remove_file(player_sessions_path(server_id)?).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context("Failed to delete player sessions file")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// All sessions of a server, oldest first.
+pub async fn get_player_sessions(server_id: Uuid) -> anyhow::Result<Vec<PlayerSession>> {
+    let mut player_sessions = PLAYER_SESSIONS.lock().await;
+    Ok(get_or_load(&mut player_sessions, server_id).await?.clone())
+}
+
+struct PlayerSessionMetricsCollector;
+
+impl MetricsCollector for PlayerSessionMetricsCollector {
+    fn name(&self) -> &'static str {
+        "player_session_metrics_collector"
+    }
+
+    fn collect<'a>(
+        &'a mut self,
+        ctx: &'a mut MetricsCollectorContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let running_servers = runner::get_running_server_ids().await;
+            let player_sessions = PLAYER_SESSIONS.lock().await;
+            let timestamp = SystemTime::now();
+
+            for server_id in running_servers {
+                let online = player_sessions.get(&server_id).map_or(0, |sessions| {
+                    sessions
+                        .iter()
+                        .filter(|session| session.left_at.is_none())
+                        .count()
+                });
+                ctx.push_metric(server_id, "player_sessions", timestamp, online as f64);
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -53,6 +53,15 @@ impl Port {
         )
     }
 
+    /// Takes a specific port, e.g. one still used by a server started by a previous daemon.
+    pub fn reserve(port: u16) -> anyhow::Result<Self> {
+        let mut pool = get_instance();
+        if !pool.used_ports.insert(port) {
+            bail!("Port {port} is already in use");
+        }
+        Ok(Port { port })
+    }
+
     pub fn port(&self) -> u16 {
         self.port
     }
@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
-    net::Ipv4Addr,
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
     sync::{
         Arc, LazyLock,
         atomic::{AtomicU64, Ordering},
@@ -17,6 +18,8 @@ use minecraft_protocol::{
     raw_packet_stream::RawPacketStream,
     text_component::TextComponent,
 };
+use minecraftd_manifest::{Connection, ServerManifest};
+use serde::{Deserialize, Serialize};
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::TcpStream,
@@ -40,6 +43,43 @@ static PROXY_SERVER: LazyLock<Mutex<ProxyServer>> =
 struct ProxyServer {
     hostname_to_server_id: HashMap<String, Uuid>,
     servers: HashMap<Uuid, Server>,
+    sleeping_servers: SleepingServers,
+    /// Hostnames of sleeping servers that are being started.
+    waking_hostnames: HashSet<String>,
+    /// Addresses of the clients being forwarded, by server ID and the local port of the connection
+    /// to the server. The server only sees connections from localhost.
+    client_addresses: HashMap<(Uuid, u16), IpAddr>,
+}
+
+/// Stopped servers with `wake_on_connect`, which are started when a player tries to join.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SleepingServers {
+    hostname_to_server_dir: HashMap<String, PathBuf>,
+}
+
+fn sleeping_servers_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("sleeping_servers.json");
+    Ok(path)
+}
+
+async fn load_sleeping_servers() -> anyhow::Result<SleepingServers> {
+    let content = tokio::fs::read_to_string(sleeping_servers_path()?)
+        .await
+        .context("Failed to read sleeping servers file")?;
+    serde_json::from_str(&content).context("Failed to parse sleeping servers file")
+}
+
+async fn save_sleeping_servers(servers: &SleepingServers) -> anyhow::Result<()> {
+    let path = sleeping_servers_path()?;
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create data directory")?;
+    tokio::fs::write(path, serde_json::to_string(servers)?)
+        .await
+        .context("Failed to write sleeping servers file")?;
+    Ok(())
 }
 
 struct Server {
@@ -55,6 +95,8 @@ struct Stats {
 }
 
 pub async fn init() -> anyhow::Result<()> {
+    PROXY_SERVER.lock().await.sleeping_servers = load_sleeping_servers().await.unwrap_or_default();
+
     metrics::register_metrics_collector(ProxyMetricsCollector::default()).await;
     start_server().await?;
 
@@ -74,6 +116,16 @@ pub async fn register_server(server_id: Uuid, hostname: &str, port: u16) {
             stats: Default::default(),
         },
     );
+
+    if proxy_server
+        .sleeping_servers
+        .hostname_to_server_dir
+        .remove(hostname)
+        .is_some()
+        && let Err(err) = save_sleeping_servers(&proxy_server.sleeping_servers).await
+    {
+        error!("Failed to save sleeping servers: {err:?}");
+    }
 }
 
 pub async fn unregister_server(server_id: Uuid) {
@@ -83,6 +135,109 @@ pub async fn unregister_server(server_id: Uuid) {
     }
 }
 
+/// The address of the client that the proxy connected to the server from `backend_port`.
+pub async fn get_client_address(server_id: Uuid, backend_port: u16) -> Option<IpAddr> {
+    let proxy_server = PROXY_SERVER.lock().await;
+    proxy_server
+        .client_addresses
+        .get(&(server_id, backend_port))
+        .copied()
+}
+
+/// Remembers a stopped server so that it is started when a player tries to join it.
+pub async fn register_sleeping_server(hostname: &str, server_dir: &Path) {
+    let mut proxy_server = PROXY_SERVER.lock().await;
+    if proxy_server.hostname_to_server_id.contains_key(hostname) {
+        // another server has taken over the hostname
+        return;
+    }
+
+    let previous = proxy_server
+        .sleeping_servers
+        .hostname_to_server_dir
+        .insert(hostname.to_string(), server_dir.to_path_buf());
+    if previous.as_deref() != Some(server_dir)
+        && let Err(err) = save_sleeping_servers(&proxy_server.sleeping_servers).await
+    {
+        error!("Failed to save sleeping servers: {err:?}");
+    }
+}
+
+/// Registers a stopped server as sleeping if its manifest has `wake_on_connect`, so that it can be
+/// woken up before it has ever run under this daemon.
+pub async fn register_sleeping_server_from_manifest(manifest: &ServerManifest, server_dir: &Path) {
+    if let Connection::Proxy { hostname } = &manifest.connection
+        && manifest.sleep.wake_on_connect
+    {
+        register_sleeping_server(hostname, server_dir).await;
+    }
+}
+
+/// Points a sleeping server at the directory it was moved to, or forgets it if it was deleted.
+pub async fn relocate_sleeping_server(server_dir: &Path, new_server_dir: Option<&Path>) {
+    let mut proxy_server = PROXY_SERVER.lock().await;
+    let sleeping_servers = &mut proxy_server.sleeping_servers.hostname_to_server_dir;
+
+    let mut changed = false;
+    sleeping_servers.retain(|_, dir| {
+        if dir != server_dir {
+            return true;
+        }
+        changed = true;
+        match new_server_dir {
+            Some(new_server_dir) => {
+                *dir = new_server_dir.to_path_buf();
+                true
+            }
+            None => false,
+        }
+    });
+
+    if changed && let Err(err) = save_sleeping_servers(&proxy_server.sleeping_servers).await {
+        error!("Failed to save sleeping servers: {err:?}");
+    }
+}
+
+async fn unregister_sleeping_server(hostname: &str) {
+    let mut proxy_server = PROXY_SERVER.lock().await;
+    if proxy_server
+        .sleeping_servers
+        .hostname_to_server_dir
+        .remove(hostname)
+        .is_some()
+        && let Err(err) = save_sleeping_servers(&proxy_server.sleeping_servers).await
+    {
+        error!("Failed to save sleeping servers: {err:?}");
+    }
+}
+
+/// Whether the sleeping server still wants to be woken up for the hostname.
+async fn can_wake(hostname: &str, server_dir: &Path) -> bool {
+    let Ok(manifest) = ServerManifest::load(server_dir).await else {
+        return false;
+    };
+    manifest.sleep.wake_on_connect
+        && matches!(&manifest.connection, Connection::Proxy { hostname: h } if h == hostname)
+}
+
+fn spawn_wake(hostname: String, server_dir: PathBuf) {
+    tokio::spawn(async move {
+        info!(
+            "Waking up server at '{}' for hostname '{hostname}'",
+            server_dir.display()
+        );
+
+        if let Err(err) = runner::start_server(&server_dir).await {
+            error!(
+                "Failed to wake up server at '{}': {err:?}",
+                server_dir.display()
+            );
+        }
+
+        PROXY_SERVER.lock().await.waking_hostnames.remove(&hostname);
+    });
+}
+
 async fn start_server() -> anyhow::Result<()> {
     let bind_address = &get_config().proxy_server.bind_address;
 
@@ -194,7 +349,42 @@ async fn handle_client(socket: TcpStream) -> anyhow::Result<()> {
 
     let proxy_server = PROXY_SERVER.lock().await;
     let Some(&server_id) = proxy_server.hostname_to_server_id.get(server_address) else {
-        send_error_message!(get_config().messages.server_not_found.clone());
+        let waking = proxy_server.waking_hostnames.contains(server_address);
+        let server_dir = proxy_server
+            .sleeping_servers
+            .hostname_to_server_dir
+            .get(server_address)
+            .cloned();
+        // the message is sent without the lock, since clients poll repeatedly while a server wakes
+        drop(proxy_server);
+
+        if waking {
+            send_error_message!(get_config().messages.server_starting.clone());
+        }
+
+        let Some(server_dir) = server_dir else {
+            send_error_message!(get_config().messages.server_not_found.clone());
+        };
+
+        if !can_wake(server_address, &server_dir).await {
+            unregister_sleeping_server(server_address).await;
+            send_error_message!(get_config().messages.server_not_found.clone());
+        }
+
+        if intent == ProtocolState::Status {
+            send_error_message!(get_config().messages.server_sleeping.clone());
+        }
+
+        let mut proxy_server = PROXY_SERVER.lock().await;
+        if proxy_server
+            .waking_hostnames
+            .insert(server_address.to_string())
+        {
+            spawn_wake(server_address.to_string(), server_dir);
+        }
+        drop(proxy_server);
+
+        send_error_message!(get_config().messages.server_starting.clone());
     };
 
     let server = proxy_server
@@ -241,6 +431,16 @@ async fn handle_client(socket: TcpStream) -> anyhow::Result<()> {
         .await
         .context("Failed to forward handshake packet to backend server")?;
 
+    let backend_port = server_socket
+        .local_addr()
+        .context("Failed to get backend connection address")?
+        .port();
+    PROXY_SERVER
+        .lock()
+        .await
+        .client_addresses
+        .insert((server_id, backend_port), peer_addr.ip());
+
     let mut socket = raw_packet_stream.into_inner();
 
     let result: anyhow::Result<()> = async {
@@ -270,6 +470,12 @@ async fn handle_client(socket: TcpStream) -> anyhow::Result<()> {
         Ok(())
     }.await;
 
+    PROXY_SERVER
+        .lock()
+        .await
+        .client_addresses
+        .remove(&(server_id, backend_port));
+
     if result.is_ok() {
         debug!("Client {peer_addr} disconnected");
     }
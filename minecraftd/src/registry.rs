@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use minecraftd_manifest::{Connection, ServerManifest};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// The shortest ID prefix servers can be addressed by.
+const MIN_ID_PREFIX_LEN: usize = 4;
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::const_new(None);
+
+/// Every server minecraftd has seen, whether or not it is running.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Registry {
+    servers: HashMap<Uuid, RegisteredServer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisteredServer {
+    pub server_dir: PathBuf,
+}
+
+fn registry_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("servers.json");
+    Ok(path)
+}
+
+async fn load() -> anyhow::Result<Registry> {
+    let content = match tokio::fs::read_to_string(registry_path()?).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Registry::default()),
+        Err(e) => return Err(e).context("Failed to read server registry"),
+    };
+    serde_json::from_str(&content).context("Failed to parse server registry")
+}
+
+async fn save(registry: &Registry) -> anyhow::Result<()> {
+    let path = registry_path()?;
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create directories for server registry")?;
+    let content = serde_json::to_string(registry).context("Failed to serialize server registry")?;
+    tokio::fs::write(path, content)
+        .await
+        .context("Failed to write server registry")?;
+    Ok(())
+}
+
+async fn get_registry() -> anyhow::Result<MappedMutexGuard<'static, Registry>> {
+    let mut registry = REGISTRY.lock().await;
+    if registry.is_none() {
+        *registry = Some(load().await?);
+    }
+    Ok(MutexGuard::map(registry, |registry| {
+        registry.as_mut().unwrap()
+    }))
+}
+
+/// Records where the server is. A server that was moved is updated, but a copy of a registered
+/// server is refused since the ID would no longer identify a single server, and so is a server whose
+/// name is taken by another one.
+pub async fn register_server(manifest: &ServerManifest, server_dir: &Path) -> anyhow::Result<()> {
+    let id = manifest.id;
+    let mut registry = get_registry().await?;
+
+    if let Some((other_id, other)) = find_by_name(&registry, &manifest.name).await
+        && other_id != id
+    {
+        bail!(
+            "Server at '{}' has the same name '{}' as the server at '{}'. Server names must be unique.",
+            server_dir.display(),
+            manifest.name,
+            other.server_dir.display()
+        );
+    }
+
+    if let Some(registered) = registry.servers.get(&id) {
+        if registered.server_dir == server_dir {
+            return Ok(());
+        }
+        if ServerManifest::load(&registered.server_dir)
+            .await
+            .is_ok_and(|manifest| manifest.id == id)
+        {
+            bail!(
+                "Server at '{}' has the same ID as the server at '{}'. If the directory was copied, change `id` in its minecraftd.yaml.",
+                server_dir.display(),
+                registered.server_dir.display()
+            );
+        }
+        info!(
+            "Server {id} has moved from '{}' to '{}'",
+            registered.server_dir.display(),
+            server_dir.display()
+        );
+    } else {
+        info!("Registering server at '{}'", server_dir.display());
+    }
+
+    registry.servers.insert(
+        id,
+        RegisteredServer {
+            server_dir: server_dir.to_path_buf(),
+        },
+    );
+    save(&registry).await
+}
+
+pub async fn unregister_server(id: Uuid) -> anyhow::Result<()> {
+    let mut registry = get_registry().await?;
+    if registry.servers.remove(&id).is_some() {
+        save(&registry).await?;
+    }
+    Ok(())
+}
+
+pub async fn get_registered_servers() -> anyhow::Result<HashMap<Uuid, RegisteredServer>> {
+    Ok(get_registry().await?.servers.clone())
+}
+
+/// Fails if a registered server already has the name.
+pub async fn ensure_name_available(name: &str) -> anyhow::Result<()> {
+    let registry = get_registry().await?;
+    if let Some((_, other)) = find_by_name(&registry, name).await {
+        bail!(
+            "A server named '{name}' already exists at '{}'",
+            other.server_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Fails if a registered server already uses the proxy hostname.
+pub async fn ensure_hostname_available(hostname: &str) -> anyhow::Result<()> {
+    let registry = get_registry().await?;
+    if let Some((_, other)) = find_by_manifest(&registry, |manifest| {
+        matches!(&manifest.connection, Connection::Proxy { hostname: other } if other == hostname)
+    })
+    .await
+    {
+        bail!(
+            "The hostname '{hostname}' is already used by the server at '{}'",
+            other.server_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Finds a server by its name, its ID or a prefix of its ID, in that order. Returns the directory
+/// of the server.
+pub async fn resolve_server(server: &str) -> anyhow::Result<PathBuf> {
+    if server.is_empty() {
+        bail!("The server name or ID must not be empty");
+    }
+
+    let registry = get_registry().await?;
+
+    let mut named = Vec::new();
+    for (id, registered) in &registry.servers {
+        if ServerManifest::load(&registered.server_dir)
+            .await
+            .is_ok_and(|manifest| manifest.id == *id && manifest.name == server)
+        {
+            named.push((id, registered));
+        }
+    }
+    match named.len() {
+        0 => {}
+        1 => return Ok(named[0].1.server_dir.clone()),
+        // names can still collide if they are edited in the manifests
+        _ => {
+            named.sort_by_key(|(id, _)| **id);
+            bail!(
+                "More than one server is named '{server}'. Rename one of them or use its ID:\n{}",
+                named
+                    .iter()
+                    .map(|(id, registered)| format!("  {id} '{}'", registered.server_dir.display()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        }
+    }
+
+    // short prefixes would match servers by accident
+    if server.len() < MIN_ID_PREFIX_LEN {
+        bail!(
+            "No server named '{server}' is registered. An ID prefix must be at least {MIN_ID_PREFIX_LEN} characters long"
+        );
+    }
+
+    let prefix = server.to_ascii_lowercase();
+    let mut matches = registry
+        .servers
+        .iter()
+        .filter(|(id, _)| id.to_string().starts_with(&prefix))
+        .collect::<Vec<_>>();
+    match matches.len() {
+        0 => bail!("No server named '{server}' or with an ID starting with it is registered"),
+        1 => Ok(matches[0].1.server_dir.clone()),
+        _ => {
+            matches.sort_by_key(|(id, _)| **id);
+            bail!(
+                "'{server}' matches more than one server:\n{}",
+                matches
+                    .iter()
+                    .map(|(id, registered)| format!("  {id} '{}'", registered.server_dir.display()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        }
+    }
+}
+
+async fn find_by_name<'a>(
+    registry: &'a Registry,
+    name: &str,
+) -> Option<(Uuid, &'a RegisteredServer)> {
+    if name.is_empty() {
+        return None;
+    }
+    find_by_manifest(registry, |manifest| manifest.name == name).await
+}
+
+/// Servers whose manifests cannot be loaded, e.g. because they were deleted, never match.
+async fn find_by_manifest(
+    registry: &Registry,
+    predicate: impl Fn(&ServerManifest) -> bool,
+) -> Option<(Uuid, &RegisteredServer)> {
+    for (id, registered) in &registry.servers {
+        if ServerManifest::load(&registered.server_dir)
+            .await
+            .is_ok_and(|manifest| manifest.id == *id && predicate(&manifest))
+        {
+            return Some((*id, registered));
+        }
+    }
+    None
+}
@@ -0,0 +1,516 @@
+use std::{
+    collections::VecDeque,
+    io::{SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, bail};
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::mpsc,
+};
+use uuid::Uuid;
+
+use crate::config::{ConsoleLogConfig, get_config};
+
+pub(super) const CURRENT_LOG_FILE_NAME: &str = "console.log";
+const ROTATED_LOG_FILE_PREFIX: &str = "console-";
+const ROTATED_LOG_FILE_SUFFIX: &str = ".log.gz";
+/// Suffix of rotated files that have not been compressed yet.
+const PENDING_LOG_FILE_SUFFIX: &str = ".log";
+const LOG_WRITER_CHANNEL_SIZE: usize = 64;
+const MAX_LINE_LENGTH: usize = 1024;
+/// How much output may be queued for an attached client before it is disconnected.
+const MAX_CLIENT_QUEUE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Console output of a server. The most recent output is kept in memory and sent to attached
+/// clients, and everything is written to rotated log files with a timestamp at the start of each
+/// line.
+pub struct ConsoleLog {
+    state: Mutex<ConsoleState>,
+    scrollback_size: usize,
+    file_tx: Option<mpsc::Sender<Vec<u8>>>,
+}
+
+struct ConsoleState {
+    scrollback: VecDeque<u8>,
+    /// `None` once the server has exited.
+    clients: Option<Vec<ConsoleClient>>,
+}
+
+struct ConsoleClient {
+    /// `None` is sent before disconnecting a client that fell behind.
+    output_tx: mpsc::UnboundedSender<Option<Vec<u8>>>,
+    queued: Arc<AtomicUsize>,
+}
+
+/// Console output for an attached client. Every byte is delivered in order, or the client is
+/// disconnected if it falls more than `MAX_CLIENT_QUEUE_SIZE` bytes behind.
+pub struct ConsoleSubscription {
+    output_rx: mpsc::UnboundedReceiver<Option<Vec<u8>>>,
+    queued: Arc<AtomicUsize>,
+}
+
+impl ConsoleLog {
+    pub fn open(id: Uuid) -> Arc<Self> {
+        let config = &get_config().console_log;
+
+        let file_tx = if config.enabled {
+            match logs_dir(id) {
+                Ok(dir) => {
+                    let (file_tx, file_rx) = mpsc::channel(LOG_WRITER_CHANNEL_SIZE);
+                    spawn_log_writer(dir, file_rx);
+                    Some(file_tx)
+                }
+                Err(e) => {
+                    error!("Failed to get console log directory: {e:?}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Arc::new(Self {
+            state: Mutex::new(ConsoleState {
+                scrollback: VecDeque::with_capacity(config.scrollback_size),
+                clients: Some(Vec::new()),
+            }),
+            scrollback_size: config.scrollback_size,
+            file_tx,
+        })
+    }
+
+    /// Waits if the log writer falls behind, so that no output is lost.
+    pub async fn write(&self, content: &[u8]) {
+        {
+            let mut state = self.state.lock().unwrap();
+
+            state.scrollback.extend(content);
+            if state.scrollback.len() > self.scrollback_size {
+                let excess = state.scrollback.len() - self.scrollback_size;
+                state.scrollback.drain(..excess);
+            }
+
+            if let Some(clients) = &mut state.clients {
+                clients.retain(|client| {
+                    if client.queued.load(Ordering::Relaxed) + content.len() > MAX_CLIENT_QUEUE_SIZE
+                    {
+                        let _ = client.output_tx.send(None);
+                        return false;
+                    }
+                    client.queued.fetch_add(content.len(), Ordering::Relaxed);
+                    client.output_tx.send(Some(content.to_vec())).is_ok()
+                });
+            }
+        }
+
+        if let Some(file_tx) = &self.file_tx {
+            let _ = file_tx.send(content.to_vec()).await;
+        }
+    }
+
+    /// Recent console output, without timestamps.
+    pub fn scrollback(&self) -> Vec<u8> {
+        self.state
+            .lock()
+            .unwrap()
+            .scrollback
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Returns the scrollback, and the output written after it until the server exits.
+    pub fn subscribe(&self) -> (Vec<u8>, ConsoleSubscription) {
+        let mut state = self.state.lock().unwrap();
+        let (output_tx, output_rx) = mpsc::unbounded_channel();
+        let queued = Arc::new(AtomicUsize::new(0));
+        if let Some(clients) = &mut state.clients {
+            clients.push(ConsoleClient {
+                output_tx,
+                queued: queued.clone(),
+            });
+        }
+
+        (
+            state.scrollback.iter().copied().collect(),
+            ConsoleSubscription { output_rx, queued },
+        )
+    }
+
+    /// Ends the subscriptions once the server has exited.
+    pub fn close(&self) {
+        self.state.lock().unwrap().clients = None;
+    }
+
+    /// The last lines of the scrollback as plain text.
+    pub fn last_lines(&self, count: usize) -> Vec<String> {
+        let mut tail = ConsoleTail::new(count);
+        tail.push(&self.scrollback());
+        tail.lines()
+    }
+}
+
+impl ConsoleSubscription {
+    /// Returns `None` once the server has exited.
+    pub async fn recv(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        match self.output_rx.recv().await {
+            Some(Some(content)) => {
+                self.queued.fetch_sub(content.len(), Ordering::Relaxed);
+                Ok(Some(content))
+            }
+            Some(None) => {
+                bail!("Disconnected because the console output could not be sent fast enough")
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// The end of `content` containing its last `count` lines, including an unterminated last line.
+pub fn tail_lines(content: &[u8], count: usize) -> &[u8] {
+    if count == 0 {
+        return &[];
+    }
+
+    // a trailing newline ends the last line rather than starting another one
+    let mut start = content.strip_suffix(b"\n").unwrap_or(content).len();
+    for _ in 0..count {
+        match content[..start].iter().rposition(|&byte| byte == b'\n') {
+            Some(newline) => start = newline,
+            None => return content,
+        }
+    }
+    &content[start + 1..]
+}
+
+/// Keeps the last lines of the console output as plain text, e.g. to include them in alerts.
+struct ConsoleTail {
+    lines: VecDeque<String>,
+    partial_line: Vec<u8>,
+    max_lines: usize,
+}
+
+impl ConsoleTail {
+    fn new(max_lines: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(max_lines),
+            partial_line: Vec::new(),
+            max_lines,
+        }
+    }
+
+    fn push(&mut self, content: &[u8]) {
+        for &byte in content {
+            if byte == b'\n' {
+                let line = strip_ansi_escapes(&self.partial_line);
+                self.partial_line.clear();
+                if self.lines.len() == self.max_lines {
+                    self.lines.pop_front();
+                }
+                self.lines.push_back(line);
+            } else if self.partial_line.len() < MAX_LINE_LENGTH {
+                self.partial_line.push(byte);
+            }
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = self.lines.iter().cloned().collect::<Vec<_>>();
+        if !self.partial_line.is_empty() {
+            lines.push(strip_ansi_escapes(&self.partial_line));
+        }
+        lines
+    }
+}
+
+/// Removes ANSI escape sequences and carriage returns from a line of console output.
+pub(super) fn strip_ansi_escapes(line: &[u8]) -> String {
+    let mut stripped = Vec::with_capacity(line.len());
+    let mut bytes = line.iter().copied();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            0x1b => {
+                // CSI sequences end with a byte in 0x40..=0x7e, other escapes are two bytes long
+                if bytes.next() == Some(b'[') {
+                    for byte in bytes.by_ref() {
+                        if (0x40..=0x7e).contains(&byte) {
+                            break;
+                        }
+                    }
+                }
+            }
+            b'\r' => {}
+            byte => stripped.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&stripped).into_owned()
+}
+
+/// Deletes the console logs of a server that was deleted.
+pub async fn delete_logs(id: Uuid) -> anyhow::Result<()> {
+    match tokio::fs::remove_dir_all(logs_dir(id)?).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context("Failed to delete console logs")
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn logs_dir(id: Uuid) -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("logs");
+    path.push(id.to_string());
+    Ok(path)
+}
+
+struct LogFile {
+    dir: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+    at_line_start: bool,
+}
+
+fn spawn_log_writer(dir: PathBuf, mut file_rx: mpsc::Receiver<Vec<u8>>) {
+    tokio::spawn(async move {
+        let config = &get_config().console_log;
+
+        let mut log_file = match LogFile::open(dir).await {
+            Ok(log_file) => log_file,
+            Err(e) => {
+                error!("Failed to open console log: {e:?}");
+                // keep receiving so that the console is not blocked
+                while file_rx.recv().await.is_some() {}
+                return;
+            }
+        };
+
+        if let Err(e) = compress_pending_log_files(&log_file.dir).await {
+            error!("Failed to compress console logs: {e:?}");
+        }
+        if let Err(e) = prune_rotated_log_files(&log_file.dir, config).await {
+            error!("Failed to prune console logs: {e:?}");
+        }
+
+        while let Some(content) = file_rx.recv().await {
+            if let Err(e) = log_file.write(&content).await {
+                error!("Failed to write console log: {e:?}");
+                continue;
+            }
+
+            if log_file.size >= config.max_file_size
+                && let Err(e) = log_file.rotate(config).await
+            {
+                error!("Failed to rotate console log: {e:?}");
+            }
+        }
+    });
+}
+
+impl LogFile {
+    async fn open(dir: PathBuf) -> anyhow::Result<Self> {
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .context("Failed to create console log directory")?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(dir.join(CURRENT_LOG_FILE_NAME))
+            .await
+            .context("Failed to open console log file")?;
+        let size = file.metadata().await?.len();
+
+        // the file is appended to when reattaching, possibly in the middle of a line
+        let at_line_start = if size == 0 {
+            true
+        } else {
+            file.seek(SeekFrom::End(-1)).await?;
+            file.read_u8().await? == b'\n'
+        };
+
+        Ok(Self {
+            dir,
+            file,
+            size,
+            at_line_start,
+        })
+    }
+
+    async fn write(&mut self, content: &[u8]) -> anyhow::Result<()> {
+        let timestamp = format!(
+            "{} ",
+            chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+        );
+
+        let mut buf = Vec::with_capacity(content.len() + timestamp.len());
+        // a line continued after rotation gets a timestamp too
+        if self.size == 0 {
+            self.at_line_start = true;
+        }
+        for &byte in content {
+            if self.at_line_start {
+                buf.extend_from_slice(timestamp.as_bytes());
+                self.at_line_start = false;
+            }
+            buf.push(byte);
+            if byte == b'\n' {
+                self.at_line_start = true;
+            }
+        }
+
+        self.file.write_all(&buf).await?;
+        self.file.flush().await?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Compresses the current log file into a rotated one and starts a new one.
+    async fn rotate(&mut self, config: &ConsoleLogConfig) -> anyhow::Result<()> {
+        let current = self.dir.join(CURRENT_LOG_FILE_NAME);
+        // each rotation gets its own name, so that a file left uncompressed is not overwritten
+        let pending = self.dir.join(format!(
+            "{ROTATED_LOG_FILE_PREFIX}{}{PENDING_LOG_FILE_SUFFIX}",
+            chrono::Local::now().format("%Y-%m-%dT%H-%M-%S%.3f")
+        ));
+
+        debug!(
+            "Rotating console log '{}' to '{}'",
+            current.display(),
+            pending.display()
+        );
+
+        tokio::fs::rename(&current, &pending).await?;
+        self.file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current)
+            .await?;
+        self.size = 0;
+
+        // also retries the files that could not be compressed on earlier rotations
+        compress_pending_log_files(&self.dir).await?;
+
+        prune_rotated_log_files(&self.dir, config).await
+    }
+}
+
+/// Compresses rotated log files that are still uncompressed.
+async fn compress_pending_log_files(dir: &Path) -> anyhow::Result<()> {
+    let mut pending = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(ROTATED_LOG_FILE_PREFIX) && name.ends_with(PENDING_LOG_FILE_SUFFIX) {
+            pending.push(entry.path());
+        }
+    }
+
+    let mut result = Ok(());
+    for source in pending {
+        let mut destination = source.clone().into_os_string();
+        destination.push(".gz");
+        let destination = PathBuf::from(destination);
+
+        if let Err(e) = tokio::task::spawn_blocking(move || compress(&source, &destination)).await?
+        {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+fn compress(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    let result = (|| {
+        let mut input = std::fs::File::open(source)?;
+        let output = std::fs::File::create(destination)?;
+        let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.flush()
+    })();
+
+    if let Err(e) = result {
+        // a partial file would be taken for a rotated log
+        let _ = std::fs::remove_file(destination);
+        return Err(e).with_context(|| format!("Failed to compress '{}'", source.display()));
+    }
+
+    std::fs::remove_file(source)?;
+    Ok(())
+}
+
+pub(super) async fn rotated_log_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with(ROTATED_LOG_FILE_PREFIX) && name.ends_with(ROTATED_LOG_FILE_SUFFIX) {
+            files.push(entry.path());
+        }
+    }
+    // the names contain the rotation time, so this is chronological
+    files.sort();
+    Ok(files)
+}
+
+/// Deletes rotated log files beyond `max_files` or older than `max_age`.
+async fn prune_rotated_log_files(dir: &Path, config: &ConsoleLogConfig) -> anyhow::Result<()> {
+    let files = rotated_log_files(dir).await?;
+    let excess = files.len().saturating_sub(config.max_files);
+
+    for (i, file) in files.iter().enumerate() {
+        let expired = tokio::fs::metadata(file)
+            .await?
+            .modified()?
+            .elapsed()
+            .is_ok_and(|age| age > config.max_age);
+
+        if i < excess || expired {
+            debug!("Deleting old console log '{}'", file.display());
+            tokio::fs::remove_file(file).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tail_of_terminated_lines() {
+        let content = b"one\ntwo\nthree\n";
+        assert_eq!(tail_lines(content, 1), b"three\n");
+        assert_eq!(tail_lines(content, 2), b"two\nthree\n");
+        assert_eq!(tail_lines(content, 3), content);
+        assert_eq!(tail_lines(content, 10), content);
+    }
+
+    #[test]
+    fn tail_with_unterminated_last_line() {
+        let content = b"one\ntwo\nthr";
+        assert_eq!(tail_lines(content, 1), b"thr");
+        assert_eq!(tail_lines(content, 2), b"two\nthr");
+    }
+
+    #[test]
+    fn tail_of_nothing() {
+        assert_eq!(tail_lines(b"one\ntwo\n", 0), b"");
+        assert_eq!(tail_lines(b"", 5), b"");
+        assert_eq!(tail_lines(b"\n\n", 1), b"\n");
+    }
+}
@@ -0,0 +1,181 @@
+use std::{collections::HashMap, path::Path, sync::LazyLock, time::Duration};
+
+use anyhow::bail;
+use minecraft_protocol::text_component::{Object, TextComponent};
+use tokio::{sync::Mutex, task::AbortHandle, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    config::{CountdownDisplay, get_config},
+    runner::{self, execute_command, tellraw},
+};
+
+/// Pending restart countdowns by server ID.
+static COUNTDOWNS: LazyLock<Mutex<HashMap<Uuid, AbortHandle>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Starts a countdown that warns the players at the configured remaining times and restarts the
+/// server once `countdown` has elapsed. Returns as soon as the countdown has started; a countdown
+/// already running for the server is replaced.
+pub async fn restart_server_with_countdown(
+    server_dir: &Path,
+    countdown: Duration,
+) -> anyhow::Result<()> {
+    let Some(id) = runner::get_running_server_id(server_dir).await? else {
+        bail!("Server at '{}' is not running", server_dir.display());
+    };
+
+    let mut countdowns = COUNTDOWNS.lock().await;
+
+    let server_dir = server_dir.to_path_buf();
+    let handle = tokio::spawn(async move {
+        if let Err(err) = run_countdown(id, &server_dir, countdown).await {
+            error!(
+                "Failed to restart server at '{}' after countdown: {err:?}",
+                server_dir.display()
+            );
+        }
+    })
+    .abort_handle();
+
+    if let Some(previous) = countdowns.insert(id, handle) {
+        info!("Replacing the restart countdown of server {id}");
+        previous.abort();
+    }
+
+    Ok(())
+}
+
+/// Cancels the restart countdown of a server, if there is one. Returns whether one was cancelled.
+pub async fn cancel_restart_countdown(id: Uuid) -> bool {
+    let Some(handle) = COUNTDOWNS.lock().await.remove(&id) else {
+        return false;
+    };
+    handle.abort();
+    info!("Cancelled restart countdown of server {id}");
+    true
+}
+
+async fn run_countdown(id: Uuid, server_dir: &Path, countdown: Duration) -> anyhow::Result<()> {
+    let deadline = Instant::now() + countdown;
+
+    let mut warnings = get_config()
+        .restart_countdown
+        .warnings
+        .iter()
+        .copied()
+        .filter(|warning| *warning < countdown && !warning.is_zero())
+        .collect::<Vec<_>>();
+    warnings.sort_unstable_by(|a, b| b.cmp(a));
+    warnings.dedup();
+
+    let result = async {
+        if !countdown.is_zero() {
+            announce(id, countdown).await;
+        }
+
+        for remaining in warnings {
+            tokio::time::sleep_until(deadline - remaining).await;
+
+            if runner::get_running_server_id(server_dir).await? != Some(id) {
+                bail!("Server stopped during the restart countdown");
+            }
+
+            announce(id, remaining).await;
+        }
+
+        tokio::time::sleep_until(deadline).await;
+        Ok(())
+    }
+    .await;
+
+    // deregister before restarting, as restarting cancels the countdown of the server
+    {
+        let mut countdowns = COUNTDOWNS.lock().await;
+        if countdowns
+            .get(&id)
+            .is_some_and(|handle| handle.id() == tokio::task::id())
+        {
+            countdowns.remove(&id);
+        }
+    }
+
+    result?;
+    runner::restart_server(server_dir).await
+}
+
+async fn announce(id: Uuid, remaining: Duration) {
+    let message = replace_remaining(
+        &get_config().messages.server_restart_countdown,
+        &format_remaining(remaining),
+    );
+
+    for display in &get_config().restart_countdown.display {
+        let result = match display {
+            CountdownDisplay::Chat => tellraw(id, "@a", message.clone()).await,
+            CountdownDisplay::Title | CountdownDisplay::Actionbar => {
+                let kind = match display {
+                    CountdownDisplay::Title => "title",
+                    _ => "actionbar",
+                };
+                match serde_json::to_string(&message) {
+                    Ok(json) => execute_command(id, &format!("title @a {kind} {json}"))
+                        .await
+                        .map(drop),
+                    Err(err) => Err(err.into()),
+                }
+            }
+        };
+
+        if let Err(err) = result {
+            warn!("Failed to send restart countdown message to server {id}: {err:?}");
+        }
+    }
+}
+
+fn replace_remaining(component: &TextComponent, remaining: &str) -> TextComponent {
+    match component {
+        TextComponent::String(text) => {
+            TextComponent::String(text.replace("{remaining}", remaining))
+        }
+        TextComponent::Object(object) => TextComponent::Object(Object {
+            text: object
+                .text
+                .as_ref()
+                .map(|text| text.replace("{remaining}", remaining)),
+            extra: object.extra.as_ref().map(|extra| {
+                extra
+                    .iter()
+                    .map(|component| replace_remaining(component, remaining))
+                    .collect()
+            }),
+            ..object.clone()
+        }),
+    }
+}
+
+/// Formats the remaining time for players, e.g. "1 minute 30 seconds".
+fn format_remaining(remaining: Duration) -> String {
+    // round up so that "10 seconds" is not shown as "9 seconds" due to timer jitter
+    let total_secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+    let units = [(3600, "hour"), (60, "minute"), (1, "second")];
+    let mut rest = total_secs;
+    let mut parts = Vec::new();
+    for (unit_secs, name) in units {
+        let count = rest / unit_secs;
+        rest %= unit_secs;
+        if count > 0 {
+            parts.push(format!(
+                "{count} {name}{}",
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+    }
+
+    if parts.is_empty() {
+        "0 seconds".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
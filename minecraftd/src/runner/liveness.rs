@@ -0,0 +1,196 @@
+use std::{net::Ipv4Addr, sync::Arc, time::Duration};
+
+use anyhow::bail;
+use minecraftd_manifest::{LivenessProbe, LivenessProbeMethod};
+use tokio::{sync::Mutex, time::MissedTickBehavior};
+use uuid::Uuid;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    bridge::Bridge,
+    runner::{RUNNER, ServerStatus, do_force_restart_server, rcon_session::RconSession},
+    util::server_list_ping::server_list_ping,
+};
+
+/// How long to wait for the JVM to print the thread dump before the server is killed.
+const THREAD_DUMP_WAIT_SECS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerHealth {
+    /// The server is not ready yet, or has not been probed since becoming ready.
+    Unknown,
+    Healthy {
+        /// Consecutive failed probes, still below the failure threshold.
+        failed_probes: u32,
+    },
+    Unhealthy {
+        failed_probes: u32,
+    },
+}
+
+/// What is needed to probe a server, copied out so that the runner is not locked while probing.
+struct ProbeTarget {
+    server_port: u16,
+    rcon: Arc<RconSession>,
+    bridge: Option<Arc<Mutex<Bridge>>>,
+}
+
+/// Probes a ready server periodically until it stops or is replaced by a restarted process.
+pub(super) fn spawn_liveness_probe(id: Uuid, pid: u32, probe: LivenessProbe) {
+    if !probe.enabled {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(probe.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // the first tick completes immediately, and the server has just been seen responding
+        interval.tick().await;
+
+        let mut failed_probes = 0;
+
+        loop {
+            interval.tick().await;
+
+            let Some(target) = get_probe_target(id, pid).await else {
+                debug!("Server {id} is no longer ready, stopping liveness probe");
+                return;
+            };
+
+            let result = match tokio::time::timeout(probe.timeout, target.probe(probe.method)).await
+            {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("Probe timed out")),
+            };
+
+            let previous_failed_probes = failed_probes;
+            match &result {
+                Ok(()) => failed_probes = 0,
+                Err(e) => {
+                    failed_probes += 1;
+                    debug!("Liveness probe of server {id} failed ({failed_probes}): {e:?}");
+                }
+            }
+
+            let server_dir = {
+                let runner = RUNNER.lock().await;
+                // the server may have been stopped while probing
+                let Some(server) = runner
+                    .running_servers
+                    .get(&id)
+                    .filter(|server| server.pid == pid)
+                else {
+                    return;
+                };
+                server
+                    .health
+                    .set(if failed_probes >= probe.failure_threshold {
+                        ServerHealth::Unhealthy { failed_probes }
+                    } else {
+                        ServerHealth::Healthy { failed_probes }
+                    });
+                server.server_dir.clone()
+            };
+
+            if failed_probes == 0 && previous_failed_probes >= probe.failure_threshold {
+                info!("Server at '{}' is healthy again", server_dir.display());
+                send_alert("server_recovered", || Alert {
+                    severity: Severity::Info,
+                    title: "Server recovered".to_string(),
+                    message: format!(
+                        "Server at `{}` is responding to liveness probes again",
+                        server_dir.display()
+                    ),
+                })
+                .await;
+            }
+
+            // act only once when the threshold is reached, not on every failure after that
+            let Err(error) = result else {
+                continue;
+            };
+            if failed_probes != probe.failure_threshold {
+                continue;
+            }
+
+            warn!(
+                "Server at '{}' failed {failed_probes} consecutive liveness probes, last error: {:?}",
+                server_dir.display(),
+                error
+            );
+            send_alert("server_unhealthy", || Alert {
+                severity: Severity::Error,
+                title: "Server is unresponsive".to_string(),
+                message: format!(
+                    "Server at `{}` failed {failed_probes} consecutive liveness probes ({error}){}",
+                    server_dir.display(),
+                    if probe.restart {
+                        " and will be restarted"
+                    } else {
+                        ""
+                    }
+                ),
+            })
+            .await;
+
+            if probe.dump_threads {
+                info!("Requesting thread dump of server {id}");
+                if let Err(e) = nix::sys::signal::kill(
+                    nix::unistd::Pid::from_raw(pid as i32),
+                    nix::sys::signal::Signal::SIGQUIT,
+                ) {
+                    error!("Failed to send SIGQUIT to server {id}: {e:?}");
+                }
+            }
+
+            if probe.restart {
+                if probe.dump_threads {
+                    tokio::time::sleep(Duration::from_secs(THREAD_DUMP_WAIT_SECS)).await;
+                }
+
+                info!(
+                    "Restarting unresponsive server at '{}'",
+                    server_dir.display()
+                );
+                if let Err(e) = do_force_restart_server(id).await {
+                    error!("Failed to restart server {id}: {e:?}");
+                }
+                return;
+            }
+        }
+    });
+}
+
+async fn get_probe_target(id: Uuid, pid: u32) -> Option<ProbeTarget> {
+    let runner = RUNNER.lock().await;
+    let server = runner.running_servers.get(&id)?;
+    if server.pid != pid || server.status.get() != ServerStatus::Ready {
+        return None;
+    }
+
+    Some(ProbeTarget {
+        server_port: server.server_port.port(),
+        rcon: server.rcon.clone(),
+        bridge: server.bridge.get().cloned(),
+    })
+}
+
+impl ProbeTarget {
+    async fn probe(&self, method: LivenessProbeMethod) -> anyhow::Result<()> {
+        match method {
+            LivenessProbeMethod::Slp => {
+                server_list_ping((Ipv4Addr::LOCALHOST, self.server_port)).await?;
+            }
+            LivenessProbeMethod::Rcon => {
+                self.rcon.execute_command("list").await?;
+            }
+            LivenessProbeMethod::Bridge => {
+                let Some(bridge) = &self.bridge else {
+                    bail!("Bridge is not connected");
+                };
+                bridge.lock().await.get_server_metrics().await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::{collections::VecDeque, io::Read, os::unix::fs::MetadataExt, path::Path, time::Duration};
+
+use anyhow::bail;
+use mcctl_protocol::LogLine;
+use regex::Regex;
+use tokio::{fs::File, io::AsyncReadExt, sync::mpsc};
+use uuid::Uuid;
+
+use crate::{
+    config::get_config,
+    runner::console_log::{CURRENT_LOG_FILE_NAME, logs_dir, rotated_log_files, strip_ansi_escapes},
+};
+
+const LOG_READER_CHANNEL_SIZE: usize = 256;
+const FOLLOW_POLL_INTERVAL_MILLIS: u64 = 250;
+
+pub struct LogQuery {
+    /// Only lines logged at or after this time, in milliseconds since the Unix epoch.
+    pub since_timestamp_millis: Option<i64>,
+    /// Only the last this many lines of the history.
+    pub lines: Option<usize>,
+    pub grep: Option<Regex>,
+    pub strip_ansi: bool,
+    pub follow: bool,
+}
+
+/// Reads the console log files of a server. Lines are sent until the history has been read, or
+/// until the receiver is dropped when following.
+pub async fn read_logs(id: Uuid, query: LogQuery) -> anyhow::Result<mpsc::Receiver<LogLine>> {
+    if !get_config().console_log.enabled {
+        bail!("Console logging is disabled in the minecraftd configuration");
+    }
+
+    let dir = logs_dir(id)?;
+    let (log_tx, log_rx) = mpsc::channel(LOG_READER_CHANNEL_SIZE);
+
+    tokio::spawn(async move {
+        let client = log_tx.clone();
+        if let Err(e) = LogStream::new(query, log_tx).run(&dir).await
+            && !client.is_closed()
+        {
+            error!("Failed to read console logs of server {id}: {e:?}");
+        }
+    });
+
+    Ok(log_rx)
+}
+
+struct LogStream {
+    query: LogQuery,
+    log_tx: mpsc::Sender<LogLine>,
+    /// Lines held back while reading the history if only the last lines are requested.
+    tail: Option<VecDeque<LogLine>>,
+    partial_line: Vec<u8>,
+    last_timestamp_millis: i64,
+}
+
+impl LogStream {
+    fn new(query: LogQuery, log_tx: mpsc::Sender<LogLine>) -> Self {
+        Self {
+            tail: query.lines.map(|_| VecDeque::new()),
+            query,
+            log_tx,
+            partial_line: Vec::new(),
+            last_timestamp_millis: 0,
+        }
+    }
+
+    async fn run(mut self, dir: &Path) -> anyhow::Result<()> {
+        for path in rotated_log_files(dir).await? {
+            // a rotated file was last written to when it was rotated
+            if let Some(since) = self.query.since_timestamp_millis
+                && let Ok(modified) = tokio::fs::metadata(&path).await?.modified()
+                && chrono::DateTime::<chrono::Utc>::from(modified).timestamp_millis() < since
+            {
+                continue;
+            }
+
+            let content = tokio::task::spawn_blocking(move || {
+                let mut content = Vec::new();
+                flate2::read::GzDecoder::new(std::fs::File::open(&path)?)
+                    .read_to_end(&mut content)?;
+                std::io::Result::Ok(content)
+            })
+            .await??;
+
+            self.push(&content).await?;
+            // a line cut off by the rotation continues in the next file with its own timestamp
+            self.flush_partial_line().await?;
+        }
+
+        let current_path = dir.join(CURRENT_LOG_FILE_NAME);
+        let mut current = match File::open(&current_path).await {
+            Ok(file) => Some(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(file) = &mut current {
+            self.read_to_end(file).await?;
+        }
+
+        if let Some(tail) = self.tail.take() {
+            for line in tail {
+                self.send(line).await?;
+            }
+        }
+
+        if !self.query.follow {
+            return self.flush_partial_line().await;
+        }
+
+        loop {
+            tokio::time::sleep(Duration::from_millis(FOLLOW_POLL_INTERVAL_MILLIS)).await;
+            if self.log_tx.is_closed() {
+                return Ok(());
+            }
+
+            if let Some(file) = &mut current {
+                self.read_to_end(file).await?;
+            }
+
+            // the log file is replaced when it is rotated, and created when the server first starts
+            let replaced = match (&current, tokio::fs::metadata(&current_path).await) {
+                (Some(file), Ok(metadata)) => file.metadata().await?.ino() != metadata.ino(),
+                (None, Ok(_)) => true,
+                (_, Err(_)) => false,
+            };
+            if replaced {
+                if let Some(file) = &mut current {
+                    self.read_to_end(file).await?;
+                }
+                current = Some(File::open(&current_path).await?);
+            }
+        }
+    }
+
+    async fn read_to_end(&mut self, file: &mut File) -> anyhow::Result<()> {
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).await?;
+        self.push(&content).await
+    }
+
+    /// Sends the complete lines in `content`. The rest is kept until the line is completed.
+    async fn push(&mut self, content: &[u8]) -> anyhow::Result<()> {
+        self.partial_line.extend_from_slice(content);
+
+        let Some(end) = self.partial_line.iter().rposition(|&byte| byte == b'\n') else {
+            return Ok(());
+        };
+        let rest = self.partial_line.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial_line, rest);
+
+        for line in complete.split(|&byte| byte == b'\n') {
+            if !line.is_empty() {
+                self.emit(line).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush_partial_line(&mut self) -> anyhow::Result<()> {
+        let line = std::mem::take(&mut self.partial_line);
+        if line.is_empty() {
+            return Ok(());
+        }
+        self.emit(&line).await
+    }
+
+    async fn emit(&mut self, raw_line: &[u8]) -> anyhow::Result<()> {
+        let Some(line) = self.parse_line(raw_line) else {
+            return Ok(());
+        };
+
+        match (&mut self.tail, self.query.lines) {
+            (Some(tail), Some(max_lines)) => {
+                if max_lines > 0 {
+                    if tail.len() == max_lines {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
+                Ok(())
+            }
+            _ => self.send(line).await,
+        }
+    }
+
+    async fn send(&self, line: LogLine) -> anyhow::Result<()> {
+        self.log_tx
+            .send(line)
+            .await
+            .map_err(|_| anyhow::anyhow!("Log client disconnected"))
+    }
+
+    /// Splits off the timestamp and applies the filters of the query.
+    fn parse_line(&mut self, raw_line: &[u8]) -> Option<LogLine> {
+        let timestamp = raw_line
+            .iter()
+            .position(|&byte| byte == b' ')
+            .and_then(|space| {
+                let timestamp = std::str::from_utf8(&raw_line[..space]).ok()?;
+                let timestamp = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+                Some((timestamp.timestamp_millis(), &raw_line[space + 1..]))
+            });
+        let content = match timestamp {
+            Some((timestamp_millis, content)) => {
+                self.last_timestamp_millis = timestamp_millis;
+                content
+            }
+            // not written by us, e.g. edited by hand
+            None => raw_line,
+        };
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if self
+            .query
+            .since_timestamp_millis
+            .is_some_and(|since| self.last_timestamp_millis < since)
+        {
+            return None;
+        }
+
+        let stripped = strip_ansi_escapes(content);
+        if let Some(grep) = &self.query.grep
+            && !grep.is_match(&stripped)
+        {
+            return None;
+        }
+
+        Some(LogLine {
+            timestamp_millis: self.last_timestamp_millis,
+            content: if self.query.strip_ansi {
+                stripped.into_bytes()
+            } else {
+                content.to_vec()
+            },
+        })
+    }
+}
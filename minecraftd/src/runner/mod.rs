@@ -1,18 +1,20 @@
 use std::{
+    collections::HashMap,
     ffi::{OsStr, OsString},
     net::Ipv4Addr,
     os::unix::process::ExitStatusExt,
     path::{Path, PathBuf},
+    process::ExitStatus,
     sync::{Arc, LazyLock, OnceLock},
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{Context, bail};
+use duration_str::HumanFormat;
 use minecraft_protocol::text_component::TextComponent;
 use minecraftd_manifest::{Connection, ExtensionType, ServerManifest};
-use pty_process::Pty;
 use rand::distr::{Alphanumeric, SampleString};
-use tokio::{process::Child, sync::Mutex, task::JoinSet, time::timeout};
+use tokio::{sync::Mutex, task::JoinSet, time::timeout};
 use uuid::Uuid;
 
 use crate::{
@@ -22,39 +24,61 @@ use crate::{
     config::get_config,
     extension::{self, cache::get_extension_symlink_info, providers::get_extension_provider},
     java_runtime::JavaRuntimeExt,
+    player_lists,
     port_pool::Port,
-    proxy_server,
+    proxy_server, registry,
     runner::{
+        console_log::ConsoleLog,
+        liveness::spawn_liveness_probe,
         metrics::init_metrics,
+        player_tracker::spawn_player_tracker,
+        rcon_session::RconSession,
         running_servers::RunningServers,
-        terminal::{TerminalInput, TerminalOutput, spawn_terminal_reader, spawn_terminal_writer},
+        terminal::{TerminalInput, spawn_terminal_reader, spawn_terminal_writer},
     },
     server_implementations::{ServerImplementation, get_server_implementation},
+    supervisor::{self, Supervisor},
+    update_rollback,
     util::{
         observable_value::ObservableValue, os_str_ext::OsStrExt,
         server_list_ping::server_list_ping, server_properties::ServerProperties,
     },
 };
 
+pub use console_log::delete_logs;
+pub use countdown::{cancel_restart_countdown, restart_server_with_countdown};
+pub use liveness::ServerHealth;
+pub use log_reader::{LogQuery, read_logs};
+pub use restart_policy::{CrashLoopState, get_crash_looping_servers, reset_by_server_dir};
 pub use terminal::{TerminalReader, TerminalWriter};
 
+mod console_log;
+mod countdown;
+mod liveness;
+mod log_reader;
 mod metrics;
+mod player_tracker;
+mod rcon_session;
+mod reattach;
+mod restart_policy;
 mod running_servers;
 mod terminal;
 
 const MINECRAFT_DEFAULT_PORT: u16 = 25565;
-const PTY_DEFAULT_ROWS: u16 = 24;
-const PTY_DEFAULT_COLS: u16 = 80;
 const REQUEST_STOP_RETRY_LIMIT: usize = 5;
 const REQUEST_STOP_RETRY_INTERVAL_SECS: u64 = 10;
 const WAIT_FOR_PLAYER_LOGOUT_INTERVAL_SECS: u64 = 60;
 const BRIDGE_CONNECT_RETRY_INTERVAL_SECS: u64 = 5;
 const BRIDGE_CONNECT_MAX_RETRIES: u32 = 10;
+const ALERT_CONSOLE_LINES: usize = 15;
+const ALERT_CONSOLE_LINE_LENGTH: usize = 200;
 
 static RUNNER: LazyLock<Mutex<Runner>> = LazyLock::new(|| Mutex::new(Runner::new()));
 
 struct Runner {
     running_servers: RunningServers,
+    /// Why servers were killed before becoming ready, reported to `wait_ready` callers.
+    start_failures: HashMap<Uuid, String>,
 }
 
 struct RunningServer {
@@ -62,13 +86,15 @@ struct RunningServer {
     status: ObservableValue<ServerStatus>,
     manifest: Arc<ServerManifest>,
     terminal_in: tokio::sync::mpsc::Sender<TerminalInput>,
-    terminal_out: tokio::sync::broadcast::Sender<TerminalOutput>,
     server_port: ServerPort,
-    rcon_port: Port,
-    rcon_password: String,
+    /// Released when the server is removed.
+    _rcon_port: Port,
+    rcon: Arc<RconSession>,
     pid: u32,
     running_since: Instant,
-    bridge: OnceLock<Mutex<Bridge>>, // bridge is connected after the server is ready
+    bridge: OnceLock<Arc<Mutex<Bridge>>>, // bridge is connected after the server is ready
+    health: ObservableValue<ServerHealth>,
+    console_log: Arc<ConsoleLog>,
 }
 
 pub struct RunningServerInfo {
@@ -78,6 +104,7 @@ pub struct RunningServerInfo {
     pub server_port: u16,
     pub players: Option<PlayersInfo>,
     pub uptime: Duration,
+    pub health: ServerHealth,
 }
 
 pub struct PlayersInfo {
@@ -116,16 +143,41 @@ impl Runner {
     fn new() -> Self {
         Self {
             running_servers: RunningServers::new(),
+            start_failures: HashMap::new(),
         }
     }
 }
 
 pub async fn init() -> anyhow::Result<()> {
     init_metrics().await?;
+    reattach::reattach_servers().await;
+    register_sleeping_servers().await;
     start_auto_start_servers().await;
     Ok(())
 }
 
+/// Registers the stopped servers with `wake_on_connect`, including ones that were created or
+/// configured while the daemon was not running.
+async fn register_sleeping_servers() {
+    let servers = match registry::get_registered_servers().await {
+        Ok(servers) => servers,
+        Err(err) => {
+            error!("Failed to get registered servers: {err:?}");
+            return;
+        }
+    };
+
+    for (id, server) in servers {
+        if RUNNER.lock().await.running_servers.get(&id).is_some() {
+            continue;
+        }
+        let Ok(manifest) = ServerManifest::load(&server.server_dir).await else {
+            continue;
+        };
+        proxy_server::register_sleeping_server_from_manifest(&manifest, &server.server_dir).await;
+    }
+}
+
 /// Never returns ServerStatus::Stopped
 pub async fn get_server_status(id: Uuid) -> Option<ServerStatus> {
     let runner = RUNNER.lock().await;
@@ -148,13 +200,21 @@ pub async fn is_server_running(server_dir: &Path) -> anyhow::Result<bool> {
         .is_some())
 }
 
+pub async fn get_running_server_id(server_dir: &Path) -> anyhow::Result<Option<Uuid>> {
+    let runner = RUNNER.lock().await;
+    runner.running_servers.get_id_by_server_dir(server_dir)
+}
+
 pub async fn get_running_servers() -> Vec<RunningServerInfo> {
     let runner = RUNNER.lock().await;
     let mut servers = Vec::new();
 
     for server in runner.running_servers.iter() {
         let players = match server.status.get() {
-            ServerStatus::Ready => {
+            // an unresponsive server would not answer the ping either
+            ServerStatus::Ready
+                if !matches!(server.health.get(), ServerHealth::Unhealthy { .. }) =>
+            {
                 server_list_ping((Ipv4Addr::LOCALHOST, server.server_port.port()))
                     .await
                     .ok()
@@ -179,6 +239,7 @@ pub async fn get_running_servers() -> Vec<RunningServerInfo> {
             server_port: server.server_port.port(),
             players,
             uptime: server.running_since.elapsed(),
+            health: server.health.get(),
         });
     }
 
@@ -195,14 +256,24 @@ pub async fn get_running_server_ids() -> Vec<Uuid> {
 }
 
 pub async fn start_server(server_dir: &Path) -> anyhow::Result<()> {
+    restart_policy::reset_by_server_dir(&server_dir.canonicalize()?).await;
     do_start_server(server_dir, false, false).await
 }
 
 pub async fn stop_server(server_dir: &Path) -> anyhow::Result<()> {
+    let cancelled_restart = restart_policy::reset_by_server_dir(&server_dir.canonicalize()?).await;
+
     let id;
     {
         let runner = RUNNER.lock().await;
         let Some(server) = runner.running_servers.get_by_server_dir(server_dir)? else {
+            if cancelled_restart {
+                info!(
+                    "Cancelled pending restart of server at '{}'",
+                    server_dir.display()
+                );
+                return Ok(());
+            }
             bail!("Server at '{}' is not running", server_dir.display());
         };
         id = server.manifest.id;
@@ -216,9 +287,37 @@ pub async fn stop_server(server_dir: &Path) -> anyhow::Result<()> {
         }
     }
 
+    cancel_restart_countdown(id).await;
+
     do_stop_server(id, false).await
 }
 
+/// Stops a server that has had no players for a while. Unlike `stop_server`, the server is kept in
+/// the auto-start list.
+pub async fn stop_idle_server(id: Uuid) -> anyhow::Result<()> {
+    do_stop_server(id, false).await
+}
+
+pub async fn get_online_players(id: Uuid) -> Option<u32> {
+    let port = {
+        let runner = RUNNER.lock().await;
+        let server = runner.running_servers.get(&id)?;
+        if server.status.get() != ServerStatus::Ready {
+            return None;
+        }
+        server.server_port.port()
+    };
+
+    let ping = timeout(
+        Duration::from_secs(10),
+        server_list_ping((Ipv4Addr::LOCALHOST, port)),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    ping.players.map(|players| players.online as u32)
+}
+
 pub async fn kill_server(server_dir: &Path) -> anyhow::Result<()> {
     let id;
     {
@@ -232,19 +331,22 @@ pub async fn kill_server(server_dir: &Path) -> anyhow::Result<()> {
     do_kill_server(id).await
 }
 
+/// Attaches to the console, replaying the last `scrollback_lines` lines of output first.
 pub async fn attach_terminal(
     server_dir: &Path,
+    scrollback_lines: usize,
+    read_only: bool,
 ) -> anyhow::Result<(TerminalReader, TerminalWriter)> {
     let runner = RUNNER.lock().await;
     let Some(server) = runner.running_servers.get_by_server_dir(server_dir)? else {
         bail!("Server at '{}' is not running", server_dir.display());
     };
 
-    let terminal_in = server.terminal_in.clone();
-    let terminal_out = server.terminal_out.subscribe();
+    let (scrollback, console) = server.console_log.subscribe();
+    let replay = console_log::tail_lines(&scrollback, scrollback_lines).to_vec();
     Ok((
-        TerminalReader::new(terminal_out),
-        TerminalWriter::new(terminal_in),
+        TerminalReader::new(replay, console),
+        TerminalWriter::new(server.terminal_in.clone(), read_only),
     ))
 }
 
@@ -258,7 +360,13 @@ pub async fn wait_ready(server_dir: &Path) -> anyhow::Result<()> {
         id = server.manifest.id;
     }
 
-    wait_for_server_status(id, ServerStatus::Ready).await
+    let result = wait_for_server_status(id, ServerStatus::Ready).await;
+    if result.is_err()
+        && let Some(reason) = RUNNER.lock().await.start_failures.remove(&id)
+    {
+        bail!(reason);
+    }
+    result
 }
 
 pub async fn restart_server(server_dir: &Path) -> anyhow::Result<()> {
@@ -270,10 +378,17 @@ pub async fn restart_server(server_dir: &Path) -> anyhow::Result<()> {
         server.manifest.id
     };
 
+    cancel_restart_countdown(id).await;
+
     do_stop_server(id, true).await
 }
 
 pub async fn shutdown() {
+    if get_config().runner.detach_on_shutdown {
+        info!("Leaving servers running under their supervisors");
+        return;
+    }
+
     let mut join_set = JoinSet::new();
     {
         let runner = RUNNER.lock().await;
@@ -325,32 +440,32 @@ pub async fn get_server_manifest(id: Uuid) -> Option<Arc<ServerManifest>> {
 }
 
 pub async fn tellraw(id: Uuid, target: &str, message: TextComponent) -> anyhow::Result<()> {
-    let runner = RUNNER.lock().await;
+    execute_command(
+        id,
+        &format!("tellraw {} {}", target, serde_json::to_string(&message)?),
+    )
+    .await?;
+    Ok(())
+}
 
-    let server = runner
-        .running_servers
-        .get(&id)
-        .context("Server is not running")?;
+/// Runs a command over RCON and returns its response text.
+pub async fn execute_command(id: Uuid, command: &str) -> anyhow::Result<String> {
+    let rcon = {
+        let runner = RUNNER.lock().await;
 
-    if server.status.get() != ServerStatus::Ready {
-        bail!("Server is not in ready state");
-    }
+        let server = runner
+            .running_servers
+            .get(&id)
+            .context("Server is not running")?;
 
-    let mut rcon_client = minecraft_rcon::Client::connect(
-        (Ipv4Addr::LOCALHOST, server.rcon_port.port()),
-        &server.rcon_password,
-    )
-    .await?;
+        if server.status.get() != ServerStatus::Ready {
+            bail!("Server is not in ready state");
+        }
 
-    rcon_client
-        .execute_command(&format!(
-            "tellraw {} {}",
-            target,
-            serde_json::to_string(&message)?
-        ))
-        .await?;
+        server.rcon.clone()
+    };
 
-    Ok(())
+    rcon.execute_command(command).await
 }
 
 async fn do_start_server(
@@ -358,9 +473,32 @@ async fn do_start_server(
     restarting: bool,
     auto_starting: bool,
 ) -> anyhow::Result<()> {
+    let server_dir = server_dir.canonicalize()?;
+
+    // updating happens before taking the runner lock since it may download files and back up
+    // the world, which must not wait for the backup lock while holding the runner lock
+    if !is_server_running(&server_dir).await? {
+        let mut manifest = ServerManifest::load(&server_dir).await?;
+        if manifest.auto_update {
+            let server_implementation = get_server_implementation(&manifest.server_implementation)
+                .with_context(|| {
+                    format!(
+                        "Unknown server implementation '{}'",
+                        manifest.server_implementation
+                    )
+                })?;
+
+            update_server_and_extensions_if_newer_version_is_available(
+                &server_dir,
+                server_implementation,
+                &mut manifest,
+            )
+            .await?;
+        }
+    }
+
     let mut runner = RUNNER.lock().await;
 
-    let server_dir = server_dir.canonicalize()?;
     if runner
         .running_servers
         .get_id_by_server_dir(&server_dir)?
@@ -371,9 +509,15 @@ async fn do_start_server(
 
     info!("Starting server at '{}'", server_dir.display());
 
-    let mut manifest = ServerManifest::load(&server_dir).await?;
+    let manifest = ServerManifest::load(&server_dir).await?;
     debug!("Loaded server manifest: {:?}", manifest);
 
+    // name conflicts are refused when servers are created or registered, but a name edited in the
+    // manifest afterwards must not keep the server from starting
+    if let Err(e) = registry::register_server(&manifest, &server_dir).await {
+        error!("Failed to register server {}: {e:?}", manifest.id);
+    }
+
     let server_implementation = get_server_implementation(&manifest.server_implementation)
         .with_context(|| {
             format!(
@@ -382,15 +526,6 @@ async fn do_start_server(
             )
         })?;
 
-    if manifest.auto_update {
-        update_server_and_extensions_if_newer_version_is_available(
-            &server_dir,
-            server_implementation,
-            &mut manifest,
-        )
-        .await?;
-    }
-
     if let Connection::Proxy { hostname } = &manifest.connection
         && runner
             .running_servers
@@ -426,6 +561,8 @@ async fn do_start_server(
 
     prepare_extensions(&server_dir, &manifest).await?;
 
+    player_lists::group::sync_to_server(manifest.id, &server_dir, &manifest).await?;
+
     manifest.java_runtime.prepare().await?;
 
     let java_path = manifest.java_runtime.java_path();
@@ -437,35 +574,66 @@ async fn do_start_server(
 
     let command_args_str =
         command_substitute_placeholders(&manifest.command, &java_path, &server_jar_path);
-    let (pty, child) = start_command_with_pty(&command_args_str, &server_dir)?;
-    let pid = child.id().context("Failed to get child process ID")?;
-
-    let (pty_reader, pty_writer) = pty.into_split();
-    let (term_in_tx, term_in_rx) = tokio::sync::mpsc::channel::<TerminalInput>(1);
-    let (term_out_tx, _) = tokio::sync::broadcast::channel::<TerminalOutput>(16);
-    spawn_terminal_writer(pty_writer, term_in_rx);
-    spawn_terminal_reader(pty_reader, term_out_tx.clone());
+    let supervisor = supervisor::spawn(manifest.id, &server_dir, &command_args_str).await?;
+    let pid = supervisor.pid;
+
+    if let Err(e) = reattach::save_state(
+        manifest.id,
+        &reattach::ServerState {
+            server_dir: server_dir.clone(),
+            server_port: server_port.port(),
+            proxy: matches!(server_port, ServerPort::Proxy(_)),
+            rcon_port: rcon_port.port(),
+            rcon_password: rcon_password.clone(),
+            started_at: SystemTime::now(),
+        },
+    )
+    .await
+    {
+        // nothing would be attached to the server, so it must not keep running unnoticed
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(pid as i32),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+        if let Err(e) = supervisor::remove_state_dir(manifest.id).await {
+            error!("{e:?}");
+        }
+        return Err(e);
+    }
 
-    spawn_process_watcher(manifest.id, child);
+    let (term_in_tx, console_log) = attach_supervisor(manifest.id, supervisor, false);
 
-    spawn_readiness_checker(manifest.id, server_port.port());
+    spawn_readiness_checker(
+        manifest.id,
+        server_port.port(),
+        manifest
+            .start_timeout
+            .unwrap_or(get_config().runner.start_timeout),
+        true,
+    );
 
     if let Connection::Proxy { hostname } = &manifest.connection {
         proxy_server::register_server(manifest.id, hostname, server_port.port()).await;
     }
 
+    if update_rollback::is_update_pending(manifest.id).await {
+        spawn_update_trial_watcher(manifest.id, pid);
+    }
+
+    runner.start_failures.remove(&manifest.id);
     runner.running_servers.insert(RunningServer {
         server_dir,
         status: ObservableValue::new(ServerStatus::Starting { restarting }),
         manifest: Arc::new(manifest),
         terminal_in: term_in_tx,
-        terminal_out: term_out_tx,
         server_port,
-        rcon_port,
-        rcon_password,
+        rcon: Arc::new(RconSession::new(rcon_port.port(), rcon_password)),
+        _rcon_port: rcon_port,
         pid,
         running_since: Instant::now(),
         bridge: OnceLock::new(),
+        health: ObservableValue::new(ServerHealth::Unknown),
+        console_log,
     });
 
     Ok(())
@@ -476,11 +644,13 @@ async fn update_server_and_extensions_if_newer_version_is_available(
     server_implementation: &dyn ServerImplementation,
     manifest: &mut ServerManifest,
 ) -> anyhow::Result<()> {
+    let previous_manifest = manifest.clone();
     let mut updated = false;
 
     if let Some((version, build)) = server_implementation
         .is_newer_version_available(&manifest.version, &manifest.build, false)
         .await?
+        && !update_rollback::is_failed_server_version(manifest.id, &version.name, &build.name).await
     {
         info!(
             "New version '{}' build '{}' is available for server implementation '{}'. Updating manifest.",
@@ -505,6 +675,13 @@ async fn update_server_and_extensions_if_newer_version_is_available(
             )
             .await
             .unwrap_or(None)
+            && !update_rollback::is_failed_extension_version(
+                manifest.id,
+                &extension.provider,
+                &extension.id,
+                &new_version.id,
+            )
+            .await
         {
             info!(
                 "New version '{}' is available for extension '{}'. Updating manifest.",
@@ -517,6 +694,7 @@ async fn update_server_and_extensions_if_newer_version_is_available(
     }
 
     if updated {
+        update_rollback::prepare_update(server_dir, &previous_manifest).await?;
         manifest.save(server_dir).await?;
     }
 
@@ -700,39 +878,51 @@ fn command_substitute_placeholders(
         .collect()
 }
 
-fn start_command_with_pty(
-    command_args_str: &[OsString],
-    server_dir: &Path,
-) -> anyhow::Result<(Pty, Child)> {
-    debug!("Starting command: {:?}", command_args_str);
-
-    let command_str = command_args_str.first().context("command is empty")?;
-    let args_str = &command_args_str[1..];
-
-    let command = pty_process::Command::new(command_str)
-        .args(args_str)
-        .current_dir(server_dir)
-        .kill_on_drop(true);
-
-    let (pty, pts) = pty_process::open().context("Failed to open PTY")?;
-    pty.resize(pty_process::Size::new(PTY_DEFAULT_ROWS, PTY_DEFAULT_COLS))
-        .context("Failed to set PTY size")?;
+/// Relays the console of the server to and from the supervisor and watches for the server process
+/// to exit. `reattached` is true when the server was started by a previous minecraftd process.
+fn attach_supervisor(
+    id: Uuid,
+    supervisor: Supervisor,
+    reattached: bool,
+) -> (tokio::sync::mpsc::Sender<TerminalInput>, Arc<ConsoleLog>) {
+    let pid = supervisor.pid;
+    let (supervisor_reader, supervisor_writer) = supervisor.into_split();
+    let (term_in_tx, term_in_rx) = tokio::sync::mpsc::channel::<TerminalInput>(1);
+    let (exit_tx, exit_rx) = tokio::sync::oneshot::channel();
+    spawn_terminal_writer(supervisor_writer, term_in_rx);
+    let console_log = ConsoleLog::open(id);
+    spawn_player_tracker(id, console_log.subscribe().1, reattached);
+    spawn_terminal_reader(supervisor_reader, console_log.clone(), exit_tx);
 
-    let child = command
-        .spawn(pts)
-        .context("Failed to spawn server process")?;
+    spawn_process_watcher(id, pid, exit_rx);
 
-    debug!(
-        "Spawned server process with PID {}",
-        child.id().unwrap_or(0)
-    );
-
-    Ok((pty, child))
+    (term_in_tx, console_log)
 }
 
-fn spawn_process_watcher(id: Uuid, mut process: Child) {
+fn spawn_process_watcher(
+    id: Uuid,
+    pid: u32,
+    exit_rx: tokio::sync::oneshot::Receiver<Option<ExitStatus>>,
+) {
     tokio::spawn(async move {
-        match process.wait().await {
+        let status = match exit_rx.await {
+            Ok(Some(status)) => Ok(status),
+            _ => {
+                // without its supervisor the server can no longer be controlled, so make sure it
+                // does not keep running unnoticed
+                let _ = nix::sys::signal::kill(
+                    nix::unistd::Pid::from_raw(pid as i32),
+                    nix::sys::signal::Signal::SIGKILL,
+                );
+                Err(anyhow::anyhow!("Lost connection to the supervisor"))
+            }
+        };
+
+        if let Err(e) = supervisor::remove_state_dir(id).await {
+            error!("{e:?}");
+        }
+
+        match status {
             Ok(status) => {
                 info!("Server process exited with status: {}", status);
 
@@ -750,22 +940,30 @@ fn spawn_process_watcher(id: Uuid, mut process: Child) {
                 drop(runner);
 
                 proxy_server::unregister_server(id).await;
+                proxy_server::register_sleeping_server_from_manifest(
+                    &server.manifest,
+                    &server.server_dir,
+                )
+                .await;
 
                 if !status.success() {
+                    let console_lines = server.console_log.last_lines(ALERT_CONSOLE_LINES);
                     send_alert("server_crash", || Alert {
                         severity: Severity::Error,
                         title: "Server crashed".to_string(),
                         message: if let Some(code) = status.code() {
                             format!(
-                                "Server at `{}` crashed with exit code {}",
+                                "Server at `{}` crashed with exit code {}.{}",
                                 server.server_dir.display(),
-                                code
+                                code,
+                                format_console_lines(&console_lines)
                             )
                         } else if let Some(signal) = status.signal() {
                             format!(
-                                "Server at `{}` was killed by signal {}",
+                                "Server at `{}` was killed by signal {}.{}",
                                 server.server_dir.display(),
-                                signal
+                                signal,
+                                format_console_lines(&console_lines)
                             )
                         } else {
                             unreachable!()
@@ -774,16 +972,32 @@ fn spawn_process_watcher(id: Uuid, mut process: Child) {
                     .await;
                 }
 
-                let restarting_after_failure = !status.success()
-                    && server.manifest.restart_on_failure
-                    && old_status == ServerStatus::Ready;
-                let restarting = restarting_after_failure
-                    || old_status == ServerStatus::Stopping { restarting: true };
+                // the process exited on its own rather than being stopped or killed
+                let crashed =
+                    !status.success() && !matches!(old_status, ServerStatus::Stopping { .. });
+
+                let mut rolled_back = false;
+                if crashed && update_rollback::is_update_pending(id).await {
+                    match update_rollback::roll_back(
+                        &server.server_dir,
+                        id,
+                        "the server crashed shortly after the update",
+                    )
+                    .await
+                    {
+                        Ok(()) => rolled_back = true,
+                        Err(e) => error!(
+                            "Failed to roll back update of server at '{}': {:?}",
+                            server.server_dir.display(),
+                            e
+                        ),
+                    }
+                }
+
+                let restarting =
+                    rolled_back || old_status == ServerStatus::Stopping { restarting: true };
 
                 if restarting {
-                    if restarting_after_failure {
-                        info!("Server is configured to restart on failure. Restarting...");
-                    }
                     if let Err(e) = do_start_server(&server.server_dir, true, false).await {
                         error!(
                             "Failed to restart server at '{}': {:?}",
@@ -791,18 +1005,43 @@ fn spawn_process_watcher(id: Uuid, mut process: Child) {
                             e
                         );
                     }
+                } else if crashed && server.manifest.restart_on_failure {
+                    restart_policy::handle_crash(id, &server.server_dir, &server.manifest).await;
                 }
             }
             Err(e) => {
                 error!("Failed to wait for server process: {:?}", e);
+
+                let mut runner = RUNNER.lock().await;
+                let Some(server) = runner.running_servers.get(&id) else {
+                    return;
+                };
+                let old_status = server.status.get();
+                server.status.set(ServerStatus::Stopped);
+                let server = runner
+                    .running_servers
+                    .remove(&id)
+                    .expect("Server should exist");
+                drop(runner);
+
+                proxy_server::unregister_server(id).await;
+
+                if !matches!(old_status, ServerStatus::Stopping { .. })
+                    && server.manifest.restart_on_failure
+                {
+                    restart_policy::handle_crash(id, &server.server_dir, &server.manifest).await;
+                }
             }
         }
     });
 }
 
-fn spawn_readiness_checker(id: Uuid, server_port: u16) {
+/// `alert` is false when reattaching to a server that may have been ready before. The server is
+/// killed if it does not become ready within `start_timeout`.
+fn spawn_readiness_checker(id: Uuid, server_port: u16, start_timeout: Duration, alert: bool) {
     tokio::spawn(async move {
         let server_addr = (Ipv4Addr::LOCALHOST, server_port);
+        let deadline = Instant::now() + start_timeout;
 
         macro_rules! is_ready {
             () => {
@@ -821,6 +1060,11 @@ fn spawn_readiness_checker(id: Uuid, server_port: u16) {
                 debug!("Server {id} is no longer starting, aborting readiness check",);
                 return;
             }
+
+            if Instant::now() >= deadline {
+                handle_start_timeout(id, start_timeout).await;
+                return;
+            }
         }
 
         {
@@ -832,17 +1076,180 @@ fn spawn_readiness_checker(id: Uuid, server_port: u16) {
 
             info!("Server {id} is now ready");
 
-            send_alert("server_ready", || Alert {
-                severity: Severity::Info,
-                title: "Server is ready".to_string(),
-                message: format!(
-                    "Server at `{}` is now ready to accept connections",
-                    server.server_dir.display()
-                ),
-            })
-            .await;
+            if alert {
+                send_alert("server_ready", || Alert {
+                    severity: Severity::Info,
+                    title: "Server is ready".to_string(),
+                    message: format!(
+                        "Server at `{}` is now ready to accept connections",
+                        server.server_dir.display()
+                    ),
+                })
+                .await;
+            }
 
             spawn_bridge_connector(id, server.server_dir.clone());
+            spawn_liveness_probe(id, server.pid, server.manifest.liveness_probe.clone());
+        }
+    });
+}
+
+/// Kills a server that got stuck while starting, and restarts it if `restart_on_failure` is set.
+async fn handle_start_timeout(id: Uuid, start_timeout: Duration) {
+    let (server_dir, manifest, console_lines) = {
+        let mut runner = RUNNER.lock().await;
+        let Some(server) = runner.running_servers.get(&id) else {
+            return;
+        };
+        let server_dir = server.server_dir.clone();
+        let manifest = server.manifest.clone();
+        let console_lines = server.console_log.last_lines(ALERT_CONSOLE_LINES);
+        runner.start_failures.insert(
+            id,
+            format!(
+                "Server did not become ready within {}",
+                start_timeout.human_format()
+            ),
+        );
+        (server_dir, manifest, console_lines)
+    };
+
+    // a server started after an update is killed and rolled back by the update trial watcher
+    let update_pending = update_rollback::is_update_pending(id).await;
+
+    if update_pending {
+        warn!(
+            "Server at '{}' did not become ready within {}, leaving it to the update trial watcher",
+            server_dir.display(),
+            start_timeout.human_format()
+        );
+    } else {
+        warn!(
+            "Server at '{}' did not become ready within {}, killing it",
+            server_dir.display(),
+            start_timeout.human_format()
+        );
+    }
+
+    send_alert("server_start_timeout", || Alert {
+        severity: Severity::Error,
+        title: "Server did not start".to_string(),
+        message: format!(
+            "Server at `{}` did not become ready within {}{}.{}",
+            server_dir.display(),
+            start_timeout.human_format(),
+            if update_pending {
+                " and will be rolled back to the version before its update"
+            } else {
+                " and was killed"
+            },
+            format_console_lines(&console_lines)
+        ),
+    })
+    .await;
+
+    if update_pending {
+        return;
+    }
+
+    if let Err(e) = do_kill_server(id).await {
+        error!("Failed to kill server {id}: {e:?}");
+        return;
+    }
+    // the server may already have been removed, in which case it is stopped anyway
+    let _ = wait_for_server_status(id, ServerStatus::Stopped).await;
+
+    if manifest.restart_on_failure {
+        restart_policy::handle_crash(id, &server_dir, &manifest).await;
+    }
+}
+
+/// Formats console lines as a code block to be appended to an alert message.
+fn format_console_lines(lines: &[String]) -> String {
+    if lines.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        " Last console output:\n```\n{}\n```",
+        lines
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .take(ALERT_CONSOLE_LINE_LENGTH)
+                    .collect::<String>()
+                    .replace("```", "'''")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+/// Watches a server started after an update: the update is rolled back if the server does not
+/// become ready in time, and confirmed once it has been running stably.
+fn spawn_update_trial_watcher(id: Uuid, pid: u32) {
+    tokio::spawn(async move {
+        let config = &get_config().update_rollback;
+
+        match timeout(
+            config.ready_timeout,
+            wait_for_server_status(id, ServerStatus::Ready),
+        )
+        .await
+        {
+            Ok(Ok(())) => {}
+            // stopped before becoming ready; crashes are rolled back by the process watcher
+            Ok(Err(_)) => return,
+            Err(_) => {
+                warn!("Updated server {id} did not become ready in time, rolling back");
+
+                let Some(server_dir) = get_server_dir(id).await else {
+                    return;
+                };
+                if let Err(e) = do_kill_server(id).await {
+                    error!("Failed to kill server {id}: {e:?}");
+                    return;
+                }
+                // the server may already have been removed, in which case it is stopped anyway
+                let _ = wait_for_server_status(id, ServerStatus::Stopped).await;
+
+                let reason = format!(
+                    "the server did not become ready within {}",
+                    config.ready_timeout.human_format()
+                );
+                if let Err(e) = update_rollback::roll_back(&server_dir, id, &reason).await {
+                    error!(
+                        "Failed to roll back update of server at '{}': {:?}",
+                        server_dir.display(),
+                        e
+                    );
+                    return;
+                }
+
+                if let Err(e) = do_start_server(&server_dir, true, false).await {
+                    error!(
+                        "Failed to start server at '{}' after rollback: {:?}",
+                        server_dir.display(),
+                        e
+                    );
+                }
+                return;
+            }
+        }
+
+        tokio::time::sleep(config.stable_period).await;
+
+        // the server may have been restarted in the meantime, in which case a new watcher runs
+        let still_running = {
+            let runner = RUNNER.lock().await;
+            runner
+                .running_servers
+                .get(&id)
+                .is_some_and(|s| s.pid == pid && s.status.get() == ServerStatus::Ready)
+        };
+
+        if still_running && let Err(e) = update_rollback::confirm_update(id).await {
+            error!("Failed to confirm update of server {id}: {e:?}");
         }
     });
 }
@@ -865,7 +1272,7 @@ fn spawn_bridge_connector(id: Uuid, server_dir: PathBuf) {
                     };
                     server
                         .bridge
-                        .set(Mutex::new(bridge))
+                        .set(Arc::new(Mutex::new(bridge)))
                         .expect("bridge already set");
 
                     return;
@@ -891,9 +1298,7 @@ async fn do_stop_server(id: Uuid, restarting: bool) -> anyhow::Result<()> {
 
         server.status.set(ServerStatus::Stopping { restarting });
 
-        if let Err(err) =
-            request_server_stop(server.rcon_port.port(), &server.rcon_password, restarting).await
-        {
+        if let Err(err) = request_server_stop(&server.rcon, restarting).await {
             drop(runner);
             debug!(
                 "Failed to request server stop. Killing it instead: {:?}",
@@ -918,30 +1323,19 @@ async fn do_stop_server(id: Uuid, restarting: bool) -> anyhow::Result<()> {
     }
 }
 
-async fn request_server_stop(
-    rcon_port: u16,
-    rcon_password: &str,
-    restarting: bool,
-) -> anyhow::Result<()> {
+async fn request_server_stop(rcon: &RconSession, restarting: bool) -> anyhow::Result<()> {
     let mut count = 0;
     loop {
         let result: anyhow::Result<()> = async {
-            let mut client =
-                minecraft_rcon::Client::connect((Ipv4Addr::LOCALHOST, rcon_port), rcon_password)
-                    .await
-                    .context("Failed to connect to RCON")?;
-
             if restarting {
-                client
-                    .execute_command(&format!(
-                        "kick @a {}",
-                        get_config().messages.server_restarting_kick
-                    ))
-                    .await?;
+                rcon.execute_command(&format!(
+                    "kick @a {}",
+                    get_config().messages.server_restarting_kick
+                ))
+                .await?;
             }
 
-            client
-                .execute_command("stop")
+            rcon.execute_command("stop")
                 .await
                 .context("Failed to send stop command")?;
 
@@ -1008,12 +1402,34 @@ async fn do_kill_server(id: Uuid) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Kills the server and starts it again once it has exited.
+async fn do_force_restart_server(id: Uuid) -> anyhow::Result<()> {
+    let runner = RUNNER.lock().await;
+    let Some(server) = runner.running_servers.get(&id) else {
+        bail!("Server is not running");
+    };
+
+    server
+        .status
+        .set(ServerStatus::Stopping { restarting: true });
+
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(server.pid as i32),
+        nix::sys::signal::Signal::SIGKILL,
+    )?;
+    Ok(())
+}
+
 async fn start_auto_start_servers() {
     let auto_start_servers = get_auto_start_servers().await;
 
     info!("Auto-starting servers: {:?}", auto_start_servers);
 
     for server_dir in auto_start_servers {
+        if is_server_running(&server_dir).await.unwrap_or(false) {
+            continue;
+        }
+
         tokio::spawn(async move {
             if let Err(e) = do_start_server(&server_dir, false, true).await {
                 error!(
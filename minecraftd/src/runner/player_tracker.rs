@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::{
+    player_sessions, proxy_server,
+    runner::console_log::{ConsoleSubscription, strip_ansi_escapes},
+};
+
+/// The timestamp, thread and level of a line, e.g. `[12:34:56] [Server thread/INFO]: `. Requiring
+/// it keeps chat messages from being mistaken for join and leave messages.
+const LINE_PREFIX: &str = r"^\[[^\]]*\](?: \[[^\]]*\])*: ";
+
+static UUID_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"{LINE_PREFIX}UUID of player (\w{{1,16}}) is ([0-9a-f-]{{36}})$"
+    ))
+    .unwrap()
+});
+static JOIN_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"{LINE_PREFIX}(\w{{1,16}})\[[^/\]]*/(\[[^\]]*\]:\d+|[^\]]+)\] logged in with entity id"
+    ))
+    .unwrap()
+});
+static LEAVE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"{LINE_PREFIX}(\w{{1,16}}) (?:left the game|lost connection: .*)$"
+    ))
+    .unwrap()
+});
+
+/// Records the players joining and leaving a server from its console output.
+///
+/// `reattached` is true when minecraftd has restarted while the server kept running, in which case
+/// the sessions that were open are still valid.
+pub(super) fn spawn_player_tracker(id: Uuid, mut console: ConsoleSubscription, reattached: bool) {
+    tokio::spawn(async move {
+        if !reattached && let Err(e) = player_sessions::end_open_sessions(id).await {
+            error!("Failed to end player sessions of server {id}: {e:?}");
+        }
+
+        let mut tracker = PlayerTracker {
+            id,
+            partial_line: Vec::new(),
+            uuids: HashMap::new(),
+        };
+
+        loop {
+            match console.recv().await {
+                Ok(Some(content)) => tracker.push(&content).await,
+                Ok(None) => break,
+                Err(e) => {
+                    error!("Stopped tracking players of server {id}: {e:?}");
+                    break;
+                }
+            }
+        }
+
+        if let Err(e) = player_sessions::end_open_sessions(id).await {
+            error!("Failed to end player sessions of server {id}: {e:?}");
+        }
+    });
+}
+
+struct PlayerTracker {
+    id: Uuid,
+    partial_line: Vec<u8>,
+    /// UUIDs logged during authentication, before the player has logged in.
+    uuids: HashMap<String, Uuid>,
+}
+
+impl PlayerTracker {
+    async fn push(&mut self, content: &[u8]) {
+        self.partial_line.extend_from_slice(content);
+
+        let Some(end) = self.partial_line.iter().rposition(|&byte| byte == b'\n') else {
+            return;
+        };
+        let rest = self.partial_line.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial_line, rest);
+
+        for line in complete.split(|&byte| byte == b'\n') {
+            let line = strip_ansi_escapes(line);
+            if let Err(e) = self.handle_line(line.trim_end()).await {
+                error!(
+                    "Failed to record player session of server {}: {e:?}",
+                    self.id
+                );
+            }
+        }
+    }
+
+    async fn handle_line(&mut self, line: &str) -> anyhow::Result<()> {
+        if let Some(captures) = UUID_REGEX.captures(line) {
+            if let Ok(uuid) = captures[2].parse() {
+                self.uuids.insert(captures[1].to_string(), uuid);
+            }
+        } else if let Some(captures) = JOIN_REGEX.captures(line) {
+            let name = &captures[1];
+            let address = self.client_address(&captures[2]).await;
+            player_sessions::record_join(self.id, name, self.uuids.remove(name), address).await?;
+        } else if let Some(captures) = LEAVE_REGEX.captures(line) {
+            player_sessions::record_leave(self.id, &captures[1]).await?;
+        }
+        Ok(())
+    }
+
+    /// The address of the player, looked up in the proxy if the player joined through it.
+    async fn client_address(&self, logged_address: &str) -> Option<IpAddr> {
+        let address: SocketAddr = logged_address.parse().ok()?;
+        if address.ip().is_loopback()
+            && let Some(client_address) =
+                proxy_server::get_client_address(self.id, address.port()).await
+        {
+            return Some(client_address);
+        }
+        Some(address.ip())
+    }
+}
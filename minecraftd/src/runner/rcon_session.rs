@@ -0,0 +1,74 @@
+use std::net::Ipv4Addr;
+
+use anyhow::Context;
+use tokio::sync::Mutex;
+
+use crate::config::get_config;
+
+/// RCON connection to a running server. It is connected on first use and reconnected after an
+/// error, so that commands do not each open a new connection.
+pub struct RconSession {
+    port: u16,
+    password: String,
+    client: Mutex<Option<minecraft_rcon::Client>>,
+}
+
+impl RconSession {
+    pub fn new(port: u16, password: String) -> Self {
+        Self {
+            port,
+            password,
+            client: Mutex::new(None),
+        }
+    }
+
+    /// Runs a command and returns its response text. Commands are run one at a time.
+    pub async fn execute_command(&self, command: &str) -> anyhow::Result<String> {
+        let mut client = self.client.lock().await;
+
+        let reused = client.is_some();
+        match self.do_execute_command(&mut client, command).await {
+            // the server may have closed an idle connection, in which case the command was not run
+            Err(e) if reused && is_connection_closed(&e) => {
+                debug!("RCON connection was closed, reconnecting: {e}");
+                self.do_execute_command(&mut client, command).await
+            }
+            result => result,
+        }
+        .with_context(|| format!("Failed to run command '{command}' over RCON"))
+    }
+
+    async fn do_execute_command(
+        &self,
+        client: &mut Option<minecraft_rcon::Client>,
+        command: &str,
+    ) -> std::io::Result<String> {
+        let connected = match client {
+            Some(connected) => connected,
+            None => client.insert(
+                minecraft_rcon::Client::connect_with_timeout(
+                    (Ipv4Addr::LOCALHOST, self.port),
+                    &self.password,
+                    get_config().runner.rcon_timeout,
+                )
+                .await?,
+            ),
+        };
+
+        let result = connected.execute_command(command).await;
+        if result.is_err() {
+            *client = None;
+        }
+        result
+    }
+}
+
+fn is_connection_closed(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
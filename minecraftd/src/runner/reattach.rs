@@ -0,0 +1,199 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+    time::{Instant, SystemTime},
+};
+
+use anyhow::{Context, bail};
+use minecraftd_manifest::{Connection, ServerManifest};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::get_config,
+    port_pool::Port,
+    proxy_server, registry,
+    runner::{
+        RUNNER, RunningServer, ServerHealth, ServerPort, ServerStatus, attach_supervisor,
+        rcon_session::RconSession, spawn_readiness_checker, spawn_update_trial_watcher,
+    },
+    supervisor, update_rollback,
+    util::observable_value::ObservableValue,
+};
+
+const STATE_FILE_NAME: &str = "server.json";
+
+/// What the daemon needs to know to take over a server started by a previous daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerState {
+    pub server_dir: PathBuf,
+    pub server_port: u16,
+    /// Whether `server_port` was allocated from the port pool for proxy mode.
+    pub proxy: bool,
+    pub rcon_port: u16,
+    pub rcon_password: String,
+    pub started_at: SystemTime,
+}
+
+pub async fn save_state(id: Uuid, state: &ServerState) -> anyhow::Result<()> {
+    let path = supervisor::state_dir(id)?.join(STATE_FILE_NAME);
+    tokio::fs::write(&path, serde_json::to_string(state)?)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+async fn load_state(id: Uuid) -> anyhow::Result<ServerState> {
+    let path = supervisor::state_dir(id)?.join(STATE_FILE_NAME);
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Takes over the servers whose supervisors outlived the previous daemon.
+pub async fn reattach_servers() {
+    let servers_dir = match supervisor::servers_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("{e:?}");
+            return;
+        }
+    };
+
+    let mut entries = match tokio::fs::read_dir(&servers_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            error!("Failed to read {}: {e:?}", servers_dir.display());
+            return;
+        }
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some(id) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| Uuid::parse_str(name).ok())
+        else {
+            continue;
+        };
+
+        reattach_server(id).await;
+    }
+}
+
+async fn reattach_server(id: Uuid) {
+    let state_dir = match supervisor::state_dir(id) {
+        Ok(state_dir) => state_dir,
+        Err(e) => {
+            error!("{e:?}");
+            return;
+        }
+    };
+
+    // everything that can fail is done before connecting, so that a supervisor that is reached is
+    // always taken over
+    let prepared = prepare_reattach(id).await;
+
+    let supervisor = match supervisor::connect(&state_dir).await {
+        Ok(supervisor) => supervisor,
+        Err(e) => {
+            // the supervisor has exited, so the state is stale
+            info!("Not reattaching to server {id}: {e:?}");
+            if let Err(e) = supervisor::remove_state_dir(id).await {
+                error!("{e:?}");
+            }
+            return;
+        }
+    };
+
+    let (state, manifest, server_port, rcon_port) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            // the state is kept so that the server is not forgotten while its process is running
+            error!(
+                "Server {id} is still running under its supervisor (PID {}), but cannot be reattached. Stop the process manually or fix the problem and restart minecraftd: {e:?}",
+                supervisor.pid
+            );
+            return;
+        }
+    };
+
+    let mut runner = RUNNER.lock().await;
+
+    info!(
+        "Reattaching to server at '{}' (PID {})",
+        state.server_dir.display(),
+        supervisor.pid
+    );
+
+    let pid = supervisor.pid;
+    let (term_in_tx, console_log) = attach_supervisor(id, supervisor, true);
+
+    spawn_readiness_checker(
+        id,
+        server_port.port(),
+        manifest
+            .start_timeout
+            .unwrap_or(get_config().runner.start_timeout),
+        false,
+    );
+
+    if let Connection::Proxy { hostname } = &manifest.connection {
+        proxy_server::register_server(id, hostname, server_port.port()).await;
+    }
+
+    if update_rollback::is_update_pending(id).await {
+        spawn_update_trial_watcher(id, pid);
+    }
+
+    let uptime = SystemTime::now()
+        .duration_since(state.started_at)
+        .unwrap_or_default();
+
+    runner.running_servers.insert(RunningServer {
+        server_dir: state.server_dir,
+        status: ObservableValue::new(ServerStatus::Starting { restarting: false }),
+        manifest: Arc::new(manifest),
+        terminal_in: term_in_tx,
+        server_port,
+        rcon: Arc::new(RconSession::new(rcon_port.port(), state.rcon_password)),
+        _rcon_port: rcon_port,
+        pid,
+        running_since: Instant::now()
+            .checked_sub(uptime)
+            .unwrap_or_else(Instant::now),
+        bridge: OnceLock::new(),
+        health: ObservableValue::new(ServerHealth::Unknown),
+        console_log,
+    });
+}
+
+async fn prepare_reattach(
+    id: Uuid,
+) -> anyhow::Result<(ServerState, ServerManifest, ServerPort, Port)> {
+    let state = load_state(id).await?;
+
+    let manifest = ServerManifest::load(&state.server_dir).await?;
+    if manifest.id != id {
+        bail!(
+            "Server at '{}' now has ID {}",
+            state.server_dir.display(),
+            manifest.id
+        );
+    }
+
+    // servers started by an older minecraftd may not be registered yet
+    if let Err(e) = registry::register_server(&manifest, &state.server_dir).await {
+        error!("Failed to register server {id}: {e:?}");
+    }
+
+    let server_port = if state.proxy {
+        ServerPort::Proxy(Port::reserve(state.server_port)?)
+    } else {
+        ServerPort::Direct(state.server_port)
+    };
+    let rcon_port = Port::reserve(state.rcon_port)?;
+
+    Ok((state, manifest, server_port, rcon_port))
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use duration_str::HumanFormat;
+use minecraftd_manifest::{RestartPolicy, ServerManifest};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    runner::do_start_server,
+};
+
+static CRASH_LOOPS: LazyLock<Mutex<HashMap<Uuid, CrashLoop>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Crash history of a server that has crashed recently.
+struct CrashLoop {
+    server_dir: PathBuf,
+    name: String,
+    crashes: VecDeque<Instant>,
+    state: CrashLoopState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashLoopState {
+    /// The server is running again, or is being started.
+    Restarted,
+    BackingOff {
+        restart_at: Instant,
+    },
+    /// The server crashed too often and is not restarted anymore.
+    Failed,
+}
+
+pub struct CrashLoopInfo {
+    pub server_dir: PathBuf,
+    pub name: String,
+    pub state: CrashLoopState,
+}
+
+/// Records a crash and restarts the server according to its restart policy, or gives up on it.
+pub async fn handle_crash(id: Uuid, server_dir: &Path, manifest: &ServerManifest) {
+    let Some(mut restart_at) = record_crash(id, server_dir, manifest).await else {
+        return;
+    };
+
+    let server_dir = server_dir.to_path_buf();
+    let manifest = manifest.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep_until(restart_at.into()).await;
+
+            {
+                let mut crash_loops = CRASH_LOOPS.lock().await;
+                // cancelled by a manual start or stop in the meantime
+                let Some(crash_loop) = crash_loops.get_mut(&id) else {
+                    return;
+                };
+                if crash_loop.state != (CrashLoopState::BackingOff { restart_at }) {
+                    return;
+                }
+                crash_loop.state = CrashLoopState::Restarted;
+            }
+
+            let Err(e) = do_start_server(&server_dir, true, false).await else {
+                return;
+            };
+            error!(
+                "Failed to restart server at '{}': {:?}",
+                server_dir.display(),
+                e
+            );
+
+            // failing to start counts as a crash so that it is retried with backoff too
+            match record_crash(id, &server_dir, &manifest).await {
+                Some(next_restart_at) => restart_at = next_restart_at,
+                None => return,
+            }
+        }
+    });
+}
+
+/// Returns when the server should be restarted, or `None` if it has crashed too often.
+async fn record_crash(id: Uuid, server_dir: &Path, manifest: &ServerManifest) -> Option<Instant> {
+    let policy = &manifest.restart_policy;
+    let now = Instant::now();
+
+    let mut crash_loops = CRASH_LOOPS.lock().await;
+    let crash_loop = crash_loops.entry(id).or_insert_with(|| CrashLoop {
+        server_dir: server_dir.to_path_buf(),
+        name: manifest.name.clone(),
+        crashes: VecDeque::new(),
+        state: CrashLoopState::Restarted,
+    });
+
+    crash_loop.crashes.push_back(now);
+    forget_old_crashes(&mut crash_loop.crashes, now, policy.window);
+
+    let crashes = crash_loop.crashes.len() as u32;
+    if crashes > policy.max_retries {
+        crash_loop.state = CrashLoopState::Failed;
+        drop(crash_loops);
+
+        error!(
+            "Server at '{}' crashed {crashes} times within {}, giving up",
+            server_dir.display(),
+            policy.window.human_format()
+        );
+        send_alert("server_restart_given_up", || Alert {
+            severity: Severity::Error,
+            title: "Server is crash-looping".to_string(),
+            message: format!(
+                "Server at `{}` crashed {crashes} times within {} and will not be restarted",
+                server_dir.display(),
+                policy.window.human_format()
+            ),
+        })
+        .await;
+        return None;
+    }
+
+    let backoff = backoff(policy, crashes);
+    let restart_at = now + backoff;
+    crash_loop.state = CrashLoopState::BackingOff { restart_at };
+
+    info!(
+        "Restarting server at '{}' in {} (crash {crashes} of at most {} within {})",
+        server_dir.display(),
+        backoff.human_format(),
+        policy.max_retries + 1,
+        policy.window.human_format()
+    );
+
+    Some(restart_at)
+}
+
+/// Drops the crashes that are more than `window` before `now`.
+fn forget_old_crashes(crashes: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while crashes
+        .front()
+        .is_some_and(|crash| now.duration_since(*crash) > window)
+    {
+        crashes.pop_front();
+    }
+}
+
+/// Delay before restarting a server that has crashed `crashes` times within the window.
+fn backoff(policy: &RestartPolicy, crashes: u32) -> Duration {
+    policy
+        .initial_backoff
+        .saturating_mul(2u32.saturating_pow(crashes.saturating_sub(1)))
+        .min(policy.max_backoff)
+}
+
+/// Forgets the crash history, cancelling a pending restart. Called when the server is started or
+/// stopped by hand. Returns whether the server was backing off or had been given up on.
+pub async fn reset(id: Uuid) -> bool {
+    let mut crash_loops = CRASH_LOOPS.lock().await;
+    crash_loops
+        .remove(&id)
+        .is_some_and(|crash_loop| crash_loop.state != CrashLoopState::Restarted)
+}
+
+/// Forgets the crash history of the server at `server_dir`. See `reset`.
+pub async fn reset_by_server_dir(server_dir: &Path) -> bool {
+    let id = {
+        let crash_loops = CRASH_LOOPS.lock().await;
+        crash_loops
+            .iter()
+            .find(|(_, crash_loop)| crash_loop.server_dir == server_dir)
+            .map(|(id, _)| *id)
+    };
+
+    match id {
+        Some(id) => reset(id).await,
+        None => false,
+    }
+}
+
+/// Servers that are waiting to be restarted or have been given up on.
+pub async fn get_crash_looping_servers() -> Vec<CrashLoopInfo> {
+    let crash_loops = CRASH_LOOPS.lock().await;
+    crash_loops
+        .values()
+        .filter(|crash_loop| crash_loop.state != CrashLoopState::Restarted)
+        .map(|crash_loop| CrashLoopInfo {
+            server_dir: crash_loop.server_dir.clone(),
+            name: crash_loop.name.clone(),
+            state: crash_loop.state,
+        })
+        .collect()
+}
+
+impl CrashLoopState {
+    /// Remaining time until the restart of a backing-off server.
+    pub fn restart_in(&self) -> Option<Duration> {
+        match self {
+            CrashLoopState::BackingOff { restart_at } => {
+                Some(restart_at.saturating_duration_since(Instant::now()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy {
+            max_retries: 5,
+            window: Duration::from_secs(600),
+            initial_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = policy();
+        let backoffs = (1..=5)
+            .map(|crashes| backoff(&policy, crashes).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, [10, 20, 40, 60, 60]);
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        let policy = policy();
+        assert_eq!(backoff(&policy, 100), policy.max_backoff);
+        assert_eq!(backoff(&policy, u32::MAX), policy.max_backoff);
+    }
+
+    #[test]
+    fn old_crashes_are_forgotten() {
+        // in the future so that the crashes are not before the start of the monotonic clock
+        let now = Instant::now() + Duration::from_secs(1000);
+        let window = Duration::from_secs(600);
+        let mut crashes = [700, 601, 600, 10, 0]
+            .into_iter()
+            .map(|ago| now - Duration::from_secs(ago))
+            .collect::<VecDeque<_>>();
+
+        forget_old_crashes(&mut crashes, now, window);
+
+        assert_eq!(crashes.len(), 3);
+        assert_eq!(crashes.front(), Some(&(now - window)));
+    }
+}
@@ -1,5 +1,12 @@
-use anyhow::Context;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::{os::unix::process::ExitStatusExt, process::ExitStatus, sync::Arc};
+
+use anyhow::{Context, bail};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+
+use crate::{
+    runner::console_log::{ConsoleLog, ConsoleSubscription},
+    supervisor::Frame,
+};
 
 const TERMINAL_BUFFER_SIZE: usize = 1024;
 
@@ -9,11 +16,6 @@ pub enum TerminalInput {
     Resize { cols: u16, rows: u16 },
 }
 
-#[derive(Debug, Clone)]
-pub enum TerminalOutput {
-    Output { content: Buffer },
-}
-
 #[derive(Debug, Clone)]
 pub struct Buffer {
     len: usize,
@@ -30,27 +32,43 @@ impl Default for Buffer {
 }
 
 pub struct TerminalReader {
-    terminal_out: tokio::sync::broadcast::Receiver<TerminalOutput>,
+    /// Scrollback sent before the live output.
+    replay: Option<Vec<u8>>,
+    console: ConsoleSubscription,
 }
 
 pub struct TerminalWriter {
     terminal_in: tokio::sync::mpsc::Sender<TerminalInput>,
+    read_only: bool,
 }
 
 impl TerminalReader {
-    pub(super) fn new(terminal_out: tokio::sync::broadcast::Receiver<TerminalOutput>) -> Self {
-        Self { terminal_out }
+    pub(super) fn new(replay: Vec<u8>, console: ConsoleSubscription) -> Self {
+        Self {
+            replay: Some(replay).filter(|replay| !replay.is_empty()),
+            console,
+        }
     }
 }
 
 impl TerminalWriter {
-    pub(super) fn new(terminal_in: tokio::sync::mpsc::Sender<TerminalInput>) -> Self {
-        Self { terminal_in }
+    pub(super) fn new(
+        terminal_in: tokio::sync::mpsc::Sender<TerminalInput>,
+        read_only: bool,
+    ) -> Self {
+        Self {
+            terminal_in,
+            read_only,
+        }
     }
 }
 
 impl mcctl_protocol::server::TerminalWriter<anyhow::Error> for TerminalWriter {
     async fn write(&mut self, content: &[u8]) -> anyhow::Result<()> {
+        if self.read_only {
+            bail!("Console is attached read-only");
+        }
+
         let mut offset = 0;
         while offset < content.len() {
             let chunk_size = std::cmp::min(TERMINAL_BUFFER_SIZE, content.len() - offset);
@@ -71,6 +89,11 @@ impl mcctl_protocol::server::TerminalWriter<anyhow::Error> for TerminalWriter {
     }
 
     async fn resize(&mut self, cols: u16, rows: u16) -> anyhow::Result<()> {
+        // the terminal size is left to the clients that can type into it
+        if self.read_only {
+            return Ok(());
+        }
+
         self.terminal_in
             .send(TerminalInput::Resize { cols, rows })
             .await
@@ -81,63 +104,77 @@ impl mcctl_protocol::server::TerminalWriter<anyhow::Error> for TerminalWriter {
 
 impl mcctl_protocol::server::TerminalReader<anyhow::Error> for TerminalReader {
     async fn read(&mut self) -> anyhow::Result<Option<mcctl_protocol::TerminalOutput>> {
-        match self.terminal_out.recv().await {
-            Ok(TerminalOutput::Output { content }) => Ok(Some(mcctl_protocol::TerminalOutput {
-                content: content.data[..content.len].to_vec(),
-            })),
-            Err(_) => Ok(None),
-        }
+        let content = match self.replay.take() {
+            Some(replay) => replay,
+            None => match self.console.recv().await? {
+                Some(content) => content,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(mcctl_protocol::TerminalOutput { content }))
     }
 }
 
 pub fn spawn_terminal_writer(
-    mut pty_writer: pty_process::OwnedWritePty,
+    mut supervisor_writer: OwnedWriteHalf,
     mut term_in_rx: tokio::sync::mpsc::Receiver<TerminalInput>,
 ) {
     tokio::spawn(async move {
         while let Some(input) = term_in_rx.recv().await {
-            match input {
+            let frame = match input {
                 TerminalInput::Input { content } => {
                     trace!(
                         "Writing to PTY: {}",
                         String::from_utf8_lossy(&content.data[..content.len]).trim()
                     );
 
-                    if let Err(e) = pty_writer.write_all(&content.data[..content.len]).await {
-                        eprintln!("Failed to write to PTY: {e}");
-                        break;
-                    }
-                }
-                TerminalInput::Resize { cols, rows } => {
-                    if let Err(e) = pty_writer.resize(pty_process::Size::new(rows, cols)) {
-                        eprintln!("Failed to resize PTY: {e}");
-                        break;
-                    }
+                    Frame::Input(content.data[..content.len].to_vec())
                 }
+                TerminalInput::Resize { cols, rows } => Frame::Resize { cols, rows },
+            };
+
+            if let Err(e) = frame.write(&mut supervisor_writer).await {
+                error!("Failed to write to supervisor: {e:?}");
+                break;
             }
         }
     });
 }
 
+/// Relays the console output from the supervisor and sends the exit status of the server process
+/// once it exits. `None` is sent if the connection to the supervisor is lost before that.
 pub fn spawn_terminal_reader(
-    mut pty_reader: pty_process::OwnedReadPty,
-    term_out_tx: tokio::sync::broadcast::Sender<TerminalOutput>,
+    mut supervisor_reader: OwnedReadHalf,
+    console_log: Arc<ConsoleLog>,
+    exit_tx: tokio::sync::oneshot::Sender<Option<ExitStatus>>,
 ) {
     tokio::spawn(async move {
-        loop {
-            let mut buffer = Buffer::default();
-
-            buffer.len = match pty_reader.read(&mut buffer.data).await {
-                Ok(0) | Err(_) => break, // I/O error on process exit
-                Ok(n) => n,
+        let exit_status = loop {
+            let content = match Frame::read(&mut supervisor_reader).await {
+                Ok(Some(Frame::Output(content))) => content,
+                Ok(Some(Frame::Exited { raw_status })) => {
+                    break Some(ExitStatus::from_raw(raw_status));
+                }
+                Ok(Some(frame)) => {
+                    warn!("Unexpected frame from supervisor: {frame:?}");
+                    continue;
+                }
+                Ok(None) => break None,
+                Err(e) => {
+                    error!("Failed to read from supervisor: {e:?}");
+                    break None;
+                }
             };
 
             trace!(
                 "Read from PTY: {}",
-                String::from_utf8_lossy(&buffer.data[..buffer.len]).trim()
+                String::from_utf8_lossy(&content).trim()
             );
 
-            let _ = term_out_tx.send(TerminalOutput::Output { content: buffer });
-        }
+            console_log.write(&content).await;
+        };
+
+        console_log.close();
+        let _ = exit_tx.send(exit_status);
     });
 }
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use croner::Cron;
+use duration_str::HumanFormat;
+use minecraftd_manifest::{ScheduledAction, ScheduledTask, ServerManifest};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    backup, runner,
+};
+
+const SCHEDULER_TICK_INTERVAL_SECS: u64 = 10;
+
+static SCHEDULED_SERVERS: Mutex<Option<ScheduledServers>> = Mutex::const_new(None);
+
+/// Servers whose schedules are evaluated. Running servers with schedules are added
+/// automatically and stay here after they stop, so that `start` tasks keep working.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduledServers {
+    server_dirs: HashSet<PathBuf>,
+}
+
+fn scheduled_servers_path() -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("scheduled_servers.json");
+    Ok(path)
+}
+
+async fn load_scheduled_servers() -> anyhow::Result<ScheduledServers> {
+    let content = tokio::fs::read_to_string(scheduled_servers_path()?)
+        .await
+        .context("Failed to read scheduled servers file")?;
+    serde_json::from_str(&content).context("Failed to parse scheduled servers file")
+}
+
+async fn save_scheduled_servers(servers: &ScheduledServers) -> anyhow::Result<()> {
+    let path = scheduled_servers_path()?;
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .context("Failed to create data directory")?;
+    tokio::fs::write(path, serde_json::to_string(servers)?)
+        .await
+        .context("Failed to write scheduled servers file")?;
+    Ok(())
+}
+
+pub fn init() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_TICK_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let mut last_tick = Local::now();
+
+        loop {
+            interval.tick().await;
+
+            let now = Local::now();
+            if let Err(err) = run_due_tasks(last_tick, now).await {
+                error!("Scheduler error: {err:?}");
+            }
+            last_tick = now;
+        }
+    });
+}
+
+/// Whether the schedules of the server are being evaluated.
+pub async fn is_server_scheduled(server_dir: &Path) -> bool {
+    if runner::is_server_running(server_dir).await.unwrap_or(false) {
+        return true;
+    }
+
+    let mut servers = SCHEDULED_SERVERS.lock().await;
+    servers
+        .get_or_insert_with(Default::default)
+        .server_dirs
+        .contains(server_dir)
+}
+
+/// Points a stopped scheduled server at the directory it was moved to, or forgets it if it was
+/// deleted.
+pub async fn relocate_scheduled_server(server_dir: &Path, new_server_dir: Option<&Path>) {
+    let mut servers = SCHEDULED_SERVERS.lock().await;
+    if servers.is_none() {
+        *servers = Some(load_scheduled_servers().await.unwrap_or_default());
+    }
+    let servers = servers.as_mut().unwrap();
+
+    if !servers.server_dirs.remove(server_dir) {
+        return;
+    }
+    if let Some(new_server_dir) = new_server_dir {
+        servers.server_dirs.insert(new_server_dir.to_path_buf());
+    }
+
+    if let Err(err) = save_scheduled_servers(servers).await {
+        error!("Failed to save scheduled servers: {err:?}");
+    }
+}
+
+/// Whether the cron expression has an occurrence in `(since, now]`.
+fn is_due(cron: &Cron, since: DateTime<Local>, now: DateTime<Local>) -> bool {
+    cron.find_next_occurrence(&since, false)
+        .is_ok_and(|next| next <= now)
+}
+
+/// Runs the tasks that were due in `(since, now]`.
+async fn run_due_tasks(since: DateTime<Local>, now: DateTime<Local>) -> anyhow::Result<()> {
+    let mut servers = SCHEDULED_SERVERS.lock().await;
+    if servers.is_none() {
+        *servers = Some(load_scheduled_servers().await.unwrap_or_default());
+    }
+    let servers = servers.as_mut().unwrap();
+
+    let mut server_dirs = servers.server_dirs.clone();
+    for id in runner::get_running_server_ids().await {
+        if let Some(server_dir) = runner::get_server_dir(id).await {
+            server_dirs.insert(server_dir);
+        }
+    }
+
+    let mut changed = false;
+
+    for server_dir in server_dirs {
+        // read from disk on every tick so that edits take effect without a restart
+        let manifest = match ServerManifest::load(&server_dir).await {
+            Ok(manifest) if !manifest.schedules.is_empty() => manifest,
+            _ => {
+                changed |= servers.server_dirs.remove(&server_dir);
+                continue;
+            }
+        };
+
+        changed |= servers.server_dirs.insert(server_dir.clone());
+
+        for task in &manifest.schedules {
+            if !is_due(&task.cron, since, now) {
+                continue;
+            }
+
+            let server_dir = server_dir.clone();
+            let manifest = manifest.clone();
+            let task = task.clone();
+            tokio::spawn(async move {
+                info!(
+                    "Running scheduled task '{}' of server at '{}'",
+                    task.name,
+                    server_dir.display()
+                );
+
+                if let Err(err) = run_task(&server_dir, &manifest, &task).await {
+                    error!(
+                        "Scheduled task '{}' of server at '{}' failed: {err:?}",
+                        task.name,
+                        server_dir.display()
+                    );
+                    send_alert("scheduled_task_failed", || Alert {
+                        severity: Severity::Error,
+                        title: "Scheduled task failed".to_string(),
+                        message: format!(
+                            "Scheduled task '{}' of server at `{}` failed: {err}",
+                            task.name,
+                            server_dir.display()
+                        ),
+                    })
+                    .await;
+                }
+            });
+        }
+    }
+
+    if changed {
+        save_scheduled_servers(servers).await?;
+    }
+
+    Ok(())
+}
+
+async fn run_task(
+    server_dir: &Path,
+    manifest: &ServerManifest,
+    task: &ScheduledTask,
+) -> anyhow::Result<()> {
+    let running = runner::is_server_running(server_dir).await?;
+
+    match &task.action {
+        ScheduledAction::Restart { .. }
+        | ScheduledAction::Stop
+        | ScheduledAction::Command { .. }
+        | ScheduledAction::Broadcast { .. }
+            if !running =>
+        {
+            info!(
+                "Skipping scheduled task '{}' since the server is not running",
+                task.name
+            );
+        }
+        ScheduledAction::Start if running => {
+            info!(
+                "Skipping scheduled task '{}' since the server is already running",
+                task.name
+            );
+        }
+        ScheduledAction::Restart { countdown: None } => runner::restart_server(server_dir).await?,
+        ScheduledAction::Restart {
+            countdown: Some(countdown),
+        } => runner::restart_server_with_countdown(server_dir, *countdown).await?,
+        ScheduledAction::Stop => runner::stop_server(server_dir).await?,
+        ScheduledAction::Start => runner::start_server(server_dir).await?,
+        ScheduledAction::Command { command } => {
+            runner::execute_command(manifest.id, command).await?;
+        }
+        ScheduledAction::Broadcast { message } => {
+            runner::tellraw(manifest.id, "@a", message.clone()).await?
+        }
+        ScheduledAction::Backup { keep } => {
+            backup::create_backup(server_dir, manifest.id, &task.name).await?;
+            if let Some(keep) = keep {
+                backup::prune_backups(manifest.id, &task.name, *keep).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the next time the task runs after now.
+pub fn next_run(task: &ScheduledTask) -> Option<DateTime<Local>> {
+    task.cron.find_next_occurrence(&Local::now(), false).ok()
+}
+
+/// Short human-readable description of the action.
+pub fn describe_action(action: &ScheduledAction) -> String {
+    match action {
+        ScheduledAction::Restart {
+            countdown: Some(countdown),
+        } => format!("restart (countdown {})", countdown.human_format()),
+        ScheduledAction::Restart { countdown: None } => "restart".to_string(),
+        ScheduledAction::Stop => "stop".to_string(),
+        ScheduledAction::Start => "start".to_string(),
+        ScheduledAction::Command { command } => format!("command: {command}"),
+        ScheduledAction::Broadcast { message } => format!(
+            "broadcast: {}",
+            serde_json::to_string(message).unwrap_or_default()
+        ),
+        ScheduledAction::Backup { keep: Some(keep) } => format!("backup (keep {keep})"),
+        ScheduledAction::Backup { keep: None } => "backup".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use croner::parser::{CronParser, Seconds, Year};
+
+    use super::*;
+
+    fn cron(pattern: &str) -> Cron {
+        CronParser::builder()
+            .seconds(Seconds::Disallowed)
+            .year(Year::Disallowed)
+            .build()
+            .parse(pattern)
+            .unwrap()
+    }
+
+    fn at(hour: u32, min: u32, sec: u32) -> DateTime<Local> {
+        // far from DST transitions, so that every local time exists exactly once
+        Local.with_ymd_and_hms(2026, 1, 15, hour, min, sec).unwrap()
+    }
+
+    #[test]
+    fn due_within_tick() {
+        let cron = cron("0 4 * * *");
+        assert!(is_due(&cron, at(3, 59, 55), at(4, 0, 5)));
+        assert!(is_due(&cron, at(3, 59, 55), at(4, 0, 0)));
+        assert!(!is_due(&cron, at(3, 59, 45), at(3, 59, 55)));
+    }
+
+    #[test]
+    fn not_due_twice() {
+        let cron = cron("0 4 * * *");
+        // the start of the interval is exclusive, so the next tick does not run the task again
+        assert!(!is_due(&cron, at(4, 0, 0), at(4, 0, 10)));
+        assert!(!is_due(&cron, at(4, 0, 5), at(4, 0, 15)));
+    }
+
+    #[test]
+    fn due_after_missed_ticks() {
+        let cron = cron("*/15 * * * *");
+        assert!(is_due(&cron, at(10, 1, 0), at(10, 40, 0)));
+        assert!(!is_due(&cron, at(10, 1, 0), at(10, 14, 59)));
+    }
+
+    #[test]
+    fn day_of_week() {
+        // 2026-01-15 is a Thursday
+        assert!(is_due(
+            &cron("30 12 * * THU"),
+            at(12, 29, 55),
+            at(12, 30, 5)
+        ));
+        assert!(!is_due(
+            &cron("30 12 * * FRI"),
+            at(12, 29, 55),
+            at(12, 30, 5)
+        ));
+    }
+}
@@ -2,15 +2,21 @@ use std::path::Path;
 
 use anyhow::{Context, bail};
 use minecraftd_manifest::{Connection, ExtensionEntry, ExtensionType, ServerManifest};
+use uuid::Uuid;
 
 use crate::{
+    auto_start, backup,
     extension::{
         self,
-        providers::{ExtensionInfo, ExtensionProvider, get_extension_provider},
+        providers::{
+            EXTENSION_PROVIDERS, ExtensionInfo, ExtensionProvider, get_extension_provider,
+        },
     },
     java_runtime::JavaRuntimeExt,
-    runner,
+    metrics, player_sessions, proxy_server, registry, runner, scheduler,
     server_implementations::get_server_implementation,
+    update_rollback,
+    util::{self, server_properties::ServerProperties},
 };
 
 pub async fn create_server(
@@ -25,6 +31,8 @@ pub async fn create_server(
         bail!("Unknown server implementation '{}'", server_implementation);
     };
 
+    registry::ensure_name_available(name).await?;
+
     tokio::fs::create_dir_all(server_dir).await?;
 
     // download the server jar if it is not already cached
@@ -54,6 +62,205 @@ pub async fn create_server(
         .await
         .context("Failed to save server manifest")?;
 
+    let server_dir = server_dir.canonicalize()?;
+    registry::register_server(&manifest, &server_dir).await?;
+    proxy_server::register_sleeping_server_from_manifest(&manifest, &server_dir).await;
+
+    Ok(())
+}
+
+/// Deletes a stopped server and what minecraftd keeps about it, except for its backups. With
+/// `keep_world`, the world directories are left in place.
+///
+/// The metrics of the server cannot be deleted from the metrics storage, so they are hidden until
+/// they expire.
+pub async fn delete_server(server_dir: &Path, keep_world: bool) -> anyhow::Result<()> {
+    let server_dir = server_dir.canonicalize()?;
+    if runner::is_server_running(&server_dir).await? {
+        bail!("Cannot delete server while it is running");
+    }
+
+    let manifest = ServerManifest::load(&server_dir)
+        .await
+        .context("Failed to load server manifest")?;
+    let id = manifest.id;
+
+    runner::reset_by_server_dir(&server_dir).await;
+    auto_start::remove_auto_start_server(&server_dir).await?;
+    proxy_server::relocate_sleeping_server(&server_dir, None).await;
+    scheduler::relocate_scheduled_server(&server_dir, None).await;
+
+    if keep_world {
+        let worlds = backup::world_dirs(&server_dir).await;
+        let mut entries = tokio::fs::read_dir(&server_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if worlds
+                .iter()
+                .any(|world| entry.file_name() == world.as_str())
+            {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                tokio::fs::remove_dir_all(&path).await
+            } else {
+                tokio::fs::remove_file(&path).await
+            }
+            .with_context(|| format!("Failed to delete '{}'", path.display()))?;
+        }
+    } else {
+        tokio::fs::remove_dir_all(&server_dir)
+            .await
+            .context("Failed to delete server directory")?;
+    }
+
+    registry::unregister_server(id).await?;
+    runner::delete_logs(id).await?;
+    player_sessions::delete_player_sessions(id).await?;
+    update_rollback::delete_state(id).await?;
+    metrics::delete_server_metrics(id).await?;
+
+    info!("Deleted server {id} at '{}'", server_dir.display());
+
+    Ok(())
+}
+
+/// Copies a stopped server to `destination_dir` as a new server with its own ID, name and RCON
+/// password. A server behind the proxy also needs its own hostname, which is derived from the
+/// hostname of the original and the new name unless given.
+pub async fn clone_server(
+    server_dir: &Path,
+    destination_dir: &Path,
+    name: &str,
+    hostname: Option<&str>,
+) -> anyhow::Result<()> {
+    let server_dir = server_dir.canonicalize()?;
+    if runner::is_server_running(&server_dir).await? {
+        bail!("Cannot clone server while it is running");
+    }
+    if tokio::fs::try_exists(destination_dir).await? {
+        bail!("'{}' already exists", destination_dir.display());
+    }
+
+    let mut manifest = ServerManifest::load(&server_dir)
+        .await
+        .context("Failed to load server manifest")?;
+
+    registry::ensure_name_available(name).await?;
+    manifest.id = Uuid::new_v4();
+    manifest.name = name.to_string();
+
+    manifest.connection = match (&manifest.connection, hostname) {
+        (Connection::Direct, None) => Connection::Direct,
+        (Connection::Direct, Some(_)) => {
+            bail!("A hostname can only be given to servers behind the proxy")
+        }
+        (Connection::Proxy { hostname: original }, hostname) => {
+            let hostname = match hostname {
+                Some(hostname) => hostname.to_string(),
+                None => derive_hostname(original, name)?,
+            };
+            registry::ensure_hostname_available(&hostname).await?;
+            Connection::Proxy { hostname }
+        }
+    };
+
+    info!(
+        "Cloning server at '{}' to '{}'",
+        server_dir.display(),
+        destination_dir.display()
+    );
+
+    let source = server_dir.clone();
+    let destination = destination_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || util::fs::copy_dir_all(&source, &destination))
+        .await
+        .unwrap()?;
+
+    // a new password is generated when the server starts
+    if let Ok(mut server_properties) = ServerProperties::load(destination_dir).await {
+        server_properties.set("rcon.password", "");
+        server_properties.save(destination_dir).await?;
+    }
+
+    manifest
+        .save(destination_dir)
+        .await
+        .context("Failed to save server manifest")?;
+
+    let destination_dir = destination_dir.canonicalize()?;
+    registry::register_server(&manifest, &destination_dir).await?;
+    proxy_server::register_sleeping_server_from_manifest(&manifest, &destination_dir).await;
+
+    Ok(())
+}
+
+/// Replaces the first label of the original hostname with the name, e.g. `survival.example.com`
+/// becomes `creative.example.com` for a server named `creative`.
+fn derive_hostname(original: &str, name: &str) -> anyhow::Result<String> {
+    let label = name.to_ascii_lowercase();
+    let Some((_, domain)) = original.split_once('.') else {
+        bail!("Cannot derive a hostname from '{original}'. Specify the hostname of the copy.");
+    };
+    if label.is_empty()
+        || label.starts_with('-')
+        || label.ends_with('-')
+        || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        bail!("Cannot derive a hostname from the name '{name}'. Specify the hostname of the copy.");
+    }
+    Ok(format!("{label}.{domain}"))
+}
+
+/// Moves a stopped server to `destination_dir`, updating everything that refers to it by its
+/// directory.
+pub async fn move_server(server_dir: &Path, destination_dir: &Path) -> anyhow::Result<()> {
+    let server_dir = server_dir.canonicalize()?;
+    if runner::is_server_running(&server_dir).await? {
+        bail!("Cannot move server while it is running");
+    }
+    if tokio::fs::try_exists(destination_dir).await? {
+        bail!("'{}' already exists", destination_dir.display());
+    }
+
+    let manifest = ServerManifest::load(&server_dir)
+        .await
+        .context("Failed to load server manifest")?;
+
+    info!(
+        "Moving server at '{}' to '{}'",
+        server_dir.display(),
+        destination_dir.display()
+    );
+
+    match tokio::fs::rename(&server_dir, destination_dir).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let source = server_dir.clone();
+            let destination = destination_dir.to_path_buf();
+            tokio::task::spawn_blocking(move || util::fs::copy_dir_all(&source, &destination))
+                .await
+                .unwrap()?;
+            tokio::fs::remove_dir_all(&server_dir)
+                .await
+                .context("Failed to delete the original server directory")?;
+        }
+        Err(e) => return Err(e).context("Failed to move server directory"),
+    }
+    let destination_dir = destination_dir.canonicalize()?;
+
+    runner::reset_by_server_dir(&server_dir).await;
+    if auto_start::get_auto_start_servers()
+        .await
+        .contains(&server_dir)
+    {
+        auto_start::remove_auto_start_server(&server_dir).await?;
+        auto_start::add_auto_start_server(&destination_dir).await?;
+    }
+    proxy_server::relocate_sleeping_server(&server_dir, Some(&destination_dir)).await;
+    scheduler::relocate_scheduled_server(&server_dir, Some(&destination_dir)).await;
+    registry::register_server(&manifest, &destination_dir).await?;
+
     Ok(())
 }
 
@@ -91,6 +298,7 @@ pub async fn update_server(
         );
     };
 
+    let previous_manifest = manifest.clone();
     let old_version = manifest.version.clone();
     let old_build = manifest.build.clone();
 
@@ -114,6 +322,8 @@ pub async fn update_server(
         .await
         .context("Failed to prepare server jar for updated version")?;
 
+    update_rollback::prepare_update(server_dir, &previous_manifest).await?;
+
     manifest
         .save(server_dir)
         .await
@@ -259,3 +469,102 @@ pub async fn add_extension(
 
     Ok(AddExtensionResult { added_extensions })
 }
+
+pub struct AdoptExtensionsResult {
+    /// Path of the jar relative to the server directory and the extension it was replaced with.
+    pub adopted: Vec<(String, ExtensionInfo)>,
+    /// Paths relative to the server directory of the jars that no extension provider knows.
+    pub unmatched: Vec<String>,
+}
+
+/// Replaces the jars in `mods` and `plugins` that were put there by hand with the same versions
+/// of the extensions they were downloaded from, so that minecraftd can update them.
+///
+/// The jars are moved into the extension cache and are symlinked back on the next start.
+pub async fn adopt_extensions(
+    server_dir: &Path,
+    auto_update: bool,
+) -> anyhow::Result<AdoptExtensionsResult> {
+    if runner::is_server_running(server_dir).await? {
+        bail!("Cannot adopt extensions while the server is running");
+    }
+
+    let mut manifest = ServerManifest::load(server_dir)
+        .await
+        .context("Failed to load server manifest")?;
+
+    let mut adopted = Vec::new();
+    let mut unmatched = Vec::new();
+    let mut adopted_jars = Vec::new();
+
+    'jars: for (type_, path) in extension::unmanaged::find_unmanaged_jars(server_dir).await? {
+        let relative_path = path
+            .strip_prefix(server_dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let jar = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+
+        for provider in EXTENSION_PROVIDERS {
+            let Some(jar_match) = provider
+                .get_extension_version_by_jar(type_, &jar)
+                .await
+                .with_context(|| format!("Failed to look up '{relative_path}'"))?
+            else {
+                continue;
+            };
+
+            let extension_info = provider
+                .get_extension_info(type_, &jar_match.extension_id)
+                .await
+                .context("Failed to get extension info")?;
+
+            extension::cache::insert(
+                *provider,
+                type_,
+                &jar_match.extension_id,
+                &jar_match.extension_version_id,
+                &jar,
+            )
+            .await
+            .context("Failed to add extension jar to cache")?;
+
+            manifest
+                .extensions
+                .retain(|m| !(m.provider == provider.name() && m.id == jar_match.extension_id));
+            manifest.extensions.push(ExtensionEntry {
+                name: extension_info.name.clone(),
+                type_,
+                provider: provider.name().to_string(),
+                id: jar_match.extension_id,
+                version_id: jar_match.extension_version_id,
+                auto_update,
+            });
+
+            adopted.push((relative_path, extension_info));
+            adopted_jars.push(path);
+            continue 'jars;
+        }
+
+        unmatched.push(relative_path);
+    }
+
+    if adopted.is_empty() {
+        return Ok(AdoptExtensionsResult { adopted, unmatched });
+    }
+
+    manifest
+        .save(server_dir)
+        .await
+        .context("Failed to save updated server manifest")?;
+
+    for path in adopted_jars {
+        tokio::fs::remove_file(&path)
+            .await
+            .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+    }
+
+    Ok(AdoptExtensionsResult { adopted, unmatched })
+}
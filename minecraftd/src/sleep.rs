@@ -0,0 +1,63 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use crate::runner;
+
+const IDLE_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Stops servers that have had no players for their `sleep.idle_timeout`.
+pub fn init() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(IDLE_CHECK_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let mut idle_since = HashMap::<Uuid, Instant>::new();
+
+        loop {
+            interval.tick().await;
+            check_idle_servers(&mut idle_since).await;
+        }
+    });
+}
+
+async fn check_idle_servers(idle_since: &mut HashMap<Uuid, Instant>) {
+    let running_server_ids = runner::get_running_server_ids().await;
+    idle_since.retain(|id, _| running_server_ids.contains(id));
+
+    for id in running_server_ids {
+        let Some(idle_timeout) = runner::get_server_manifest(id)
+            .await
+            .and_then(|manifest| manifest.sleep.idle_timeout)
+        else {
+            idle_since.remove(&id);
+            continue;
+        };
+
+        // servers that are starting or cannot be pinged are not considered idle
+        if runner::get_online_players(id).await != Some(0) {
+            idle_since.remove(&id);
+            continue;
+        }
+
+        let since = *idle_since.entry(id).or_insert_with(Instant::now);
+        if since.elapsed() < idle_timeout {
+            continue;
+        }
+
+        idle_since.remove(&id);
+        info!(
+            "Stopping server {id} since it has had no players for {}s",
+            since.elapsed().as_secs()
+        );
+
+        tokio::spawn(async move {
+            if let Err(err) = runner::stop_idle_server(id).await {
+                error!("Failed to stop idle server {id}: {err:?}");
+            }
+        });
+    }
+}
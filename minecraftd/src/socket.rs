@@ -1,14 +1,20 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use anyhow::{Context, bail};
 use mcctl_protocol::*;
 use minecraftd_manifest::{Connection, ServerManifest};
 
 use crate::{
+    backup,
     extension::providers::{EXTENSION_PROVIDERS, get_extension_provider},
+    import,
     metrics::{self, MetricsQuery},
+    player_lists, player_sessions, proxy_server, registry,
     runner::{self, TerminalReader, TerminalWriter},
-    server,
+    scheduler, server,
     server_implementations::{SERVER_IMPLEMENTATIONS, get_server_implementation},
 };
 
@@ -125,38 +131,213 @@ impl mcctl_protocol::server::RequestHandler<anyhow::Error, TerminalReader, Termi
 
     async fn attach_terminal(
         server_dir: &Path,
+        scrollback_lines: Option<u32>,
+        read_only: bool,
     ) -> Result<(TerminalReader, TerminalWriter), anyhow::Error> {
         if !server_dir.is_absolute() {
             bail!("server_dir must be absolute");
         }
 
-        runner::attach_terminal(server_dir).await
+        runner::attach_terminal(
+            server_dir,
+            scrollback_lines.unwrap_or(0) as usize,
+            read_only,
+        )
+        .await
     }
 
     async fn get_running_servers() -> anyhow::Result<Vec<RunningServer>> {
         let servers = runner::get_running_servers().await;
+        let crash_looping_servers = runner::get_crash_looping_servers().await;
 
-        Ok(servers
+        let mut running_servers = servers
             .into_iter()
             .map(|s| RunningServer {
                 server_dir: s.server_dir.to_string_lossy().to_string(),
                 name: s.name,
-                status: match s.status {
-                    runner::ServerStatus::Starting { restarting: false } => ServerStatus::Starting,
-                    runner::ServerStatus::Ready => ServerStatus::Ready,
-                    runner::ServerStatus::Stopping { restarting: false } => ServerStatus::Stopping,
-                    runner::ServerStatus::Starting { restarting: true }
-                    | runner::ServerStatus::Stopping { restarting: true } => {
-                        ServerStatus::Restarting
-                    }
-                    runner::ServerStatus::Stopped => unreachable!(),
-                } as i32,
+                status: server_status(s.status) as i32,
                 uptime_seconds: s.uptime.as_secs(),
                 port: s.server_port as u32,
                 player_count: s.players.as_ref().map(|p| p.online),
                 max_players: s.players.as_ref().map(|p| p.max),
+                restart_in_seconds: None,
+                health: match s.health {
+                    runner::ServerHealth::Unknown => None,
+                    runner::ServerHealth::Healthy { .. } => Some(ServerHealth::Healthy as i32),
+                    runner::ServerHealth::Unhealthy { .. } => Some(ServerHealth::Unhealthy as i32),
+                },
+                failed_probes: match s.health {
+                    runner::ServerHealth::Unknown => 0,
+                    runner::ServerHealth::Healthy { failed_probes }
+                    | runner::ServerHealth::Unhealthy { failed_probes } => failed_probes,
+                },
             })
-            .collect())
+            .collect::<Vec<_>>();
+
+        running_servers.extend(crash_looping_servers.into_iter().map(|s| RunningServer {
+            server_dir: s.server_dir.to_string_lossy().to_string(),
+            name: s.name,
+            status: match s.state {
+                runner::CrashLoopState::Failed => ServerStatus::Failed,
+                _ => ServerStatus::BackingOff,
+            } as i32,
+            uptime_seconds: 0,
+            port: 0,
+            player_count: None,
+            max_players: None,
+            restart_in_seconds: s.state.restart_in().map(|d| d.as_secs()),
+            health: None,
+            failed_probes: 0,
+        }));
+
+        Ok(running_servers)
+    }
+
+    async fn register_server(server_dir: &Path) -> anyhow::Result<()> {
+        if !server_dir.is_absolute() {
+            bail!("server_dir must be absolute");
+        }
+
+        let server_dir = server_dir.canonicalize()?;
+        let manifest = ServerManifest::load(&server_dir).await?;
+
+        registry::register_server(&manifest, &server_dir).await?;
+        proxy_server::register_sleeping_server_from_manifest(&manifest, &server_dir).await;
+
+        Ok(())
+    }
+
+    async fn resolve_server(server: &str) -> anyhow::Result<PathBuf> {
+        registry::resolve_server(server).await
+    }
+
+    async fn delete_server(server_dir: &Path, keep_world: bool) -> anyhow::Result<()> {
+        if !server_dir.is_absolute() {
+            bail!("server_dir must be absolute");
+        }
+
+        server::delete_server(server_dir, keep_world).await
+    }
+
+    async fn clone_server(
+        server_dir: &Path,
+        destination_dir: &Path,
+        name: &str,
+        hostname: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if !server_dir.is_absolute() || !destination_dir.is_absolute() {
+            bail!("server_dir and destination_dir must be absolute");
+        }
+
+        server::clone_server(server_dir, destination_dir, name, hostname).await
+    }
+
+    async fn move_server(server_dir: &Path, destination_dir: &Path) -> anyhow::Result<()> {
+        if !server_dir.is_absolute() || !destination_dir.is_absolute() {
+            bail!("server_dir and destination_dir must be absolute");
+        }
+
+        server::move_server(server_dir, destination_dir).await
+    }
+
+    async fn import_server(
+        server_dir: &Path,
+        name: &str,
+        connection: ConnectionType,
+        hostname: Option<&str>,
+        jar: Option<&str>,
+    ) -> anyhow::Result<ImportServerResponse> {
+        if !server_dir.is_absolute() {
+            bail!("server_dir must be absolute");
+        }
+
+        let connection = match connection {
+            mcctl_protocol::ConnectionType::Direct => Connection::Direct,
+            mcctl_protocol::ConnectionType::Proxy => Connection::Proxy {
+                hostname: hostname
+                    .context("Hostname must be provided for proxy connection")?
+                    .to_string(),
+            },
+        };
+
+        let result = import::import_server(server_dir, name, connection, jar).await?;
+
+        Ok(ImportServerResponse {
+            server_implementation: result.server_implementation,
+            version: result.version,
+            build: result.build,
+            unmanaged_jars: result.unmanaged_jars,
+            build_assumed: result.build_assumed,
+        })
+    }
+
+    async fn adopt_extensions(
+        server_dir: &Path,
+        auto_update: bool,
+    ) -> anyhow::Result<AdoptExtensionsResponse> {
+        if !server_dir.is_absolute() {
+            bail!("server_dir must be absolute");
+        }
+
+        let result = server::adopt_extensions(server_dir, auto_update).await?;
+
+        Ok(AdoptExtensionsResponse {
+            adopted: result
+                .adopted
+                .into_iter()
+                .map(|(path, extension)| AdoptedExtension {
+                    path,
+                    extension: Some(ExtensionInfo {
+                        id: extension.id,
+                        name: extension.name,
+                    }),
+                })
+                .collect(),
+            unmatched: result.unmatched,
+        })
+    }
+
+    async fn list_servers() -> anyhow::Result<Vec<ServerInfo>> {
+        let crash_looping_servers = runner::get_crash_looping_servers().await;
+
+        let mut servers = Vec::new();
+        for (id, registered) in registry::get_registered_servers().await? {
+            let manifest = ServerManifest::load(&registered.server_dir).await.ok();
+
+            let status = match runner::get_server_status(id).await {
+                Some(status) => Some(server_status(status)),
+                None => crash_looping_servers
+                    .iter()
+                    .find(|s| s.server_dir == registered.server_dir)
+                    .map(|s| match s.state {
+                        runner::CrashLoopState::Failed => ServerStatus::Failed,
+                        _ => ServerStatus::BackingOff,
+                    }),
+            };
+
+            servers.push(ServerInfo {
+                id: id.to_string(),
+                server_dir: registered.server_dir.to_string_lossy().to_string(),
+                manifest: manifest.map(|manifest| ServerManifestInfo {
+                    name: manifest.name,
+                    server_implementation: manifest.server_implementation,
+                    version: manifest.version,
+                    build: manifest.build,
+                    connection: match manifest.connection {
+                        Connection::Direct => ConnectionType::Direct,
+                        Connection::Proxy { .. } => ConnectionType::Proxy,
+                    } as i32,
+                    hostname: match manifest.connection {
+                        Connection::Direct => None,
+                        Connection::Proxy { hostname } => Some(hostname),
+                    },
+                }),
+                status: status.map(|status| status as i32),
+            });
+        }
+
+        servers.sort_by(|a, b| a.server_dir.cmp(&b.server_dir));
+        Ok(servers)
     }
 
     async fn wait_ready(server_dir: &Path) -> anyhow::Result<()> {
@@ -170,13 +351,16 @@ impl mcctl_protocol::server::RequestHandler<anyhow::Error, TerminalReader, Termi
         Ok(())
     }
 
-    async fn restart_server(server_dir: &Path) -> anyhow::Result<()> {
+    async fn restart_server(server_dir: &Path, countdown: Option<Duration>) -> anyhow::Result<()> {
         let server_dir = Path::new(&server_dir);
         if !server_dir.is_absolute() {
             bail!("server_dir must be absolute");
         }
 
-        runner::restart_server(server_dir).await?;
+        match countdown {
+            Some(countdown) => runner::restart_server_with_countdown(server_dir, countdown).await?,
+            None => runner::restart_server(server_dir).await?,
+        }
 
         Ok(())
     }
@@ -376,6 +560,221 @@ impl mcctl_protocol::server::RequestHandler<anyhow::Error, TerminalReader, Termi
                 .collect(),
         })
     }
+
+    async fn list_backups(server_dir: &Path) -> anyhow::Result<Vec<BackupInfo>> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        Ok(backup::list_backups(manifest.id)
+            .await?
+            .iter()
+            .map(backup_info)
+            .collect())
+    }
+
+    async fn create_backup(server_dir: &Path) -> anyhow::Result<BackupInfo> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        let backup =
+            backup::create_backup(server_dir, manifest.id, backup::MANUAL_BACKUP_LABEL).await?;
+
+        Ok(backup_info(&backup))
+    }
+
+    async fn restore_backup(
+        server_dir: &Path,
+        backup_id: &str,
+    ) -> anyhow::Result<Option<BackupInfo>> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        let safety_backup = backup::restore_backup(server_dir, manifest.id, backup_id).await?;
+
+        Ok(safety_backup.as_ref().map(backup_info))
+    }
+
+    async fn delete_backup(server_dir: &Path, backup_id: &str) -> anyhow::Result<()> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        backup::delete_backup(manifest.id, backup_id).await
+    }
+
+    async fn verify_backups(
+        server_dir: &Path,
+        backup_id: Option<&str>,
+    ) -> anyhow::Result<Vec<BackupVerificationResult>> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        Ok(backup::verify_backups(manifest.id, backup_id)
+            .await?
+            .into_iter()
+            .map(|result| BackupVerificationResult {
+                backup_id: result.backup_id,
+                checked_chunks: result.checked_chunks as u32,
+                missing_chunks: result.missing_chunks,
+                corrupt_chunks: result.corrupt_chunks,
+            })
+            .collect())
+    }
+
+    async fn list_schedules(server_dir: &Path) -> anyhow::Result<ListSchedulesResponse> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        Ok(ListSchedulesResponse {
+            tasks: manifest
+                .schedules
+                .iter()
+                .map(|task| ScheduledTaskInfo {
+                    name: task.name.clone(),
+                    cron: task.cron.to_string(),
+                    action: scheduler::describe_action(&task.action),
+                    next_run: scheduler::next_run(task).map(|t| t.timestamp()),
+                })
+                .collect(),
+            active: scheduler::is_server_scheduled(server_dir).await,
+        })
+    }
+
+    async fn read_logs(
+        server_dir: &Path,
+        since_timestamp_millis: Option<i64>,
+        lines: Option<u32>,
+        grep: Option<&str>,
+        strip_ansi: bool,
+        follow: bool,
+    ) -> anyhow::Result<tokio::sync::mpsc::Receiver<LogLine>> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        let grep = grep
+            .map(regex::Regex::new)
+            .transpose()
+            .context("Invalid grep pattern")?;
+
+        runner::read_logs(
+            manifest.id,
+            runner::LogQuery {
+                since_timestamp_millis,
+                lines: lines.map(|l| l as usize),
+                grep,
+                strip_ansi,
+                follow,
+            },
+        )
+        .await
+    }
+
+    async fn execute_command(server_dir: &Path, command: &str) -> anyhow::Result<String> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        runner::execute_command(manifest.id, command).await
+    }
+
+    async fn get_player_sessions(
+        server_dir: &Path,
+        online_only: bool,
+        since: Option<i64>,
+        player: Option<&str>,
+    ) -> anyhow::Result<Vec<mcctl_protocol::PlayerSession>> {
+        let manifest = ServerManifest::load(server_dir).await?;
+
+        // sessions left open by a server that is not running end when it starts again
+        if online_only && !runner::is_server_running(server_dir).await? {
+            return Ok(Vec::new());
+        }
+
+        Ok(player_sessions::get_player_sessions(manifest.id)
+            .await?
+            .into_iter()
+            .filter(|session| !online_only || session.left_at.is_none())
+            .filter(|session| {
+                since.is_none_or(|since| {
+                    session
+                        .left_at
+                        .is_none_or(|left_at| left_at.timestamp() >= since)
+                })
+            })
+            .filter(|session| {
+                player.is_none_or(|player| {
+                    session.name.eq_ignore_ascii_case(player)
+                        || session
+                            .uuid
+                            .is_some_and(|uuid| Ok(uuid) == player.parse::<uuid::Uuid>())
+                })
+            })
+            .map(|session| mcctl_protocol::PlayerSession {
+                uuid: session.uuid.map(|uuid| uuid.to_string()),
+                name: session.name,
+                joined_at: session.joined_at.timestamp(),
+                left_at: session.left_at.map(|left_at| left_at.timestamp()),
+                address: session.address.map(|address| address.to_string()),
+            })
+            .collect())
+    }
+
+    async fn get_player_list(
+        server_dir: &Path,
+        list_type: PlayerListType,
+    ) -> anyhow::Result<Vec<mcctl_protocol::PlayerListEntry>> {
+        Ok(
+            player_lists::get_player_list(server_dir, player_list_type(list_type))
+                .await?
+                .into_iter()
+                .map(|entry| mcctl_protocol::PlayerListEntry {
+                    uuid: entry.uuid,
+                    name: entry.name,
+                    level: entry.level,
+                    reason: entry.reason,
+                    expires: entry.expires,
+                })
+                .collect(),
+        )
+    }
+
+    async fn add_player_list_entry(
+        server_dir: &Path,
+        list_type: PlayerListType,
+        player: &str,
+        reason: Option<&str>,
+    ) -> anyhow::Result<String> {
+        player_lists::add_player_list_entry(server_dir, player_list_type(list_type), player, reason)
+            .await
+    }
+
+    async fn remove_player_list_entry(
+        server_dir: &Path,
+        list_type: PlayerListType,
+        player: &str,
+    ) -> anyhow::Result<String> {
+        player_lists::remove_player_list_entry(server_dir, player_list_type(list_type), player)
+            .await
+    }
+}
+
+fn player_list_type(list_type: PlayerListType) -> player_lists::PlayerListType {
+    match list_type {
+        PlayerListType::Whitelist => player_lists::PlayerListType::Whitelist,
+        PlayerListType::Ops => player_lists::PlayerListType::Ops,
+        PlayerListType::BannedPlayers => player_lists::PlayerListType::BannedPlayers,
+    }
+}
+
+/// Never called with `runner::ServerStatus::Stopped`.
+fn server_status(status: runner::ServerStatus) -> ServerStatus {
+    match status {
+        runner::ServerStatus::Starting { restarting: false } => ServerStatus::Starting,
+        runner::ServerStatus::Ready => ServerStatus::Ready,
+        runner::ServerStatus::Stopping { restarting: false } => ServerStatus::Stopping,
+        runner::ServerStatus::Starting { restarting: true }
+        | runner::ServerStatus::Stopping { restarting: true } => ServerStatus::Restarting,
+        runner::ServerStatus::Stopped => unreachable!(),
+    }
+}
+
+fn backup_info(backup: &backup::Backup) -> BackupInfo {
+    BackupInfo {
+        id: backup.id.clone(),
+        label: backup.metadata.label.clone(),
+        created_at: backup.metadata.created_at.timestamp(),
+        worlds: backup.metadata.worlds.clone(),
+    }
 }
 
 pub async fn start_server() -> anyhow::Result<()> {
@@ -0,0 +1,106 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+const TAG_HELLO: u8 = 0;
+const TAG_OUTPUT: u8 = 1;
+const TAG_EXITED: u8 = 2;
+const TAG_INPUT: u8 = 3;
+const TAG_RESIZE: u8 = 4;
+
+/// Messages exchanged between the daemon and a supervisor. Each frame is a tag byte followed by
+/// a big-endian u32 payload length and the payload.
+#[derive(Debug)]
+pub enum Frame {
+    /// Sent by the supervisor when the daemon connects.
+    Hello {
+        pid: u32,
+    },
+    /// Output of the server console.
+    Output(Vec<u8>),
+    /// Sent by the supervisor when the server process has exited, with the raw wait status.
+    Exited {
+        raw_status: i32,
+    },
+    /// Input to the server console.
+    Input(Vec<u8>),
+    Resize {
+        cols: u16,
+        rows: u16,
+    },
+}
+
+impl Frame {
+    /// Returns `None` if the stream was closed between frames.
+    pub async fn read(reader: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Option<Self>> {
+        let tag = match reader.read_u8().await {
+            Ok(tag) => tag,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let len = reader.read_u32().await? as usize;
+        if len > MAX_FRAME_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Frame too large: {len} bytes"),
+            ));
+        }
+        let mut payload = vec![0; len];
+        reader.read_exact(&mut payload).await?;
+
+        let invalid_payload = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid payload for frame with tag {tag}"),
+            )
+        };
+
+        let frame = match tag {
+            TAG_HELLO => Frame::Hello {
+                pid: u32::from_be_bytes(payload.try_into().map_err(|_| invalid_payload())?),
+            },
+            TAG_OUTPUT => Frame::Output(payload),
+            TAG_EXITED => Frame::Exited {
+                raw_status: i32::from_be_bytes(payload.try_into().map_err(|_| invalid_payload())?),
+            },
+            TAG_INPUT => Frame::Input(payload),
+            TAG_RESIZE => {
+                let payload: [u8; 4] = payload.try_into().map_err(|_| invalid_payload())?;
+                Frame::Resize {
+                    cols: u16::from_be_bytes([payload[0], payload[1]]),
+                    rows: u16::from_be_bytes([payload[2], payload[3]]),
+                }
+            }
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown frame tag {tag}"),
+                ));
+            }
+        };
+
+        Ok(Some(frame))
+    }
+
+    pub async fn write(&self, writer: &mut (impl AsyncWrite + Unpin)) -> std::io::Result<()> {
+        let (tag, payload): (u8, &[u8]) = match self {
+            Frame::Hello { pid } => (TAG_HELLO, &pid.to_be_bytes()),
+            Frame::Output(content) => (TAG_OUTPUT, content),
+            Frame::Exited { raw_status } => (TAG_EXITED, &raw_status.to_be_bytes()),
+            Frame::Input(content) => (TAG_INPUT, content),
+            Frame::Resize { cols, rows } => {
+                let [c0, c1] = cols.to_be_bytes();
+                let [r0, r1] = rows.to_be_bytes();
+                (TAG_RESIZE, &[c0, c1, r0, r1])
+            }
+        };
+
+        let mut buf = Vec::with_capacity(5 + payload.len());
+        buf.push(tag);
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(payload);
+        writer.write_all(&buf).await?;
+        writer.flush().await
+    }
+}
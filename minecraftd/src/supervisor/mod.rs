@@ -0,0 +1,162 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, bail};
+use tokio::net::{
+    UnixStream,
+    unix::{OwnedReadHalf, OwnedWriteHalf},
+};
+use uuid::Uuid;
+
+pub use frame::Frame;
+
+mod frame;
+mod process;
+
+pub const PTY_DEFAULT_ROWS: u16 = 24;
+pub const PTY_DEFAULT_COLS: u16 = 80;
+
+const SOCKET_FILE_NAME: &str = "supervisor.sock";
+const LOG_FILE_NAME: &str = "supervisor.log";
+const SPAWN_TIMEOUT_SECS: u64 = 10;
+const SPAWN_POLL_INTERVAL_MILLIS: u64 = 50;
+
+/// Connection from the daemon to the supervisor of a running server.
+pub struct Supervisor {
+    pub pid: u32,
+    reader: OwnedReadHalf,
+    writer: OwnedWriteHalf,
+}
+
+impl Supervisor {
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        (self.reader, self.writer)
+    }
+}
+
+/// Runs the supervisor if the daemon was invoked as `minecraftd supervise`. Returns `None`
+/// otherwise.
+pub async fn run_if_requested() -> Option<anyhow::Result<()>> {
+    let args = std::env::args_os().collect::<Vec<_>>();
+    if args.get(1).is_none_or(|arg| arg != "supervise") {
+        return None;
+    }
+
+    let Some(state_dir) = args.get(2) else {
+        return Some(Err(anyhow::anyhow!(
+            "Usage: minecraftd supervise <state dir> <command>..."
+        )));
+    };
+
+    Some(process::run(Path::new(state_dir), &args[3..]).await)
+}
+
+/// Directory holding the state of all servers that have a supervisor.
+pub fn servers_dir() -> anyhow::Result<PathBuf> {
+    let mut path =
+        dirs::runtime_dir().context("XDG_RUNTIME_DIR environment variable is not set")?;
+    path.push("minecraftd");
+    path.push("servers");
+    Ok(path)
+}
+
+pub fn state_dir(id: Uuid) -> anyhow::Result<PathBuf> {
+    Ok(servers_dir()?.join(id.to_string()))
+}
+
+/// Starts the server process under a new supervisor and connects to it.
+pub async fn spawn(
+    id: Uuid,
+    server_dir: &Path,
+    command: &[OsString],
+) -> anyhow::Result<Supervisor> {
+    let state_dir = state_dir(id)?;
+    let _ = tokio::fs::remove_dir_all(&state_dir).await;
+    tokio::fs::create_dir_all(&state_dir)
+        .await
+        .context("Failed to create supervisor state directory")?;
+
+    let log_path = state_dir.join(LOG_FILE_NAME);
+    let log_file = std::fs::File::create(&log_path).context("Failed to create supervisor log")?;
+
+    debug!("Starting command under supervisor: {:?}", command);
+
+    let mut child = tokio::process::Command::new(
+        std::env::current_exe().context("Failed to get path of the minecraftd executable")?,
+    )
+    .arg("supervise")
+    .arg(&state_dir)
+    .args(command)
+    .current_dir(server_dir)
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(log_file)
+    // keep the supervisor out of the daemon's process group so that it survives signals sent to it
+    .process_group(0)
+    .spawn()
+    .context("Failed to spawn supervisor")?;
+
+    let deadline = Instant::now() + Duration::from_secs(SPAWN_TIMEOUT_SECS);
+    loop {
+        if let Ok(supervisor) = connect(&state_dir).await {
+            debug!(
+                "Spawned server process with PID {} under supervisor",
+                supervisor.pid
+            );
+
+            // reap the supervisor once it exits
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+
+            return Ok(supervisor);
+        }
+
+        if let Some(status) = child.try_wait()? {
+            bail!(
+                "Supervisor exited with {status}. See '{}' for details",
+                log_path.display()
+            );
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill().await;
+            bail!(
+                "Supervisor did not start in time. See '{}' for details",
+                log_path.display()
+            );
+        }
+
+        tokio::time::sleep(Duration::from_millis(SPAWN_POLL_INTERVAL_MILLIS)).await;
+    }
+}
+
+/// Connects to the supervisor whose state is in `state_dir`.
+pub async fn connect(state_dir: &Path) -> anyhow::Result<Supervisor> {
+    let stream = UnixStream::connect(state_dir.join(SOCKET_FILE_NAME))
+        .await
+        .context("Failed to connect to supervisor")?;
+    let (mut reader, writer) = stream.into_split();
+
+    match Frame::read(&mut reader).await? {
+        Some(Frame::Hello { pid }) => Ok(Supervisor {
+            pid,
+            reader,
+            writer,
+        }),
+        frame => bail!("Expected hello from supervisor, got {frame:?}"),
+    }
+}
+
+pub async fn remove_state_dir(id: Uuid) -> anyhow::Result<()> {
+    let state_dir = state_dir(id)?;
+    match tokio::fs::remove_dir_all(&state_dir).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context("Failed to remove supervisor state directory"),
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    collections::VecDeque, ffi::OsString, os::unix::process::ExitStatusExt, path::Path,
+    time::Duration,
+};
+
+use anyhow::Context;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{UnixListener, unix::OwnedWriteHalf},
+    signal::unix::{SignalKind, signal},
+    sync::mpsc,
+};
+
+use crate::supervisor::{PTY_DEFAULT_COLS, PTY_DEFAULT_ROWS, SOCKET_FILE_NAME, frame::Frame};
+
+/// Output produced while no daemon is connected is kept up to this size.
+const OUTPUT_BACKLOG_SIZE: usize = 1024 * 1024;
+/// How long an exited supervisor waits for a daemon to collect the exit status.
+const EXIT_STATUS_KEEP_SECS: u64 = 60 * 60;
+const PTY_READ_BUFFER_SIZE: usize = 4096;
+
+/// Entry point of `minecraftd supervise <state dir> <command>...`. Runs the server process on a
+/// PTY and relays its console to whichever daemon is connected, so that the server keeps running
+/// while the daemon restarts.
+pub async fn run(state_dir: &Path, command: &[OsString]) -> anyhow::Result<()> {
+    let program = command.first().context("command is empty")?;
+
+    let (pty, pts) = pty_process::open().context("Failed to open PTY")?;
+    pty.resize(pty_process::Size::new(PTY_DEFAULT_ROWS, PTY_DEFAULT_COLS))
+        .context("Failed to set PTY size")?;
+
+    let mut child = pty_process::Command::new(program)
+        .args(&command[1..])
+        .spawn(pts)
+        .context("Failed to spawn server process")?;
+    let pid = child.id().context("Failed to get child process ID")?;
+
+    info!("Spawned server process with PID {pid}");
+
+    // the daemon waits for the socket to appear, so it is bound only after the server is spawned
+    let socket_path = state_dir.join(SOCKET_FILE_NAME);
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Failed to bind to {}", socket_path.display()))?;
+
+    let (mut pty_reader, mut pty_writer) = pty.into_split();
+
+    let mut sigterm =
+        signal(SignalKind::terminate()).context("Failed to set up SIGTERM handler")?;
+    let mut sigint = signal(SignalKind::interrupt()).context("Failed to set up SIGINT handler")?;
+    let mut sighup = signal(SignalKind::hangup()).context("Failed to set up SIGHUP handler")?;
+
+    let mut connection: Option<(OwnedWriteHalf, mpsc::Receiver<Frame>)> = None;
+    let mut backlog = VecDeque::<u8>::new();
+    let mut pty_open = true;
+    let mut exit_status = None;
+    let exit_status_deadline = tokio::time::sleep(Duration::MAX);
+    tokio::pin!(exit_status_deadline);
+
+    let mut buf = [0u8; PTY_READ_BUFFER_SIZE];
+
+    loop {
+        tokio::select! {
+            result = listener.accept() => {
+                let (stream, _) = match result {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        error!("Failed to accept connection: {e:?}");
+                        continue;
+                    }
+                };
+
+                info!("Daemon connected");
+
+                let (mut reader, mut writer) = stream.into_split();
+                let (frame_tx, frame_rx) = mpsc::channel(16);
+                tokio::spawn(async move {
+                    while let Ok(Some(frame)) = Frame::read(&mut reader).await {
+                        if frame_tx.send(frame).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                let result = async {
+                    Frame::Hello { pid }.write(&mut writer).await?;
+                    if !backlog.is_empty() {
+                        Frame::Output(backlog.drain(..).collect()).write(&mut writer).await?;
+                    }
+                    if let Some(raw_status) = exit_status {
+                        Frame::Exited { raw_status }.write(&mut writer).await?;
+                    }
+                    std::io::Result::Ok(())
+                }
+                .await;
+
+                match result {
+                    Ok(()) if exit_status.is_some() => break,
+                    Ok(()) => connection = Some((writer, frame_rx)),
+                    Err(e) => warn!("Failed to write to daemon: {e:?}"),
+                }
+            }
+            result = pty_reader.read(&mut buf), if pty_open => {
+                let n = match result {
+                    Ok(0) | Err(_) => { // I/O error on process exit
+                        pty_open = false;
+                        continue;
+                    }
+                    Ok(n) => n,
+                };
+
+                if let Some((writer, _)) = &mut connection {
+                    if let Err(e) = Frame::Output(buf[..n].to_vec()).write(writer).await {
+                        warn!("Failed to write to daemon, disconnecting: {e:?}");
+                        connection = None;
+                        push_backlog(&mut backlog, &buf[..n]);
+                    }
+                } else {
+                    push_backlog(&mut backlog, &buf[..n]);
+                }
+            }
+            frame = async { connection.as_mut().unwrap().1.recv().await }, if connection.is_some() => {
+                let result = match frame {
+                    Some(Frame::Input(content)) => pty_writer.write_all(&content).await,
+                    Some(Frame::Resize { cols, rows }) => pty_writer
+                        .resize(pty_process::Size::new(rows, cols))
+                        .map_err(std::io::Error::other),
+                    Some(frame) => {
+                        warn!("Unexpected frame from daemon: {frame:?}");
+                        Ok(())
+                    }
+                    None => {
+                        info!("Daemon disconnected");
+                        connection = None;
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    error!("Failed to write to PTY: {e:?}");
+                }
+            }
+            status = child.wait(), if exit_status.is_none() => {
+                let status = status.context("Failed to wait for server process")?;
+                info!("Server process exited with status: {status}");
+
+                // forward the last output before reporting the exit
+                while pty_open {
+                    match tokio::time::timeout(Duration::from_millis(100), pty_reader.read(&mut buf)).await {
+                        Ok(Ok(n)) if n > 0 => push_backlog(&mut backlog, &buf[..n]),
+                        _ => pty_open = false,
+                    }
+                }
+
+                let raw_status = status.into_raw();
+                exit_status = Some(raw_status);
+
+                if let Some((mut writer, _)) = connection.take() {
+                    let result = async {
+                        if !backlog.is_empty() {
+                            Frame::Output(backlog.drain(..).collect()).write(&mut writer).await?;
+                        }
+                        Frame::Exited { raw_status }.write(&mut writer).await
+                    }
+                    .await;
+                    match result {
+                        Ok(()) => break,
+                        Err(e) => warn!("Failed to report exit status to daemon: {e:?}"),
+                    }
+                }
+
+                exit_status_deadline
+                    .as_mut()
+                    .reset(tokio::time::Instant::now() + Duration::from_secs(EXIT_STATUS_KEEP_SECS));
+            }
+            _ = &mut exit_status_deadline => {
+                warn!("No daemon collected the exit status, exiting");
+                break;
+            }
+            _ = sigterm.recv() => request_stop(&mut pty_writer).await,
+            _ = sigint.recv() => request_stop(&mut pty_writer).await,
+            _ = sighup.recv() => request_stop(&mut pty_writer).await,
+        }
+    }
+
+    let _ = std::fs::remove_file(&socket_path);
+
+    Ok(())
+}
+
+/// Stops the server gracefully when the supervisor itself is asked to terminate, e.g. on system
+/// shutdown.
+async fn request_stop(pty_writer: &mut pty_process::OwnedWritePty) {
+    info!("Received termination signal, stopping server");
+    if let Err(e) = pty_writer.write_all(b"stop\n").await {
+        error!("Failed to send stop command: {e:?}");
+    }
+}
+
+fn push_backlog(backlog: &mut VecDeque<u8>, content: &[u8]) {
+    backlog.extend(content);
+    if backlog.len() > OUTPUT_BACKLOG_SIZE {
+        let excess = backlog.len() - OUTPUT_BACKLOG_SIZE;
+        backlog.drain(..excess);
+    }
+}
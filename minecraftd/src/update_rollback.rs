@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use minecraftd_manifest::ServerManifest;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    alert::{Alert, Severity, send_alert},
+    backup,
+    config::get_config,
+};
+
+const PRE_UPDATE_BACKUP_LABEL: &str = "pre-update";
+const STATE_FILE_NAME: &str = "state.json";
+
+static STATE_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RollbackState {
+    /// Set from the moment an update is applied until the updated server has run stably.
+    pending_update: Option<PendingUpdate>,
+    /// Updates that were rolled back. They are not applied again by auto-update.
+    #[serde(default)]
+    failed_updates: Vec<FailedUpdate>,
+}
+
+impl RollbackState {
+    /// A version that fails again is only recorded once.
+    fn add_failed_update(&mut self, failed_update: FailedUpdate) {
+        if !self.failed_updates.contains(&failed_update) {
+            self.failed_updates.push(failed_update);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingUpdate {
+    updated_at: DateTime<Utc>,
+    /// World backup taken before the update, if the server had a world
+    backup_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FailedUpdate {
+    Server {
+        version: String,
+        build: String,
+    },
+    Extension {
+        provider: String,
+        id: String,
+        version_id: String,
+    },
+}
+
+/// The previous manifest is kept as `minecraftd.yaml` next to the state file.
+fn rollback_dir(server_id: Uuid) -> anyhow::Result<PathBuf> {
+    let mut path = dirs::data_dir().context("Failed to get data directory")?;
+    path.push("minecraftd");
+    path.push("rollback");
+    path.push(server_id.to_string());
+    Ok(path)
+}
+
+async fn load_state(server_id: Uuid) -> anyhow::Result<RollbackState> {
+    let path = rollback_dir(server_id)?.join(STATE_FILE_NAME);
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str(&content).context("Failed to parse rollback state"),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(RollbackState::default()),
+        Err(err) => Err(err).context("Failed to read rollback state"),
+    }
+}
+
+async fn save_state(server_id: Uuid, state: &RollbackState) -> anyhow::Result<()> {
+    let dir = rollback_dir(server_id)?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .context("Failed to create rollback directory")?;
+    tokio::fs::write(dir.join(STATE_FILE_NAME), serde_json::to_string(state)?)
+        .await
+        .context("Failed to write rollback state")?;
+    Ok(())
+}
+
+/// Snapshots the manifest and world of a stopped server before an update is applied to it.
+///
+/// If an earlier update has not been confirmed yet, its snapshot is kept, since it is the last
+/// state known to work.
+pub async fn prepare_update(server_dir: &Path, previous: &ServerManifest) -> anyhow::Result<()> {
+    if !get_config().update_rollback.enabled {
+        return Ok(());
+    }
+
+    let _lock = STATE_LOCK.lock().await;
+
+    let mut state = load_state(previous.id).await?;
+    if state.pending_update.is_some() {
+        debug!("An earlier update is still pending, keeping its snapshot");
+        return Ok(());
+    }
+
+    let backup_id = if backup::world_dirs(server_dir).await.is_empty() {
+        None
+    } else {
+        let backup = backup::create_backup(server_dir, previous.id, PRE_UPDATE_BACKUP_LABEL)
+            .await
+            .context("Failed to back up the world before updating")?;
+
+        // the backup just taken is the one a rollback restores, so it is always kept
+        if let Err(err) = backup::prune_backups(
+            previous.id,
+            PRE_UPDATE_BACKUP_LABEL,
+            get_config().update_rollback.keep_backups.max(1),
+        )
+        .await
+        {
+            error!(
+                "Failed to prune pre-update backups for server at '{}': {err:?}",
+                server_dir.display()
+            );
+        }
+
+        Some(backup.id)
+    };
+
+    previous
+        .save(&rollback_dir(previous.id)?)
+        .await
+        .context("Failed to save the previous manifest")?;
+
+    state.pending_update = Some(PendingUpdate {
+        updated_at: Utc::now(),
+        backup_id,
+    });
+    save_state(previous.id, &state).await?;
+
+    info!(
+        "Saved snapshot of server at '{}' before updating",
+        server_dir.display()
+    );
+
+    Ok(())
+}
+
+pub async fn is_update_pending(server_id: Uuid) -> bool {
+    let _lock = STATE_LOCK.lock().await;
+    load_state(server_id)
+        .await
+        .is_ok_and(|state| state.pending_update.is_some())
+}
+
+/// Called once the updated server has run long enough to be considered working.
+pub async fn confirm_update(server_id: Uuid) -> anyhow::Result<()> {
+    let _lock = STATE_LOCK.lock().await;
+
+    let mut state = load_state(server_id).await?;
+    if state.pending_update.take().is_none() {
+        return Ok(());
+    }
+    save_state(server_id, &state).await?;
+
+    info!("Update of server {server_id} confirmed");
+
+    Ok(())
+}
+
+/// Restores the manifest (and thereby the extensions) from before the pending update. The world
+/// is restored as well if the Minecraft version changed, since the new version may have upgraded
+/// it to a format the old one cannot read. If the world cannot be restored, the manifest is left
+/// as it is. The server must be stopped.
+pub async fn roll_back(server_dir: &Path, server_id: Uuid, reason: &str) -> anyhow::Result<()> {
+    let _lock = STATE_LOCK.lock().await;
+
+    let mut state = load_state(server_id).await?;
+    let Some(pending_update) = state.pending_update.take() else {
+        return Ok(());
+    };
+
+    let previous = ServerManifest::load(&rollback_dir(server_id)?)
+        .await
+        .context("Failed to load the previous manifest")?;
+    let current = ServerManifest::load(server_dir)
+        .await
+        .context("Failed to load server manifest")?;
+
+    if current.version != previous.version || current.build != previous.build {
+        state.add_failed_update(FailedUpdate::Server {
+            version: current.version.clone(),
+            build: current.build.clone(),
+        });
+    }
+    for extension in &current.extensions {
+        if !previous.extensions.iter().any(|e| {
+            e.provider == extension.provider
+                && e.id == extension.id
+                && e.version_id == extension.version_id
+        }) {
+            state.add_failed_update(FailedUpdate::Extension {
+                provider: extension.provider.clone(),
+                id: extension.id.clone(),
+                version_id: extension.version_id.clone(),
+            });
+        }
+    }
+
+    // the world is restored first, since the previous version must not run on a world the new
+    // one has upgraded
+    let world_restored = if current.version != previous.version
+        && let Some(backup_id) = &pending_update.backup_id
+    {
+        if let Err(e) = backup::restore_backup(server_dir, server_id, backup_id).await {
+            // the failed updates are still recorded so that they are not applied again
+            save_state(server_id, &state).await?;
+            return Err(e).context(
+                "Failed to restore the world from before the update, keeping the updated manifest",
+            );
+        }
+        true
+    } else {
+        false
+    };
+
+    let manifest_restored = previous
+        .save(server_dir)
+        .await
+        .context("Failed to restore the previous manifest");
+
+    save_state(server_id, &state).await?;
+    manifest_restored?;
+
+    warn!(
+        "Rolled back update of server at '{}': {reason}",
+        server_dir.display()
+    );
+
+    send_alert("update_rolled_back", || Alert {
+        severity: Severity::Error,
+        title: "Update rolled back".to_string(),
+        message: format!(
+            "The update of server at `{}` to version {} build {} was rolled back to version {} build {}{} because {reason}.",
+            server_dir.display(),
+            current.version,
+            current.build,
+            previous.version,
+            previous.build,
+            if world_restored {
+                " (including the world)"
+            } else {
+                ""
+            },
+        ),
+    })
+    .await;
+
+    Ok(())
+}
+
+/// Forgets the pending update and the failed updates of a server that was deleted.
+pub async fn delete_state(server_id: Uuid) -> anyhow::Result<()> {
+    let _lock = STATE_LOCK.lock().await;
+    match tokio::fs::remove_dir_all(rollback_dir(server_id)?).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context("Failed to delete update rollback state")
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn is_failed_server_version(server_id: Uuid, version: &str, build: &str) -> bool {
+    let _lock = STATE_LOCK.lock().await;
+    load_state(server_id).await.is_ok_and(|state| {
+        state.failed_updates.contains(&FailedUpdate::Server {
+            version: version.to_string(),
+            build: build.to_string(),
+        })
+    })
+}
+
+pub async fn is_failed_extension_version(
+    server_id: Uuid,
+    provider: &str,
+    id: &str,
+    version_id: &str,
+) -> bool {
+    let _lock = STATE_LOCK.lock().await;
+    load_state(server_id).await.is_ok_and(|state| {
+        state.failed_updates.contains(&FailedUpdate::Extension {
+            provider: provider.to_string(),
+            id: id.to_string(),
+            version_id: version_id.to_string(),
+        })
+    })
+}
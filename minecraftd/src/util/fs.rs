@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Copies a directory recursively. Symlinks, such as the ones to managed extensions, are copied as
+/// symlinks rather than followed. `destination` must not exist.
+pub fn copy_dir_all(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir(destination)
+        .with_context(|| format!("Failed to create directory '{}'", destination.display()))?;
+
+    for entry in std::fs::read_dir(source)
+        .with_context(|| format!("Failed to read directory '{}'", source.display()))?
+    {
+        let entry = entry?;
+        let source_path = entry.path();
+        let destination_path = destination.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            copy_dir_all(&source_path, &destination_path)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(&source_path)?;
+            std::os::unix::fs::symlink(target, &destination_path).with_context(|| {
+                format!("Failed to create symlink '{}'", destination_path.display())
+            })?;
+        } else {
+            std::fs::copy(&source_path, &destination_path)
+                .with_context(|| format!("Failed to copy '{}'", source_path.display()))?;
+        }
+    }
+
+    Ok(())
+}
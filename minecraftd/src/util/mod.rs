@@ -1,6 +1,7 @@
 use std::pin::Pin;
 
 pub mod cached_mojang_piston_api;
+pub mod fs;
 pub mod lazy_init_http_client;
 pub mod observable_value;
 pub mod os_str_ext;